@@ -0,0 +1,355 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+};
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{
+        label::label::{Label, RowGrids},
+        puzzle::Puzzle,
+        sliding_puzzle::SlidingPuzzle,
+    },
+    solver::{heuristic::manhattan::ManhattanDistance, solver::Solver},
+};
+
+use crate::{
+    enums::{ChartValue, LabelType},
+    goal, grid, heuristic,
+    progress::Progress,
+    reconstruct, solvers, util,
+};
+
+/// Part of a solution, from the end of the previous phase to the move after which the pieces of
+/// some label classes are solved for the rest of the solution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Phase {
+    /// The label classes that are solved by the end of this phase. Classes that are solved at
+    /// the same move are in the same phase.
+    pub labels: Vec<u64>,
+    /// The number of single tile moves before the phase starts.
+    pub start: usize,
+    /// The number of single tile moves after which the phase is solved.
+    pub end: usize,
+}
+
+impl Phase {
+    pub fn moves(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// The phases of a solution, and the label classes that it doesn't solve.
+pub struct Analysis {
+    pub phases: Vec<Phase>,
+    pub unsolved: Vec<u64>,
+    pub moves: usize,
+    /// The time in seconds at which each single tile move was made, if known.
+    pub move_times: Option<Vec<f64>>,
+    /// The time of the whole solution in seconds, if known.
+    pub time: Option<f64>,
+}
+
+impl Analysis {
+    /// Adds the time of each move, giving the time and TPS of each phase.
+    pub fn with_move_times(mut self, times: Vec<f64>) -> Self {
+        self.time = times.last().copied();
+        self.move_times = Some(times);
+        self
+    }
+
+    /// Adds the time of the whole solution, giving its TPS but not the TPS of each phase.
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// The time after `moves` single tile moves, if the move times are known.
+    fn time_at(&self, moves: usize) -> Option<f64> {
+        let times = self.move_times.as_ref()?;
+        Some(moves.checked_sub(1).map_or(0.0, |i| times[i]))
+    }
+
+    pub fn phase_time(&self, phase: &Phase) -> Option<f64> {
+        Some(self.time_at(phase.end)? - self.time_at(phase.start)?)
+    }
+}
+
+/// A part of a solution that can be replaced by fewer moves.
+pub struct Waste {
+    /// The number of single tile moves before the part starts.
+    pub start: usize,
+    /// The number of single tile moves after which the part ends.
+    pub end: usize,
+    /// The shorter moves, which are empty if the part returns to a state seen before.
+    pub replacement: Algorithm,
+}
+
+impl Waste {
+    pub fn saving(&self) -> usize {
+        self.end - self.start - self.replacement.len_stm::<usize>()
+    }
+}
+
+impl Display for Waste {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "moves {}-{}: ", self.start + 1, self.end)?;
+        if self.replacement.len_stm::<u64>() == 0 {
+            write!(f, "returns to the state after move {}", self.start)?;
+        } else {
+            write!(f, "can be replaced by {}", self.replacement)?;
+        }
+        write!(f, ", saving {} moves", self.saving())
+    }
+}
+
+/// The label classes whose pieces are all in their solved positions. The gap is ignored.
+fn solved_labels(state: &Puzzle, label: &dyn Label) -> Vec<bool> {
+    let size = state.size();
+    let (width, _) = size.into();
+    let mut solved = vec![true; label.num_labels(size) as usize];
+
+    for (i, piece) in grid::pieces(state).into_iter().enumerate() {
+        if piece == 0 {
+            continue;
+        }
+        let home = goal::solved_pos_xy(piece, size);
+        if home != (i as u64 % width, i as u64 / width) {
+            solved[label.position_label(size, home) as usize] = false;
+        }
+    }
+
+    solved
+}
+
+/// The state after each single tile move of `alg`, starting with `state` itself.
+fn states(state: &Puzzle, alg: &Algorithm) -> Result<Vec<Puzzle>, Box<dyn Error>> {
+    let mut state = state.clone();
+    let mut states = vec![state.clone()];
+    for dir in grid::directions(alg) {
+        if !state.try_move_dir(dir) {
+            return Err("the algorithm can not be applied to the state".into());
+        }
+        states.push(state.clone());
+    }
+
+    Ok(states)
+}
+
+/// The value plotted by `--chart` for each state of `alg` applied to `state`. The optimal
+/// number of moves left needs every state to be solved, which can be slow.
+pub fn chart_values(
+    state: &Puzzle,
+    alg: &Algorithm,
+    value: ChartValue,
+    label: &dyn Label,
+) -> Result<Vec<u64>, Box<dyn Error>> {
+    let states = states(state, alg)?;
+
+    match value {
+        ChartValue::Optimal => {
+            let mut solver = solvers::labelled_solver(LabelType::RowGrids)?;
+            let mut progress = Progress::new("solve", Some(states.len() as u64));
+            states
+                .iter()
+                .map(|s| {
+                    let length = solver(s)?.len_stm::<u64>();
+                    progress.inc();
+                    Ok(length)
+                })
+                .collect()
+        }
+        ChartValue::Md => Ok(states.iter().map(heuristic::manhattan_distance).collect()),
+        ChartValue::Labels => Ok(states
+            .iter()
+            .map(|s| solved_labels(s, label).iter().filter(|&&b| b).count() as u64)
+            .collect()),
+    }
+}
+
+/// The parts of `alg` applied to `state` that return to a state seen before, and the windows of
+/// `window` moves outside of them that can be replaced by a shorter sequence of moves. The
+/// parts don't overlap, and each window is only reported once, so the savings can be added up.
+pub fn wasted(
+    state: &Puzzle,
+    alg: &Algorithm,
+    window: usize,
+) -> Result<Vec<Waste>, Box<dyn Error>> {
+    let states: Vec<String> = states(state, alg)?.iter().map(Puzzle::to_string).collect();
+
+    // Following each state to the last time it appears finds the longest loops first
+    let last: HashMap<&str, usize> = states
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.as_str(), i))
+        .collect();
+    let mut waste = Vec::new();
+    let mut i = 0;
+    while i < states.len() {
+        let end = last[states[i].as_str()];
+        if end > i {
+            waste.push(Waste {
+                start: i,
+                end,
+                replacement: Algorithm::new(),
+            });
+        }
+        i = end + 1;
+    }
+
+    let moves = states.len() - 1;
+    // The windows are applied to the usual solved state, whatever the goal is, so they are
+    // solved to it too
+    let mut solver = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
+    let total = moves.saturating_sub(window) as u64 + 1;
+    let mut progress = Progress::new("wasted", Some(total));
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while window > 0 && start + window <= moves && !util::interrupted() {
+        progress.set(start as u64, Some(total));
+
+        let end = start + window;
+        if waste.iter().any(|w| w.start < end && start < w.end) {
+            start += 1;
+            continue;
+        }
+
+        let slice = alg.try_slice(start..end)?;
+        let Some(size) = slice.min_applicable_size() else {
+            start += 1;
+            continue;
+        };
+        let mut puzzle = Puzzle::new(size);
+        puzzle.apply_alg(&slice);
+
+        let solution = solver.solve(&puzzle)?;
+        if solution.len_stm::<usize>() < window {
+            windows.push(Waste {
+                start,
+                end,
+                replacement: solution.inverse(),
+            });
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+    drop(progress);
+
+    waste.extend(windows);
+    waste.sort_by_key(|w| w.start);
+
+    Ok(waste)
+}
+
+/// Splits `alg` applied to `state` into phases, one for each move after which a label class
+/// becomes solved and stays solved until the end.
+pub fn analyze(
+    state: &Puzzle,
+    alg: &Algorithm,
+    label: &dyn Label,
+) -> Result<Analysis, Box<dyn Error>> {
+    let solved: Vec<Vec<bool>> = states(state, alg)?
+        .iter()
+        .map(|s| solved_labels(s, label))
+        .collect();
+    let moves = solved.len() - 1;
+
+    // The first move after which each class is solved until the end, grouped by that move
+    let mut ends: BTreeMap<usize, Vec<u64>> = BTreeMap::new();
+    let mut unsolved = Vec::new();
+    for class in 0..solved[0].len() {
+        match solved.iter().rposition(|s| !s[class]) {
+            Some(last) if last == moves => unsolved.push(class as u64),
+            Some(last) => ends.entry(last + 1).or_default().push(class as u64),
+            None => ends.entry(0).or_default().push(class as u64),
+        }
+    }
+
+    let mut start = 0;
+    let phases = ends
+        .into_iter()
+        .map(|(end, labels)| {
+            let phase = Phase { labels, start, end };
+            start = end;
+            phase
+        })
+        .collect();
+
+    Ok(Analysis {
+        phases,
+        unsolved,
+        moves,
+        move_times: None,
+        time: None,
+    })
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timed = self.move_times.is_some();
+        let mut header = vec!["phase", "labels", "moves", "end"];
+        if timed {
+            header.extend(["time", "tps"]);
+        }
+
+        let rows: Vec<Vec<String>> = self
+            .phases
+            .iter()
+            .enumerate()
+            .map(|(i, phase)| {
+                let labels: Vec<String> = phase.labels.iter().map(u64::to_string).collect();
+                let mut row = vec![
+                    (i + 1).to_string(),
+                    labels.join(","),
+                    phase.moves().to_string(),
+                    phase.end.to_string(),
+                ];
+                if let Some(time) = self.phase_time(phase) {
+                    let tps = reconstruct::tps(phase.moves() as u64, time);
+                    row.extend([format!("{time:.3}"), format!("{tps:.2}")]);
+                }
+                row
+            })
+            .collect();
+
+        let header: Vec<String> = header.into_iter().map(str::to_string).collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|c| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|r| r[c].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in std::iter::once(&header).chain(&rows) {
+            // The labels are left aligned, and the numbers right aligned
+            let fields: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(c, (field, &w))| match c {
+                    0 | 1 => format!("{field:<w$}"),
+                    _ => format!("{field:>w$}"),
+                })
+                .collect();
+            writeln!(f, "{}", fields.join("  "))?;
+        }
+
+        write!(f, "total: {} moves", self.moves)?;
+        if let Some(time) = self.time {
+            let tps = reconstruct::tps(self.moves as u64, time);
+            write!(f, " in {time:.3}s, {tps:.2} TPS")?;
+        }
+
+        if !self.unsolved.is_empty() {
+            let labels: Vec<String> = self.unsolved.iter().map(u64::to_string).collect();
+            write!(f, "\nnot solved: labels {}", labels.join(","))?;
+        }
+
+        Ok(())
+    }
+}
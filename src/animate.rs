@@ -0,0 +1,177 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, fontdb, TreeParsing, TreeTextToPath},
+};
+use svg::Document;
+
+/// An RGBA image with straight (not premultiplied) alpha.
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Converts SVG documents to pixels, loading the system fonts once.
+pub struct Rasterizer {
+    fontdb: fontdb::Database,
+}
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rasterizer {
+    pub fn new() -> Self {
+        let mut fontdb = fontdb::Database::new();
+        fontdb.load_system_fonts();
+        Self { fontdb }
+    }
+
+    pub fn with_fontdb(fontdb: fontdb::Database) -> Self {
+        Self { fontdb }
+    }
+
+    pub fn rasterize(&self, document: &Document) -> Result<Image, Box<dyn Error>> {
+        let mut tree = usvg::Tree::from_str(&document.to_string(), &usvg::Options::default())?;
+        tree.convert_text(&self.fontdb);
+        let tree = resvg::Tree::from_usvg(&tree);
+
+        let size = tree.size.to_int_size();
+        let mut pixmap =
+            Pixmap::new(size.width(), size.height()).ok_or("the image has zero size")?;
+        tree.render(Transform::default(), &mut pixmap.as_mut());
+
+        let data = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect();
+
+        Ok(Image {
+            width: size.width(),
+            height: size.height(),
+            data,
+        })
+    }
+}
+
+/// Writes an image as an Encapsulated PostScript file, with one point per pixel. Transparent
+/// pixels are drawn over a white background.
+pub fn save_eps<P: AsRef<Path>>(path: P, image: &Image) -> Result<(), Box<dyn Error>> {
+    let (width, height) = (image.width, image.height);
+    let mut file = BufWriter::new(File::create(path)?);
+
+    writeln!(file, "%!PS-Adobe-3.0 EPSF-3.0")?;
+    writeln!(file, "%%BoundingBox: 0 0 {width} {height}")?;
+    writeln!(file, "%%EndComments")?;
+    writeln!(file, "gsave")?;
+    writeln!(file, "{width} {height} scale")?;
+    writeln!(
+        file,
+        "{width} {height} 8 [{width} 0 0 -{height} 0 {height}] currentfile /ASCIIHexDecode \
+        filter false 3 colorimage"
+    )?;
+
+    for row in image.data.chunks(4 * width as usize) {
+        for p in row.chunks(4) {
+            let alpha = p[3] as u32;
+            for &c in &p[..3] {
+                let c = (c as u32 * alpha + 255 * (255 - alpha)) / 255;
+                write!(file, "{c:02x}")?;
+            }
+        }
+        writeln!(file)?;
+    }
+
+    writeln!(file, ">")?;
+    writeln!(file, "grestore")?;
+    writeln!(file, "showpage")?;
+    writeln!(file, "%%EOF")?;
+
+    Ok(())
+}
+
+/// Saves the frames as an animation, with each frame shown for `frame_duration` milliseconds.
+/// The output is an APNG if the path has a `png` or `apng` extension, and a GIF otherwise.
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    frames: &[Document],
+    frame_duration: u16,
+) -> Result<(), Box<dyn Error>> {
+    let rasterizer = Rasterizer::new();
+    let images = frames
+        .iter()
+        .map(|f| rasterizer.rasterize(f))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let is_png = matches!(
+        path.as_ref().extension().and_then(|e| e.to_str()),
+        Some("png" | "apng")
+    );
+
+    let file = BufWriter::new(File::create(path)?);
+    if is_png {
+        save_apng(file, &images, frame_duration)
+    } else {
+        save_gif(file, images, frame_duration)
+    }
+}
+
+fn save_gif(
+    file: BufWriter<File>,
+    images: Vec<Image>,
+    frame_duration: u16,
+) -> Result<(), Box<dyn Error>> {
+    let Some(first) = images.first() else {
+        return Ok(());
+    };
+
+    let (width, height) = (first.width as u16, first.height as u16);
+    let mut encoder = gif::Encoder::new(file, width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for mut image in images {
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut image.data, 10);
+        // GIF frame delays are in hundredths of a second
+        frame.delay = frame_duration / 10;
+        encoder.write_frame(&frame)?;
+    }
+
+    Ok(())
+}
+
+fn save_apng(
+    file: BufWriter<File>,
+    images: &[Image],
+    frame_duration: u16,
+) -> Result<(), Box<dyn Error>> {
+    let Some(first) = images.first() else {
+        return Ok(());
+    };
+
+    let mut encoder = png::Encoder::new(file, first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(images.len() as u32, 0)?;
+    encoder.set_frame_delay(frame_duration, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+    for image in images {
+        writer.write_image_data(&image.data)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::error::Error;
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+use svg::{
+    node::{
+        self,
+        element::{AnimateTransform, Group, Rectangle, Text},
+    },
+    Document,
+};
+
+use crate::{
+    grid,
+    render::{self, RenderOptions},
+};
+
+/// Creates a single SVG image in which the moves of `alg` are animated using SMIL, with each
+/// move taking `move_duration` milliseconds. The animation loops forever.
+pub fn document(
+    state: &Puzzle,
+    alg: &Algorithm,
+    options: &RenderOptions,
+    move_duration: u16,
+) -> Result<Document, Box<dyn Error>> {
+    let size = state.size();
+    let (width, height) = size.into();
+    let colors = render::piece_colors(state, options)?;
+    let tile_size = options.tile_size;
+
+    let mut pieces = grid::pieces(state);
+    let directions = grid::directions(alg);
+    let n = directions.len().max(1) as f32;
+
+    // Keyframes (time, position) for each piece, indexed by piece
+    let mut keyframes: Vec<Vec<(f32, (u64, u64))>> = vec![Vec::new(); pieces.len()];
+    for (i, &p) in pieces.iter().enumerate() {
+        keyframes[p as usize].push((0.0, (i as u64 % width, i as u64 / width)));
+    }
+
+    let mut gap = state.gap_position_xy();
+    for (k, &dir) in directions.iter().enumerate() {
+        let from = grid::moved_piece_pos(gap, dir, size)
+            .ok_or("the algorithm can not be applied to the state")?;
+        let (gap_idx, from_idx) = (
+            (gap.0 + gap.1 * width) as usize,
+            (from.0 + from.1 * width) as usize,
+        );
+        let piece = pieces[from_idx] as usize;
+
+        keyframes[piece].push((k as f32 / n, from));
+        keyframes[piece].push(((k + 1) as f32 / n, gap));
+
+        pieces.swap(gap_idx, from_idx);
+        gap = from;
+    }
+
+    for frames in &mut keyframes {
+        let &(_, last) = frames.last().unwrap();
+        frames.push((1.0, last));
+        frames.dedup_by(|a, b| a.0 == b.0);
+    }
+
+    let duration = format!("{}ms", directions.len() as u64 * move_duration as u64);
+    let font_size = render::font_size(size, options);
+
+    let (background, text_color, border) = render::theme_colors(options);
+
+    let mut document = Document::new()
+        .set("width", width as f32 * tile_size)
+        .set("height", height as f32 * tile_size);
+
+    if let Some(background) = background {
+        document = document.add(
+            Rectangle::new()
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", render::hex(background)),
+        );
+    }
+
+    for (piece, frames) in keyframes.iter().enumerate().skip(1) {
+        let color = colors[piece - 1];
+
+        let (stroke, stroke_width) = match border {
+            Some(stroke) => (render::hex(stroke.color), stroke.width),
+            None => ("black".to_string(), 1.0),
+        };
+
+        let rect = Rectangle::new()
+            .set("width", tile_size)
+            .set("height", tile_size)
+            .set("rx", options.corner_radius.unwrap_or(0.0))
+            .set("fill", render::hex(color))
+            .set("stroke", stroke)
+            .set("stroke-width", stroke_width);
+
+        let text = Text::new()
+            .set("x", tile_size / 2.0)
+            .set("y", tile_size / 2.0)
+            .set("font-size", font_size)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", render::hex(text_color))
+            .add(node::Text::new(piece.to_string()));
+
+        let values: Vec<_> = frames
+            .iter()
+            .map(|(_, (x, y))| format!("{} {}", *x as f32 * tile_size, *y as f32 * tile_size))
+            .collect();
+        let key_times: Vec<_> = frames.iter().map(|(t, _)| t.to_string()).collect();
+
+        let mut group = Group::new()
+            .set("transform", format!("translate({})", values[0]))
+            .add(rect);
+        if !options.no_text {
+            group = group.add(text);
+        }
+
+        if !directions.is_empty() {
+            group = group.add(
+                AnimateTransform::new()
+                    .set("attributeName", "transform")
+                    .set("type", "translate")
+                    .set("values", values.join(";"))
+                    .set("keyTimes", key_times.join(";"))
+                    .set("dur", duration.clone())
+                    .set("repeatCount", "indefinite"),
+            );
+        }
+
+        document = document.add(group);
+    }
+
+    render::style(render::fit(document, options), options)
+}
@@ -0,0 +1,104 @@
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use slidy::puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle};
+
+use crate::{
+    enums::LabelType,
+    goal, grid,
+    heuristic::{HeuristicType, Heuristics},
+    solvers,
+};
+
+/// The operations that can be benchmarked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BenchCommand {
+    Solve,
+    Md,
+    Lc,
+    Wd,
+}
+
+/// Random solvable states, which are the same for every run with the same seed.
+pub fn states(size: Size, samples: u64, seed: u64) -> Result<Vec<Puzzle>, Box<dyn Error>> {
+    let (width, height) = size.into();
+    if width < 2 || height < 2 {
+        return Err("the puzzle must be at least 2x2".into());
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut pieces: Vec<u64> = (0..size.area()).collect();
+
+    (0..samples)
+        .map(|_| {
+            pieces.shuffle(&mut rng);
+            let mut state = grid::from_pieces(&pieces, size)?;
+            if !goal::is_solvable(&state) {
+                // Swapping two pieces other than the gap changes the parity
+                let (a, b) = if state.gap_position() < 2 {
+                    (2, 3)
+                } else {
+                    (0, 1)
+                };
+                state.swap_pieces(a, b);
+            }
+            Ok(state)
+        })
+        .collect()
+}
+
+/// The time taken by each operation, sorted from fastest to slowest.
+pub fn run(command: BenchCommand, states: &[Puzzle]) -> Result<Vec<Duration>, Box<dyn Error>> {
+    let mut solver = solvers::labelled_solver(LabelType::RowGrids)?;
+    let mut heuristics = Heuristics::default();
+
+    let mut times = states
+        .iter()
+        .map(|state| {
+            let start = Instant::now();
+            match command {
+                BenchCommand::Solve => {
+                    solver(state)?;
+                }
+                BenchCommand::Md => {
+                    heuristics.bound(HeuristicType::Md, state);
+                }
+                BenchCommand::Lc => {
+                    heuristics.bound(HeuristicType::Lc, state);
+                }
+                BenchCommand::Wd => {
+                    heuristics
+                        .bound(HeuristicType::Wd, state)
+                        .ok_or("walking distance is not available for this size")?;
+                }
+            }
+            Ok(start.elapsed())
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    times.sort_unstable();
+    Ok(times)
+}
+
+/// The mean, median, 95th percentile and throughput of the sorted times.
+pub fn report(times: &[Duration]) -> String {
+    if times.is_empty() {
+        return "no samples".to_string();
+    }
+
+    let total: Duration = times.iter().sum();
+    let mean = total / times.len() as u32;
+    let percentile = |p: usize| times[(times.len() * p / 100).min(times.len() - 1)];
+    let throughput = times.len() as f64 / total.as_secs_f64();
+
+    format!(
+        "samples: {}\nmean: {mean:?}\nmedian: {:?}\np95: {:?}\nthroughput: {throughput:.1}/s",
+        times.len(),
+        percentile(50),
+        percentile(95)
+    )
+}
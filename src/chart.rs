@@ -0,0 +1,111 @@
+use svg::{
+    node::{
+        self,
+        element::{Line, Polyline, Rectangle, Text},
+    },
+    Document,
+};
+
+const WIDTH: f32 = 720.0;
+const HEIGHT: f32 = 400.0;
+/// The space around the plot for the axis labels.
+const MARGIN: f32 = 50.0;
+/// Roughly how many ticks are drawn on each axis.
+const TICKS: u64 = 10;
+
+/// The gap between ticks for values up to `max`: 1, 2 or 5 times a power of 10.
+fn tick_step(max: u64) -> u64 {
+    let rough = max.div_ceil(TICKS).max(1);
+    let mut power = 1;
+    loop {
+        for step in [1, 2, 5].map(|m| m * power) {
+            if step >= rough {
+                return step;
+            }
+        }
+        power *= 10;
+    }
+}
+
+fn text(x: f32, y: f32, anchor: &str, s: &str) -> Text {
+    Text::new()
+        .set("x", x)
+        .set("y", y)
+        .set("font-size", 12)
+        .set("font-family", "sans-serif")
+        .set("text-anchor", anchor)
+        .set("dominant-baseline", "central")
+        .add(node::Text::new(s))
+}
+
+fn line(from: (f32, f32), to: (f32, f32), color: &str) -> Line {
+    Line::new()
+        .set("x1", from.0)
+        .set("y1", from.1)
+        .set("x2", to.0)
+        .set("y2", to.1)
+        .set("stroke", color)
+        .set("stroke-width", 1)
+}
+
+/// A line chart of `values` against their index, with the y axis labelled `label`. Dashed
+/// vertical lines are drawn at the indices in `marks`, e.g. the ends of the phases of a solve.
+pub fn line_chart(values: &[u64], label: &str, marks: &[usize]) -> Document {
+    let last = values.len().saturating_sub(1).max(1) as u64;
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let (plot_width, plot_height) = (WIDTH - 2.0 * MARGIN, HEIGHT - 2.0 * MARGIN);
+    let x = |i: u64| MARGIN + i as f32 * plot_width / last as f32;
+    let y = |v: u64| MARGIN + plot_height - v as f32 * plot_height / max as f32;
+
+    let mut document = Document::new()
+        .set("width", WIDTH)
+        .set("height", HEIGHT)
+        .add(
+            Rectangle::new()
+                .set("width", WIDTH)
+                .set("height", HEIGHT)
+                .set("fill", "white"),
+        );
+
+    // Grid lines and ticks
+    let step = tick_step(last);
+    for i in (0..=last).step_by(step as usize) {
+        document = document
+            .add(line((x(i), y(0)), (x(i), y(max)), "#e0e0e0"))
+            .add(text(x(i), y(0) + 15.0, "middle", &i.to_string()));
+    }
+    let step = tick_step(max);
+    for v in (0..=max).step_by(step as usize) {
+        document = document
+            .add(line((x(0), y(v)), (x(last), y(v)), "#e0e0e0"))
+            .add(text(x(0) - 8.0, y(v), "end", &v.to_string()));
+    }
+
+    for &mark in marks {
+        document = document.add(
+            line((x(mark as u64), y(0)), (x(mark as u64), y(max)), "#888888")
+                .set("stroke-dasharray", "4 4"),
+        );
+    }
+
+    // Axes
+    document = document
+        .add(line((x(0), y(0)), (x(last), y(0)), "black"))
+        .add(line((x(0), y(0)), (x(0), y(max)), "black"))
+        .add(text(WIDTH / 2.0, HEIGHT - 12.0, "middle", "move"))
+        .add(text(MARGIN, MARGIN / 2.0, "middle", label));
+
+    let points: Vec<String> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| format!("{},{}", x(i as u64), y(v)))
+        .collect();
+
+    document.add(
+        Polyline::new()
+            .set("points", points.join(" "))
+            .set("fill", "none")
+            .set("stroke", "#0072b2")
+            .set("stroke-width", 2),
+    )
+}
@@ -0,0 +1,299 @@
+use std::{error::Error, str::FromStr};
+
+use palette::{rgb::Rgba, Darken, FromColor, Hsl, Lighten, Srgb, WithAlpha};
+use slidy::puzzle::{
+    color_scheme::ColorScheme, coloring::Coloring, label::label::Label, puzzle::Puzzle, size::Size,
+    sliding_puzzle::SlidingPuzzle,
+};
+use thiserror::Error;
+
+use crate::{goal, grid};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ColorError {
+    #[error("InvalidColor: \"{0}\" is not a color of the form #rrggbb or #rrggbbaa")]
+    InvalidColor(String),
+
+    #[error("EmptyPalette: the palette must contain at least one color")]
+    EmptyPalette,
+
+    #[error("InvalidStroke: \"{0}\" is not a stroke of the form <color>:<width>")]
+    InvalidStroke(String),
+}
+
+/// Parses a color written as `#rrggbb` or `#rrggbbaa`.
+pub fn parse_color(s: &str) -> Result<Rgba, ColorError> {
+    let err = || ColorError::InvalidColor(s.to_string());
+
+    let hex = s.trim().strip_prefix('#').ok_or_else(err)?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(err());
+    }
+
+    let channels = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let alpha = channels.get(3).copied().unwrap_or(255);
+    let color: Rgba<Srgb, u8> = Rgba::new(channels[0], channels[1], channels[2], alpha);
+    Ok(color.into_format())
+}
+
+/// An outline drawn around each tile, written as `<color>:<width>`, e.g. `#000000:2`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+    pub color: Rgba,
+    pub width: f32,
+}
+
+impl FromStr for Stroke {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ColorError::InvalidStroke(s.to_string());
+
+        let (color, width) = s.rsplit_once(':').ok_or_else(err)?;
+        Ok(Self {
+            color: parse_color(color)?,
+            width: width.trim().parse().map_err(|_| err())?,
+        })
+    }
+}
+
+/// A coloring that cycles through a user-defined list of colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette(Vec<Rgba>);
+
+impl Palette {
+    pub fn new(colors: Vec<Rgba>) -> Result<Self, ColorError> {
+        if colors.is_empty() {
+            Err(ColorError::EmptyPalette)
+        } else {
+            Ok(Self(colors))
+        }
+    }
+
+    /// Reads a palette from a file containing colors separated by commas or newlines.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::from_str(&contents.replace('\n', ","))?)
+    }
+
+    /// The Okabe-Ito palette, which stays distinguishable with the common forms of color vision
+    /// deficiency. Black is left out, so that the text on the tiles is readable.
+    pub fn okabe_ito() -> Self {
+        Self::from_str("#e69f00,#56b4e9,#009e73,#f0e442,#0072b2,#d55e00,#cc79a7,#999999").unwrap()
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colors = s
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(parse_color)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(colors)
+    }
+}
+
+impl Coloring for Palette {
+    fn color(&self, label: u64, _num_labels: u64) -> Rgba {
+        self.0[label as usize % self.0.len()]
+    }
+}
+
+/// A rainbow where the hues are spread over the full color wheel, so the first and last labels
+/// have similar colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RainbowFull {
+    pub lightness: f32,
+}
+
+impl Coloring for RainbowFull {
+    fn color(&self, label: u64, num_labels: u64) -> Rgba {
+        let hue = 360.0 * label as f32 / num_labels.max(1) as f32;
+        Srgb::from_color(Hsl::new(hue, 1.0, self.lightness)).with_alpha(1.0)
+    }
+}
+
+/// The colors of another coloring, lightened by `amount` (between 0 and 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lightened<C: Coloring> {
+    pub coloring: C,
+    pub amount: f32,
+}
+
+impl<C: Coloring> Coloring for Lightened<C> {
+    fn color(&self, label: u64, num_labels: u64) -> Rgba {
+        let color = self.coloring.color(label, num_labels);
+        let hsl = Hsl::from_color(color.color).lighten(self.amount);
+        Srgb::from_color(hsl).with_alpha(color.alpha)
+    }
+}
+
+/// The colors of another coloring, with every other label darkened so that adjacent labels are
+/// easier to tell apart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Alternating<C: Coloring> {
+    pub coloring: C,
+}
+
+impl<C: Coloring> Coloring for Alternating<C> {
+    fn color(&self, label: u64, num_labels: u64) -> Rgba {
+        let color = self.coloring.color(label, num_labels);
+        if label % 2 == 0 {
+            color
+        } else {
+            let hsl = Hsl::from_color(color.color).darken(0.25);
+            Srgb::from_color(hsl).with_alpha(color.alpha)
+        }
+    }
+}
+
+/// A smooth gradient through the colors of a palette, from the first label to the last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient(Palette);
+
+impl Gradient {
+    pub fn new(palette: Palette) -> Self {
+        Self(palette)
+    }
+
+    /// An approximation of the viridis color map, which is perceptually uniform and readable with
+    /// color vision deficiency.
+    pub fn viridis() -> Self {
+        Self(Palette::from_str("#440154,#3b528b,#21918c,#5ec962,#fde725").unwrap())
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self(Palette::from_str("#ff0000,#0000ff").unwrap())
+    }
+}
+
+impl Coloring for Gradient {
+    fn color(&self, label: u64, num_labels: u64) -> Rgba {
+        let stops = &self.0 .0;
+        if stops.len() == 1 || num_labels <= 1 {
+            return stops[0];
+        }
+
+        let t = label as f32 / (num_labels - 1) as f32 * (stops.len() - 1) as f32;
+        let i = (t.floor() as usize).min(stops.len() - 2);
+        let (a, b, t) = (stops[i], stops[i + 1], t - i as f32);
+
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        Rgba::new(
+            lerp(a.red, b.red),
+            lerp(a.green, b.green),
+            lerp(a.blue, b.blue),
+            lerp(a.alpha, b.alpha),
+        )
+    }
+}
+
+/// Colors each piece by whether it is in its solved position. If a label is given, a piece only
+/// counts as solved once every piece with the same label is in its solved position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolvedStatus {
+    width: u64,
+    solved: Vec<bool>,
+    solved_color: Rgba,
+    unsolved_color: Rgba,
+}
+
+impl SolvedStatus {
+    pub fn new(
+        state: &Puzzle,
+        label: Option<&dyn Label>,
+        solved_color: Rgba,
+        unsolved_color: Rgba,
+    ) -> Self {
+        let size = state.size();
+        let (width, _) = size.into();
+        let pieces = grid::pieces(state);
+        let goal = grid::pieces(&goal::solved(size));
+        let mut solved: Vec<bool> = (0..pieces.len())
+            .map(|i| goal[i] == 0 || pieces[i] == goal[i])
+            .collect();
+
+        if let Some(label) = label {
+            let labels: Vec<u64> = (0..pieces.len() as u64)
+                .map(|i| label.position_label(size, (i % width, i / width)))
+                .collect();
+            let unsolved_labels: Vec<u64> = (0..pieces.len())
+                .filter(|&i| !solved[i])
+                .map(|i| labels[i])
+                .collect();
+            for (i, s) in solved.iter_mut().enumerate() {
+                *s &= !unsolved_labels.contains(&labels[i]);
+            }
+        }
+
+        Self {
+            width,
+            solved,
+            solved_color,
+            unsolved_color,
+        }
+    }
+}
+
+impl ColorScheme for SolvedStatus {
+    fn color(&self, _size: Size, (x, y): (u64, u64)) -> Rgba {
+        if self.solved[(x + y * self.width) as usize] {
+            self.solved_color
+        } else {
+            self.unsolved_color
+        }
+    }
+}
+
+/// Colors each piece by its Manhattan distance from its solved position, using a gradient from
+/// distance 0 to the largest possible distance on the puzzle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistanceHeatmap {
+    width: u64,
+    distances: Vec<u64>,
+    max_distance: u64,
+    gradient: Gradient,
+}
+
+impl DistanceHeatmap {
+    pub fn new(state: &Puzzle, gradient: Gradient) -> Self {
+        let size = state.size();
+        let (width, height) = size.into();
+        let mut distances = vec![0; size.area() as usize];
+        for (i, p) in grid::pieces(state).into_iter().enumerate() {
+            if p != 0 {
+                let pos = (i as u64 % width, i as u64 / width);
+                let (x, y) = goal::solved_pos_xy(p, size);
+                distances[(x + y * width) as usize] = grid::manhattan(pos, (x, y));
+            }
+        }
+
+        Self {
+            width,
+            distances,
+            max_distance: width + height - 2,
+            gradient,
+        }
+    }
+
+    pub fn default_gradient() -> Gradient {
+        Gradient::new(Palette::from_str("#2e7d32,#fdd835,#c62828").unwrap())
+    }
+}
+
+impl ColorScheme for DistanceHeatmap {
+    fn color(&self, _size: Size, (x, y): (u64, u64)) -> Rgba {
+        let distance = self.distances[(x + y * self.width) as usize];
+        self.gradient.color(distance, self.max_distance + 1)
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use slidy::puzzle::puzzle::Puzzle;
+
+use crate::{
+    enums::LabelType,
+    goal,
+    heuristic::{HeuristicType, Heuristics},
+    solvers::{self, SolveFn},
+    watch::Search,
+};
+
+/// A way of finding an optimal solution.
+enum Method {
+    /// The IDA* search of watch-solve, which counts the nodes it expands.
+    Search(HeuristicType),
+    /// The solver of the slidy library, with the Manhattan distance of a label.
+    Label(LabelType, SolveFn),
+}
+
+impl Method {
+    fn name(&self) -> String {
+        match self {
+            Method::Search(heuristic) => format!("ida-{}", heuristic.name()),
+            Method::Label(label, _) => match label.to_possible_value() {
+                Some(value) => format!("label-{}", value.get_name()),
+                None => format!("label-{label:?}"),
+            },
+        }
+    }
+}
+
+/// The result of solving one state with one method.
+pub struct Run {
+    pub method: String,
+    /// The length of the solution, or why there isn't one.
+    pub length: Result<u64, String>,
+    /// The number of nodes expanded, if the method counts them.
+    pub nodes: Option<u64>,
+    pub time: Duration,
+}
+
+/// The runs of every method on one state.
+pub struct Comparison {
+    pub runs: Vec<Run>,
+}
+
+impl Comparison {
+    /// Whether two methods found solutions of different lengths. They are all optimal, so this
+    /// means one of them is wrong.
+    pub fn disagrees(&self) -> bool {
+        let mut lengths = self.runs.iter().filter_map(|r| r.length.as_ref().ok());
+        let first = lengths.next();
+        lengths.any(|l| Some(l) != first)
+    }
+}
+
+/// The methods being compared. The label solvers and the walking distance tables are built once
+/// and reused for every state.
+pub struct Solvers {
+    methods: Vec<Method>,
+    heuristics: Heuristics,
+    /// The most nodes an IDA* search expands before giving up.
+    max_nodes: u64,
+}
+
+impl Solvers {
+    pub fn new(
+        heuristics: &[HeuristicType],
+        labels: &[LabelType],
+        max_nodes: u64,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut methods: Vec<Method> = heuristics.iter().map(|&h| Method::Search(h)).collect();
+        for &label in labels {
+            methods.push(Method::Label(label, solvers::labelled_solver(label)?));
+        }
+
+        Ok(Self {
+            methods,
+            heuristics: Heuristics::default(),
+            max_nodes,
+        })
+    }
+
+    pub fn compare(&mut self, state: &Puzzle) -> Result<Comparison, Box<dyn Error>> {
+        if !goal::is_solvable(state) {
+            return Err("the state is unsolvable".into());
+        }
+
+        let mut runs = Vec::new();
+        for method in &mut self.methods {
+            let start = Instant::now();
+            let (length, nodes) = match method {
+                Method::Search(heuristic) => {
+                    match Search::new(state.clone(), *heuristic, &mut self.heuristics) {
+                        Ok(mut search) => {
+                            let length = search
+                                .run(self.max_nodes)
+                                .map(|s| s.len() as u64)
+                                .ok_or_else(|| {
+                                    format!("no solution within {} nodes", search.nodes)
+                                });
+                            (length, Some(search.nodes))
+                        }
+                        Err(e) => (Err(e.to_string()), None),
+                    }
+                }
+                Method::Label(_, solver) => (
+                    solver(state)
+                        .map(|a| a.len_stm::<u64>())
+                        .map_err(|e| e.to_string()),
+                    None,
+                ),
+            };
+
+            runs.push(Run {
+                method: method.name(),
+                length,
+                nodes,
+                time: start.elapsed(),
+            });
+        }
+
+        Ok(Comparison { runs })
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header = ["method", "length", "nodes", "time"]
+            .map(str::to_string)
+            .to_vec();
+        let rows: Vec<Vec<String>> = self
+            .runs
+            .iter()
+            .map(|run| {
+                vec![
+                    run.method.clone(),
+                    run.length.as_ref().map_or("-".to_string(), u64::to_string),
+                    run.nodes.map_or("-".to_string(), |n| n.to_string()),
+                    format!("{:.3}s", run.time.as_secs_f64()),
+                ]
+            })
+            .collect();
+
+        let widths: Vec<usize> = (0..header.len())
+            .map(|c| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|r| r[c].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in std::iter::once(&header).chain(&rows) {
+            // The method is left aligned, and the numbers right aligned
+            let fields: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(c, (field, &w))| match c {
+                    0 => format!("{field:<w$}"),
+                    _ => format!("{field:>w$}"),
+                })
+                .collect();
+            writeln!(f, "{}", fields.join("  ").trim_end())?;
+        }
+
+        for run in &self.runs {
+            if let Err(e) = &run.length {
+                writeln!(f, "{}: {e}", run.method)?;
+            }
+        }
+
+        if self.disagrees() {
+            writeln!(
+                f,
+                "warning: the methods found solutions of different lengths"
+            )?;
+        }
+
+        Ok(())
+    }
+}
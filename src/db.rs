@@ -0,0 +1,158 @@
+use std::{error::Error, str::FromStr};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use slidy::{algorithm::algorithm::Algorithm, puzzle::puzzle::Puzzle};
+
+use crate::solvers::SolveFn;
+
+/// The method recorded for solutions found by `solve --db`. Only solutions with this method are
+/// used by `solve --db`, since solutions added by hand may not be optimal.
+pub const OPTIMAL: &str = "optimal";
+
+/// One solution in a database.
+pub struct Entry {
+    pub state: Puzzle,
+    pub solution: Algorithm,
+    /// How the solution was found, e.g. [`OPTIMAL`] or the name of a method.
+    pub method: String,
+    pub tags: Vec<String>,
+}
+
+impl Entry {
+    /// The state, the solution, its length, the method and the tags, separated by commas.
+    pub fn record(&self) -> [String; 5] {
+        [
+            self.state.to_string(),
+            self.solution.to_string(),
+            self.solution.len_stm::<u64>().to_string(),
+            self.method.clone(),
+            self.tags.join(","),
+        ]
+    }
+}
+
+/// Which entries to return from [`Database::query`]. Every filter that is given must match.
+#[derive(Default)]
+pub struct Query<'a> {
+    pub state: Option<&'a Puzzle>,
+    pub method: Option<&'a str>,
+    pub tag: Option<&'a str>,
+}
+
+/// A database of solutions, stored in an SQLite file. Each state can have any number of
+/// solutions, and adding a solution that is already stored replaces its method and tags.
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating it if it doesn't exist.
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::open(path).map_err(|e| format!("{path}: {e}"))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS solutions (
+                state TEXT NOT NULL,
+                solution TEXT NOT NULL,
+                length INTEGER NOT NULL,
+                method TEXT NOT NULL,
+                tags TEXT NOT NULL,
+                PRIMARY KEY (state, solution)
+            );
+            CREATE INDEX IF NOT EXISTS solutions_method ON solutions (method);",
+        )?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn add(&self, entry: &Entry) -> Result<(), Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO solutions (state, solution, length, method, tags)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.state.to_string(),
+                entry.solution.to_string(),
+                entry.solution.len_stm::<u64>(),
+                entry.method,
+                entry.tags.join(","),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// The shortest solution of `state` with the [`OPTIMAL`] method, if there is one.
+    pub fn optimal(&self, state: &Puzzle) -> Result<Option<Algorithm>, Box<dyn Error>> {
+        let solution: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT solution FROM solutions WHERE state = ?1 AND method = ?2
+                ORDER BY length LIMIT 1",
+                params![state.to_string(), OPTIMAL],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(solution.map(|s| Algorithm::from_str(&s)).transpose()?)
+    }
+
+    /// The entries matching `query`, ordered by state and then by length.
+    pub fn query(&self, query: &Query) -> Result<Vec<Entry>, Box<dyn Error>> {
+        // A filter that isn't given is NULL, which matches everything
+        let mut statement = self.connection.prepare(
+            "SELECT state, solution, method, tags FROM solutions
+            WHERE (?1 IS NULL OR state = ?1)
+                AND (?2 IS NULL OR method = ?2)
+                AND (?3 IS NULL OR ',' || tags || ',' LIKE '%,' || ?3 || ',%')
+            ORDER BY state, length",
+        )?;
+        let rows = statement.query_map(
+            params![query.state.map(Puzzle::to_string), query.method, query.tag],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )?;
+
+        let entries = rows
+            .map(|row| -> Result<Entry, Box<dyn Error>> {
+                let (state, solution, method, tags) = row?;
+                Ok(Entry {
+                    state: Puzzle::from_str(&state)?,
+                    solution: Algorithm::from_str(&solution)?,
+                    method,
+                    tags: tags
+                        .split(',')
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                })
+            })
+            .collect();
+
+        entries
+    }
+}
+
+/// Wraps a solver so that states with an [`OPTIMAL`] solution in `db` are answered from it, and
+/// the solutions of the other states are added to it.
+pub fn cached(mut solver: SolveFn, db: Database) -> SolveFn {
+    Box::new(move |state: &Puzzle| {
+        if let Some(solution) = db.optimal(state)? {
+            return Ok(solution);
+        }
+
+        let solution = solver(state)?;
+        db.add(&Entry {
+            state: state.clone(),
+            solution: solution.clone(),
+            method: OPTIMAL.to_string(),
+            tags: Vec::new(),
+        })?;
+
+        Ok(solution)
+    })
+}
@@ -0,0 +1,233 @@
+use std::{error::Error, path::Path, time::Duration};
+
+use clap::ValueEnum;
+use slidy::puzzle::{
+    label::{
+        label::{
+            Checkerboard, Diagonals, Fringe, Label, RowGrids, Rows, SplitFringe, SplitSquareFringe,
+            SquareFringe,
+        },
+        scaled::Scaled,
+    },
+    size::Size,
+};
+
+use crate::util::json_string;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+pub enum LabelType {
+    RowGrids,
+    Rows,
+    Fringe,
+    SquareFringe,
+    SplitFringe,
+    SplitSquareFringe,
+    Diagonals,
+    Checkerboard,
+    Grids,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColoringType {
+    None,
+    Black,
+    Rainbow,
+    RainbowBright,
+    RainbowFull,
+    RainbowBrightFull,
+    Alternating,
+    Gradient,
+    Custom,
+    OkabeIto,
+    Viridis,
+    SolvedStatus,
+    Heatmap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Svg,
+    Pdf,
+    Eps,
+}
+
+impl OutputFormat {
+    /// The format matching the extension of `path`, defaulting to SVG.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("pdf") => OutputFormat::Pdf,
+            Some("eps") => OutputFormat::Eps,
+            _ => OutputFormat::Svg,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Svg => "svg",
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Eps => "eps",
+        }
+    }
+}
+
+/// Formats for printing one record per result. `Csv` and `Tsv` start with a header row, and
+/// `Ndjson` prints one JSON object per line, including the time taken to compute it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+    Ndjson,
+}
+
+impl TableFormat {
+    /// The header row, if the format has one.
+    pub fn header(self, columns: &[&str]) -> Option<String> {
+        match self {
+            TableFormat::Csv | TableFormat::Tsv => Some(self.row(columns, columns, Duration::ZERO)),
+            TableFormat::Ndjson => None,
+        }
+    }
+
+    /// Formats one record, quoting or escaping fields where needed.
+    pub fn row<S: AsRef<str>>(self, columns: &[&str], fields: &[S], elapsed: Duration) -> String {
+        let fields = fields.iter().map(AsRef::as_ref);
+
+        match self {
+            TableFormat::Csv => fields
+                .map(|f| {
+                    if f.contains([',', '"', '\n']) {
+                        format!("\"{}\"", f.replace('"', "\"\""))
+                    } else {
+                        f.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            TableFormat::Tsv => fields
+                .map(|f| f.replace(['\t', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t"),
+            TableFormat::Ndjson => {
+                let entries = columns
+                    .iter()
+                    .zip(fields)
+                    .map(|(c, f)| {
+                        // Numbers are written as JSON numbers, everything else as strings. A
+                        // leading + is kept, as in a +2 penalty
+                        let value = match f.parse::<u64>() {
+                            Ok(n) if !f.starts_with('+') => n.to_string(),
+                            _ => json_string(f),
+                        };
+                        format!("{}:{value}", json_string(c))
+                    })
+                    .chain(std::iter::once(format!(
+                        "\"time_ms\":{:.3}",
+                        elapsed.as_secs_f64() * 1000.0
+                    )));
+                format!("{{{}}}", entries.collect::<Vec<_>>().join(","))
+            }
+        }
+    }
+}
+
+/// What `analyze --chart` plots against the move number: the optimal number of moves left,
+/// the Manhattan distance, or the number of solved label classes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ChartValue {
+    Optimal,
+    Md,
+    Labels,
+}
+
+impl ChartValue {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChartValue::Optimal => "optimal moves left",
+            ChartValue::Md => "Manhattan distance",
+            ChartValue::Labels => "solved label classes",
+        }
+    }
+}
+
+/// Other programs whose solves can be imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SimFormat {
+    Slidysim,
+}
+
+/// The formats that solves can be exported to. `Macro` converts algorithms to keypresses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Slidysim,
+    Macro,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+/// What to do when one input of a batch fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ErrorPolicy {
+    Continue,
+    Stop,
+}
+
+/// Whether the direction of a move is the direction the tile moves in, or the direction the
+/// blank moves in (the opposite direction).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Convention {
+    #[default]
+    Tile,
+    Blank,
+}
+
+/// The corner the blank is in when the puzzle is solved. The other pieces are in order in the
+/// rest of the positions, so e.g. with the blank in the top left, 1 is next to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum BlankHome {
+    #[default]
+    BottomRight,
+    BottomLeft,
+    TopRight,
+    TopLeft,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StateFormatter {
+    Inline,
+    Grid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    Stm,
+    Mtm,
+}
+
+pub fn grid_size(size: Size) -> (u64, u64) {
+    let (width, height) = size.into();
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+pub fn boxed_label(label_type: LabelType, size: Size) -> Result<Box<dyn Label>, Box<dyn Error>> {
+    Ok(match label_type {
+        LabelType::RowGrids => Box::new(RowGrids),
+        LabelType::Rows => Box::new(Rows),
+        LabelType::Fringe => Box::new(Fringe),
+        LabelType::SquareFringe => Box::new(SquareFringe),
+        LabelType::SplitFringe => Box::new(SplitFringe),
+        LabelType::SplitSquareFringe => Box::new(SplitSquareFringe),
+        LabelType::Diagonals => Box::new(Diagonals),
+        LabelType::Checkerboard => Box::new(Checkerboard),
+        LabelType::Grids => Box::new(Scaled::new(RowGrids, grid_size(size))?),
+    })
+}
@@ -0,0 +1,160 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle},
+};
+
+use crate::{enums::BlankHome, grid};
+
+static HOME: OnceLock<BlankHome> = OnceLock::new();
+
+/// Sets the corner the blank is in when the puzzle is solved, from the global `--blank-home`
+/// option.
+pub fn set_home(home: BlankHome) {
+    let _ = HOME.set(home);
+}
+
+pub fn home() -> BlankHome {
+    HOME.get().copied().unwrap_or_default()
+}
+
+/// Whether the goal is the usual one mirrored left to right, and top to bottom.
+fn mirrors() -> (bool, bool) {
+    match home() {
+        BlankHome::BottomRight => (false, false),
+        BlankHome::BottomLeft => (true, false),
+        BlankHome::TopRight => (false, true),
+        BlankHome::TopLeft => (true, true),
+    }
+}
+
+/// Mirrors `pos` so that the blank's home moves to the bottom right corner, and back again.
+fn reflect((x, y): (u64, u64), size: Size) -> (u64, u64) {
+    let (width, height) = size.into();
+    let (mirror_x, mirror_y) = mirrors();
+    (
+        if mirror_x { width - 1 - x } else { x },
+        if mirror_y { height - 1 - y } else { y },
+    )
+}
+
+/// The position of `piece` in the solved state, where `0` is the gap. The gap is at its home,
+/// and the other pieces are in order in the rest of the positions, in row-major order.
+pub fn solved_pos_xy(piece: u64, size: Size) -> (u64, u64) {
+    let (width, height) = size.into();
+    let (x, y) = reflect((width - 1, height - 1), size);
+    let home = x + y * width;
+    let idx = match piece {
+        0 => home,
+        p if p <= home => p - 1,
+        p => p,
+    };
+    (idx % width, idx / width)
+}
+
+/// The solved state of a puzzle of this size.
+pub fn solved(size: Size) -> Puzzle {
+    if home() == BlankHome::BottomRight {
+        return Puzzle::new(size);
+    }
+
+    let (width, _) = size.into();
+    let mut pieces = vec![0; size.area() as usize];
+    for p in 1..size.area() {
+        let (x, y) = solved_pos_xy(p, size);
+        pieces[(x + y * width) as usize] = p;
+    }
+    grid::from_pieces(&pieces, size).unwrap()
+}
+
+/// Whether `state` is the solved state of its size.
+pub fn is_solved(state: &Puzzle) -> bool {
+    match home() {
+        BlankHome::BottomRight => state.is_solved(),
+        _ => grid::pieces(state) == grid::pieces(&solved(state.size())),
+    }
+}
+
+/// Whether `state` can be solved. The goals with the blank in different corners are not always
+/// reachable from each other, so this depends on the goal.
+pub fn is_solvable(state: &Puzzle) -> bool {
+    to_standard(state).is_solvable()
+}
+
+/// The state mirrored so that the blank's home is in the bottom right, with the pieces renamed so
+/// that the solved state becomes the usual one. Its solutions are the solutions of `state` under
+/// [`reflect_alg`], so the solvers and heuristics, which all assume the usual solved state, can
+/// be used on it.
+pub fn to_standard(state: &Puzzle) -> Puzzle {
+    if home() == BlankHome::BottomRight {
+        return state.clone();
+    }
+
+    let size = state.size();
+    let (width, _) = size.into();
+    let pieces = grid::pieces(state);
+    let standard: Vec<u64> = (0..size.area())
+        .map(|i| {
+            let (x, y) = reflect((i % width, i / width), size);
+            match pieces[(x + y * width) as usize] {
+                0 => 0,
+                p => {
+                    let (px, py) = reflect(solved_pos_xy(p, size), size);
+                    px + py * width + 1
+                }
+            }
+        })
+        .collect();
+
+    grid::from_pieces(&standard, size).unwrap()
+}
+
+/// The inverse of [`to_standard`], e.g. to turn a random state that is solvable to the usual
+/// solved state into one that is solvable to the goal.
+pub fn from_standard(state: &Puzzle) -> Puzzle {
+    if home() == BlankHome::BottomRight {
+        return state.clone();
+    }
+
+    let size = state.size();
+    let (width, _) = size.into();
+    let pieces = grid::pieces(state);
+    let goal = grid::pieces(&solved(size));
+    let original: Vec<u64> = (0..size.area())
+        .map(|i| {
+            let (x, y) = reflect((i % width, i / width), size);
+            match pieces[(x + y * width) as usize] {
+                0 => 0,
+                p => {
+                    let (px, py) = reflect(((p - 1) % width, (p - 1) / width), size);
+                    goal[(px + py * width) as usize]
+                }
+            }
+        })
+        .collect();
+
+    grid::from_pieces(&original, size).unwrap()
+}
+
+/// Mirrors the moves of `alg` in the same way as [`to_standard`], which maps solutions of the
+/// mirrored state back to solutions of the original one, and the other way around.
+pub fn reflect_alg(alg: &Algorithm) -> Algorithm {
+    let (mirror_x, mirror_y) = mirrors();
+    if !mirror_x && !mirror_y {
+        return alg.clone();
+    }
+
+    let s: String = alg
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            'L' if mirror_x => 'R',
+            'R' if mirror_x => 'L',
+            'U' if mirror_y => 'D',
+            'D' if mirror_y => 'U',
+            c => c,
+        })
+        .collect();
+    Algorithm::from_str(&s).unwrap()
+}
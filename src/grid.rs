@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+use slidy::{
+    algorithm::{algorithm::Algorithm, direction::Direction},
+    puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle},
+};
+
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Left,
+    Direction::Down,
+    Direction::Right,
+];
+
+/// The position of `piece` in the solved state, where `0` is the gap.
+pub fn solved_pos_xy(piece: u64, size: Size) -> (u64, u64) {
+    let (width, _) = size.into();
+    let idx = if piece == 0 {
+        size.area() - 1
+    } else {
+        piece - 1
+    };
+    (idx % width, idx / width)
+}
+
+/// The position of the piece that moves into the gap when the gap is at `gap` and a move in
+/// direction `dir` is made, or `None` if the move is not possible.
+pub fn moved_piece_pos(gap: (u64, u64), dir: Direction, size: Size) -> Option<(u64, u64)> {
+    let (width, height) = size.into();
+    let (x, y) = gap;
+    match dir {
+        Direction::Up => (y + 1 < height).then(|| (x, y + 1)),
+        Direction::Left => (x + 1 < width).then(|| (x + 1, y)),
+        Direction::Down => y.checked_sub(1).map(|y| (x, y)),
+        Direction::Right => x.checked_sub(1).map(|x| (x, y)),
+    }
+}
+
+pub fn manhattan(a: (u64, u64), b: (u64, u64)) -> u64 {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// The pieces of `state` in row-major order.
+pub fn pieces(state: &Puzzle) -> Vec<u64> {
+    let (width, height) = state.size().into();
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| state.piece_at_xy((x, y))))
+        .collect()
+}
+
+/// Builds a puzzle from pieces in row-major order.
+pub fn from_pieces(pieces: &[u64], size: Size) -> Result<Puzzle, <Puzzle as FromStr>::Err> {
+    Puzzle::from_str(&format_rows(pieces, size, |p| p.to_string()))
+}
+
+/// Formats a row-major list of pieces using the same layout as `Puzzle`'s `Display` impl.
+pub fn format_rows<T>(pieces: &[T], size: Size, f: impl Fn(&T) -> String) -> String {
+    let (width, _) = size.into();
+    let mut s = String::new();
+    for (i, p) in pieces.iter().enumerate() {
+        if i > 0 {
+            s.push(if i as u64 % width == 0 { '/' } else { ' ' });
+        }
+        s.push_str(&f(p));
+    }
+    s
+}
+
+/// The single tile moves of `alg`, in order.
+pub fn directions(alg: &Algorithm) -> Vec<Direction> {
+    alg.multi_tile_moves()
+        .flat_map(|m| std::iter::repeat(m.direction).take(m.amount as usize))
+        .collect()
+}
+
+pub fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::Up => Direction::Down,
+        Direction::Left => Direction::Right,
+        Direction::Down => Direction::Up,
+        Direction::Right => Direction::Left,
+    }
+}
+
+pub fn direction_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Left => 'L',
+        Direction::Down => 'D',
+        Direction::Right => 'R',
+    }
+}
+
+pub fn parse_direction(c: char) -> Option<Direction> {
+    match c {
+        'U' => Some(Direction::Up),
+        'L' => Some(Direction::Left),
+        'D' => Some(Direction::Down),
+        'R' => Some(Direction::Right),
+        _ => None,
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, VecDeque};
+
+use clap::ValueEnum;
+use slidy::puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle};
+
+use crate::{goal, grid, memory};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum HeuristicType {
+    Md,
+    Lc,
+    Wd,
+}
+
+impl HeuristicType {
+    pub fn name(self) -> &'static str {
+        match self {
+            HeuristicType::Md => "md",
+            HeuristicType::Lc => "lc",
+            HeuristicType::Wd => "wd",
+        }
+    }
+}
+
+/// The largest width or height for which walking distance tables are built.
+pub const MAX_WALKING_DISTANCE_SIZE: u64 = 5;
+
+/// Computes heuristic values, caching the walking distance tables of every size seen so far.
+#[derive(Default)]
+pub struct Heuristics {
+    /// `None` for sizes whose tables don't fit within the memory limit.
+    walking_distance: HashMap<(u64, u64), Option<(WalkingDistanceTable, WalkingDistanceTable)>>,
+    /// Whether a warning has been printed about falling back to linear conflicts.
+    warned: bool,
+}
+
+impl Heuristics {
+    /// Returns `None` if the heuristic is not available for puzzles of this size. If the walking
+    /// distance tables don't fit within the memory limit, linear conflicts are used instead.
+    pub fn bound(&mut self, heuristic: HeuristicType, state: &Puzzle) -> Option<u64> {
+        self.standard_bound(heuristic, &goal::to_standard(state))
+    }
+
+    /// [`Heuristics::bound`] for a state whose goal is the usual solved state.
+    fn standard_bound(&mut self, heuristic: HeuristicType, state: &Puzzle) -> Option<u64> {
+        let md = standard_manhattan_distance(state);
+        match heuristic {
+            HeuristicType::Md => Some(md),
+            HeuristicType::Lc => Some(md + 2 * linear_conflicts(state)),
+            HeuristicType::Wd => self.walking_distance(state),
+        }
+    }
+
+    fn walking_distance(&mut self, state: &Puzzle) -> Option<u64> {
+        let size = state.size();
+        let (width, height) = size.into();
+        if width.max(height) > MAX_WALKING_DISTANCE_SIZE {
+            return None;
+        }
+
+        let tables = self
+            .walking_distance
+            .entry((width, height))
+            .or_insert_with(|| {
+                let budget = memory::limit();
+                let rows = WalkingDistanceTable::new(height, width, budget / 2)?;
+                let columns = WalkingDistanceTable::new(width, height, budget / 2)?;
+                Some((rows, columns))
+            });
+
+        let Some((rows, columns)) = tables else {
+            if !self.warned {
+                eprintln!(
+                    "warning: the walking distance tables for {width}x{height} don't fit within \
+                    the memory limit, using linear conflicts instead"
+                );
+                self.warned = true;
+            }
+            return self.standard_bound(HeuristicType::Lc, state);
+        };
+
+        let pieces = grid::pieces(state);
+        let mut row_counts = vec![0; (height * height) as usize];
+        let mut column_counts = vec![0; (width * width) as usize];
+        let (mut gap_row, mut gap_column) = (0, 0);
+        for (i, &p) in pieces.iter().enumerate() {
+            let (x, y) = (i as u64 % width, i as u64 / width);
+            if p == 0 {
+                (gap_column, gap_row) = (x, y);
+            } else {
+                let (gx, gy) = grid::solved_pos_xy(p, size);
+                row_counts[(y * height + gy) as usize] += 1;
+                column_counts[(x * width + gx) as usize] += 1;
+            }
+        }
+
+        row_counts.push(gap_row as u8);
+        column_counts.push(gap_column as u8);
+
+        Some(rows.distance(&row_counts) + columns.distance(&column_counts))
+    }
+}
+
+pub fn manhattan_distance(state: &Puzzle) -> u64 {
+    standard_manhattan_distance(&goal::to_standard(state))
+}
+
+fn standard_manhattan_distance(state: &Puzzle) -> u64 {
+    let size = state.size();
+    let (width, _) = size.into();
+    grid::pieces(state)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, p)| p != 0)
+        .map(|(i, p)| {
+            grid::manhattan(
+                (i as u64 % width, i as u64 / width),
+                grid::solved_pos_xy(p, size),
+            )
+        })
+        .sum()
+}
+
+/// The number of pieces that must be removed from each row and column so that the remaining
+/// pieces which belong in that row or column are in the correct relative order. Each such piece
+/// costs at least two moves on top of its Manhattan distance. The goal is the usual solved state.
+fn linear_conflicts(state: &Puzzle) -> u64 {
+    let size = state.size();
+    let (width, height) = size.into();
+    let pieces = grid::pieces(state);
+    let goal = |x: u64, y: u64| {
+        let p = pieces[(x + y * width) as usize];
+        (p != 0).then(|| grid::solved_pos_xy(p, size))
+    };
+
+    let rows = (0..height).map(|y| {
+        let line: Vec<u64> = (0..width)
+            .filter_map(|x| goal(x, y).filter(|g| g.1 == y).map(|g| g.0))
+            .collect();
+        line.len() as u64 - longest_increasing_subsequence(&line)
+    });
+
+    let columns = (0..width).map(|x| {
+        let line: Vec<u64> = (0..height)
+            .filter_map(|y| goal(x, y).filter(|g| g.0 == x).map(|g| g.1))
+            .collect();
+        line.len() as u64 - longest_increasing_subsequence(&line)
+    });
+
+    rows.chain(columns).sum()
+}
+
+fn longest_increasing_subsequence(values: &[u64]) -> u64 {
+    let mut tails: Vec<u64> = Vec::new();
+    for &v in values {
+        match tails.binary_search(&v) {
+            Ok(_) => {}
+            Err(i) if i == tails.len() => tails.push(v),
+            Err(i) => tails[i] = v,
+        }
+    }
+    tails.len() as u64
+}
+
+/// Distances of every reachable walking distance state from the solved state, for one axis of a
+/// puzzle with `lines` rows (or columns) of `len` pieces each.
+///
+/// A state is the `lines * lines` matrix counting, for each line, how many pieces belong in each
+/// goal line, followed by the line containing the gap.
+pub struct WalkingDistanceTable {
+    distances: HashMap<Vec<u8>, u64>,
+}
+
+impl WalkingDistanceTable {
+    /// Returns `None` if the table would use more than `max_bytes` bytes.
+    pub fn new(lines: u64, len: u64, max_bytes: u64) -> Option<Self> {
+        let n = lines as usize;
+        // Each state is stored in the map and possibly the queue, with the overhead of a `Vec`
+        let state_size = 2 * (n * n + 1 + std::mem::size_of::<Vec<u8>>()) + 16;
+        let max_states = max_bytes / state_size as u64;
+
+        let mut goal = vec![0u8; n * n + 1];
+        for i in 0..n {
+            goal[i * n + i] = len as u8;
+        }
+        goal[n * n - 1] -= 1;
+        goal[n * n] = (n - 1) as u8;
+
+        let mut distances = HashMap::from([(goal.clone(), 0)]);
+        let mut queue = VecDeque::from([goal]);
+
+        while let Some(state) = queue.pop_front() {
+            let d = distances[&state];
+            let gap = state[n * n] as usize;
+
+            let neighbours = [gap.checked_sub(1), Some(gap + 1).filter(|&l| l < n)];
+            for line in neighbours.into_iter().flatten() {
+                for g in 0..n {
+                    if state[line * n + g] == 0 {
+                        continue;
+                    }
+
+                    let mut next = state.clone();
+                    next[line * n + g] -= 1;
+                    next[gap * n + g] += 1;
+                    next[n * n] = line as u8;
+
+                    if !distances.contains_key(&next) {
+                        if distances.len() as u64 >= max_states {
+                            return None;
+                        }
+                        distances.insert(next.clone(), d + 1);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        Some(Self { distances })
+    }
+
+    pub fn distance(&self, state: &[u8]) -> u64 {
+        self.distances[state]
+    }
+}
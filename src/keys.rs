@@ -0,0 +1,270 @@
+use std::{error::Error, fs, path::PathBuf, str::FromStr};
+
+use slidy::algorithm::direction::Direction;
+use thiserror::Error;
+
+use crate::{enums::Convention, grid, notation, terminal::Key};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum KeysError {
+    #[error("InvalidLine: line {0}: expected \"action = keys\"")]
+    InvalidLine(usize),
+
+    #[error("UnknownAction: line {0}: \"{1}\" is not an action")]
+    UnknownAction(usize, String),
+
+    #[error("UnknownKey: line {0}: \"{1}\" is not a key")]
+    UnknownKey(usize, String),
+
+    #[error("UnknownPreset: line {0}: \"{1}\" is not a preset, expected arrows, wasd or vim")]
+    UnknownPreset(usize, String),
+}
+
+/// Something that can be bound to keys in the interactive modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Slide a tile, in the tile convention.
+    Move(Direction),
+    Undo,
+    /// Make the last undone move again.
+    Redo,
+    /// Go back to the start of the current scramble.
+    Reset,
+    /// Switch to a new scramble.
+    Scramble,
+    Quit,
+}
+
+impl Action {
+    fn parse(s: &str) -> Option<Self> {
+        let action = match s {
+            "up" => Action::Move(Direction::Up),
+            "left" => Action::Move(Direction::Left),
+            "down" => Action::Move(Direction::Down),
+            "right" => Action::Move(Direction::Right),
+            "undo" => Action::Undo,
+            "redo" => Action::Redo,
+            "reset" => Action::Reset,
+            "scramble" => Action::Scramble,
+            "quit" => Action::Quit,
+            _ => return None,
+        };
+
+        Some(action)
+    }
+}
+
+/// Parses a key name: a single character, or one of `Up`, `Down`, `Left`, `Right`, `Home`,
+/// `End`, `Enter`, `Esc`, `Backspace` and `Space`.
+fn parse_key(s: &str) -> Option<Key> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+
+    let key = match s.to_lowercase().as_str() {
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "enter" | "return" => Key::Enter,
+        "esc" | "escape" => Key::Escape,
+        "backspace" => Key::Backspace,
+        "space" => Key::Char(' '),
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn key_name(key: Key) -> String {
+    match key {
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::Enter => "enter".to_string(),
+        Key::Escape => "esc".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char(c) => c.to_string(),
+    }
+}
+
+/// The movement keys of a preset, in the order up, left, down, right.
+fn preset(name: &str) -> Option<[Key; 4]> {
+    let keys = match name {
+        "arrows" => [Key::Up, Key::Left, Key::Down, Key::Right],
+        "wasd" => [
+            Key::Char('w'),
+            Key::Char('a'),
+            Key::Char('s'),
+            Key::Char('d'),
+        ],
+        "vim" => [
+            Key::Char('k'),
+            Key::Char('h'),
+            Key::Char('j'),
+            Key::Char('l'),
+        ],
+        _ => return None,
+    };
+
+    Some(keys)
+}
+
+/// The keys used by `play` and `practice`, read from the keys file in the config directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keybindings {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let mut bindings: Vec<(Action, Vec<Key>)> = grid::DIRECTIONS
+            .iter()
+            .enumerate()
+            .map(|(i, &dir)| {
+                let keys = ["arrows", "wasd"].map(|p| preset(p).unwrap()[i]);
+                (Action::Move(dir), keys.to_vec())
+            })
+            .collect();
+
+        bindings.extend([
+            (Action::Undo, vec![Key::Backspace, Key::Char('z')]),
+            (Action::Redo, vec![Key::Char('y')]),
+            (Action::Reset, vec![Key::Char('x')]),
+            (Action::Scramble, vec![Key::Char('r')]),
+            (Action::Quit, vec![Key::Char('q'), Key::Escape]),
+        ]);
+
+        Self { bindings }
+    }
+}
+
+impl Keybindings {
+    /// The file the keybindings are read from: `slidy/keys` in `$XDG_CONFIG_HOME`, or in
+    /// `~/.config` if that isn't set. `$SLIDY_KEYS` overrides this.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("SLIDY_KEYS") {
+            return Some(PathBuf::from(path));
+        }
+
+        let config = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(config.join("slidy").join("keys"))
+    }
+
+    /// Reads the keybindings file, if there is one. Actions that aren't in the file keep their
+    /// default keys.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let contents = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        contents
+            .parse()
+            .map_err(|e: KeysError| format!("{}: {e}", path.display()).into())
+    }
+
+    fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        // A key only does one thing, so it is removed from the other actions
+        for (_, bound) in &mut self.bindings {
+            bound.retain(|k| !keys.contains(k));
+        }
+
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, bound)) => *bound = keys,
+            None => self.bindings.push((action, keys)),
+        }
+    }
+
+    /// The action bound to a key. Moves follow `--convention`.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        let action = self
+            .bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|&(action, _)| action)?;
+
+        Some(match (action, notation::convention()) {
+            (Action::Move(dir), Convention::Blank) => {
+                let c = notation::flip_char(grid::direction_char(dir));
+                Action::Move(grid::parse_direction(c).unwrap())
+            }
+            (action, _) => action,
+        })
+    }
+
+    /// The names of the keys bound to an action, e.g. `q/esc`.
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self
+            .bindings
+            .iter()
+            .find(|&&(a, _)| a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default();
+
+        keys.iter()
+            .map(|&k| key_name(k))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// The names of the movement keys, e.g. `up/w left/a down/s right/d`.
+    pub fn describe_moves(&self) -> String {
+        grid::DIRECTIONS
+            .iter()
+            .map(|&dir| self.describe(Action::Move(dir)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl FromStr for Keybindings {
+    type Err = KeysError;
+
+    /// Parses lines of the form `action = key key ...`, where the action is `up`, `left`,
+    /// `down`, `right`, `undo`, `redo`, `reset`, `scramble` or `quit`, or `preset = arrows`,
+    /// `wasd` or `vim` to set all four movement keys. Empty lines and lines starting with `#` are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bindings = Self::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let n = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, value) = line.split_once('=').ok_or(KeysError::InvalidLine(n))?;
+            let (name, value) = (name.trim().to_lowercase(), value.trim());
+
+            if name == "preset" {
+                let keys =
+                    preset(value).ok_or_else(|| KeysError::UnknownPreset(n, value.to_string()))?;
+                for (&dir, key) in grid::DIRECTIONS.iter().zip(keys) {
+                    bindings.bind(Action::Move(dir), vec![key]);
+                }
+                continue;
+            }
+
+            let action =
+                Action::parse(&name).ok_or_else(|| KeysError::UnknownAction(n, name.clone()))?;
+            let keys = value
+                .split_whitespace()
+                .map(|k| parse_key(k).ok_or_else(|| KeysError::UnknownKey(n, k.to_string())))
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.bind(action, keys);
+        }
+
+        Ok(bindings)
+    }
+}
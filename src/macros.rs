@@ -0,0 +1,56 @@
+use clap::ValueEnum;
+use slidy::algorithm::algorithm::Algorithm;
+
+use crate::grid::{self, DIRECTIONS};
+
+/// How a keypress macro is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MacroStyle {
+    /// The key names separated by spaces.
+    Keys,
+    /// An AutoHotkey script.
+    Ahk,
+    /// An xdotool command.
+    Xdotool,
+}
+
+/// The keys pressed for up, left, down and right moves. Most simulators move a tile in the
+/// direction of the arrow key that is pressed.
+pub const DEFAULT_KEYS: [&str; 4] = ["Up", "Left", "Down", "Right"];
+
+/// Parses four comma separated key names, for up, left, down and right moves.
+pub fn parse_keys(s: &str) -> Result<[String; 4], String> {
+    let keys: Vec<String> = s.split(',').map(|k| k.trim().to_string()).collect();
+    if keys.iter().any(String::is_empty) {
+        return Err("key names can not be empty".to_string());
+    }
+
+    keys.try_into()
+        .map_err(|k: Vec<_>| format!("expected 4 keys, found {}", k.len()))
+}
+
+/// A macro that presses the key for each move of `alg`, waiting `delay` milliseconds between
+/// key presses.
+pub fn keypress_macro(
+    alg: &Algorithm,
+    style: MacroStyle,
+    keys: &[String; 4],
+    delay: u64,
+) -> String {
+    let presses: Vec<&str> = grid::directions(alg)
+        .into_iter()
+        .map(|dir| {
+            let idx = DIRECTIONS.iter().position(|&d| d == dir).unwrap();
+            keys[idx].as_str()
+        })
+        .collect();
+
+    match style {
+        MacroStyle::Keys => presses.join(" "),
+        MacroStyle::Ahk => {
+            let presses: String = presses.iter().map(|k| format!("{{{k}}}")).collect();
+            format!("SetKeyDelay, {delay}\nSend {presses}")
+        }
+        MacroStyle::Xdotool => format!("xdotool key --delay {delay} {}", presses.join(" ")),
+    }
+}
@@ -1,36 +1,105 @@
 #![feature(int_roundings)]
 
+mod analyze;
+mod animate;
+mod animated_svg;
+mod bench;
+mod chart;
+mod coloring;
+mod compare;
+mod db;
+mod enums;
+mod goal;
+mod grid;
+mod heuristic;
+mod keys;
+mod macros;
+mod masked;
+mod memory;
+mod multi_blank;
+mod notation;
+mod output;
+mod overlay;
+mod pipeline;
+mod play;
+mod practice;
+mod progress;
+mod reconstruct;
+mod render;
+mod repl;
+mod replay;
+mod rpc;
+mod schema;
+mod session;
+mod slidysim;
+mod solvers;
+mod stats;
+mod stream;
+mod terminal;
+mod timer;
+mod traffic;
+mod url;
 mod util;
+mod validate;
+mod watch;
+mod weighted;
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    error::Error,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use std::{error::Error, rc::Rc, str::FromStr};
-
-use clap::{command, ArgGroup, Parser, Subcommand, ValueEnum};
-use palette::rgb::Rgba;
+use clap::{command, ArgAction, ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use rand::seq::SliceRandom;
 use slidy::{
     algorithm::algorithm::Algorithm,
     puzzle::{
-        color_scheme::{tiled::Tiled, ColorScheme, Scheme, SchemeList},
-        coloring::{Coloring, Monochrome, Rainbow},
-        label::{
-            label::{
-                Checkerboard, Diagonals, Fringe, Label, RowGrids, Rows, SplitFringe,
-                SplitSquareFringe, SquareFringe,
-            },
-            scaled::Scaled,
-        },
+        label::label::{Label, RowGrids},
         puzzle::Puzzle,
-        render::{Renderer, RendererBuilder, Text},
         scrambler::{RandomMoves, RandomState, Scrambler},
         size::Size,
         sliding_puzzle::SlidingPuzzle,
     },
-    solver::{
-        heuristic::{manhattan::ManhattanDistance, Heuristic},
-        solver::Solver,
+    solver::{heuristic::manhattan::ManhattanDistance, solver::Solver},
+};
+
+use crate::{
+    bench::BenchCommand,
+    coloring::{Gradient, Palette},
+    enums::{
+        boxed_label, BlankHome, ChartValue, Convention, ErrorFormat, ErrorPolicy, ExportFormat,
+        LabelType, Metric, OutputFormat, SimFormat, StateFormatter, TableFormat,
     },
+    heuristic::{HeuristicType, Heuristics},
+    keys::Keybindings,
+    macros::{MacroStyle, DEFAULT_KEYS},
+    masked::MaskedPuzzle,
+    multi_blank::{MultiBlankAlgorithm, MultiBlankPuzzle},
+    notation::{NotationOptions, Pair, Relay},
+    overlay::Overlay,
+    pipeline::{Kind, Pipeline, Stage, Value},
+    practice::Phase,
+    progress::Progress,
+    reconstruct::Reconstruction,
+    render::RenderOptions,
+    session::{Score, Session},
+    slidysim::Solve as SlidysimSolve,
+    solvers::{labelled_solver, SolveFn},
+    stream::{Moves, StreamError},
+    util::{self, loop_func, par_try_func, read_all, try_func, try_func_once, Status},
+    weighted::Weights,
 };
 
-use crate::util::{loop_func, try_func, try_func_once};
+const STREAM_HELP: &str = "Read the algorithms from the input a move at a time instead of \
+    parsing them first, for algorithms too long to keep in memory";
+
+const WEIGHTS_HELP: &str = "The cost of moving each piece, as a comma-separated list of the \
+    weights of the pieces 1, 2, ... in order. By default each piece costs its own number";
 
 #[derive(Parser, Debug)]
 #[command(
@@ -42,56 +111,430 @@ use crate::util::{loop_func, try_func, try_func_once};
 struct Args {
     #[command(subcommand)]
     command: Command,
+
+    #[clap(
+        long,
+        global = true,
+        default_value = "text",
+        help = "Format of the errors written to stderr for inputs that can't be processed"
+    )]
+    error_format: ErrorFormat,
+
+    #[clap(
+        long,
+        global = true,
+        default_value = "stop",
+        help = "Whether to continue with the remaining inputs after an input can't be processed"
+    )]
+    on_error: ErrorPolicy,
+
+    #[clap(
+        long,
+        global = true,
+        conflicts_with = "on_error",
+        help = "Report invalid inputs on stderr and continue with the rest, the same as \
+        --on-error continue"
+    )]
+    skip_invalid: bool,
+
+    #[command(flatten)]
+    notation: NotationOptions,
+
+    #[clap(
+        long,
+        global = true,
+        default_value = "bottom-right",
+        help = "The corner the blank is in when the puzzle is solved, with the other pieces in \
+        order in the rest of the positions. Used when solving, checking solvability, computing \
+        heuristics, generating and rendering"
+    )]
+    blank_home: BlankHome,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Read inputs from this file instead of stdin, or from stdin if it is -. Can be \
+        given more than once to read several files in order"
+    )]
+    input: Vec<String>,
+
+    #[clap(
+        long,
+        global = true,
+        conflicts_with = "quiet",
+        help = "Write the output to this file instead of stdout. The file is only replaced once \
+        the command has finished"
+    )]
+    output_file: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Show a progress bar on stderr for long running commands (solve, optimize and \
+        generate). Ignored if stderr is not a terminal"
+    )]
+    progress: bool,
+
+    // Not global, because several commands have their own -v/--verbose flag
+    #[clap(
+        short,
+        long,
+        action = ArgAction::Count,
+        help = "Log progress to stderr, and with -vv every input. Must be given before the command"
+    )]
+    verbose: u8,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Report the time taken for each input, and in total, on stderr"
+    )]
+    time: bool,
+
+    #[clap(
+        long,
+        global = true,
+        value_parser = memory::parse_memory,
+        help = "The memory that tables and caches may use, e.g. 512M or 2G. Walking distance uses \
+        linear conflicts instead if its tables don't fit, and solve --cache remembers fewer states"
+    )]
+    max_memory: Option<u64>,
+
+    #[clap(
+        short,
+        long,
+        global = true,
+        default_value_t = 1,
+        help = "The number of threads used to process inputs. Used by format, invert, length, md \
+        and solvable. The output is in the same order as the inputs"
+    )]
+    jobs: usize,
+
+    #[clap(
+        short,
+        long,
+        global = true,
+        help = "Don't print anything to stdout. The exit code is 0 on success, 1 if an input was \
+        invalid, 2 if a state was unsolvable, 3 if an input was filtered out, and 130 if the \
+        command was interrupted"
+    )]
+    quiet: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
+    #[clap(
+        about = "Splits a solution into phases by when each label class is solved",
+        long_about = "Splits a solution into phases by when each label class is solved. A class \
+        is solved when all of its pieces are in their solved positions, and its phase ends at \
+        the move after which it stays solved until the end of the solution. Classes solved at \
+        the same move are in one phase. The number of single tile moves of each phase is \
+        printed with the label classes it solves. render-labels shows the classes of each \
+        label.\n\n\
+        The solution is given either as an algorithm, optionally with the --solve-time of the \
+        whole solve, or as a --reconstruction with timestamps in the format read by reconstruct. \
+        With a reconstruction, the time and TPS of each phase are printed too. Moves in a group with \
+        one timestamp are spread evenly over its time.\n\n\
+        With --chart, an SVG line chart is also saved with the optimal number of moves left, \
+        the Manhattan distance or the number of solved label classes after each move, chosen \
+        with --chart-value, and the ends of the phases marked. The optimal number of moves \
+        left needs every state of the solution to be solved, which can be slow.\n\n\
+        With --wasted, the moves that could have been saved are listed: each part of the \
+        solution that returns to a state it has already been in, and each window of --window \
+        moves outside of those that can be replaced by a shorter sequence, with the shorter \
+        sequence. Every window is solved optimally, so larger windows are slower."
+    )]
+    #[clap(group(ArgGroup::new("solution").required(true)))]
+    Analyze {
+        #[clap(short, long, value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(short, long, group = "solution", value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(
+            short,
+            long,
+            group = "solution",
+            help = "A solve with timestamps, e.g. \"RDD@0.4 LU@0.9 | R3@1.8\""
+        )]
+        reconstruction: Option<Reconstruction>,
+
+        #[clap(
+            short = 't',
+            long = "solve-time",
+            requires = "alg",
+            help = "The time of the whole solve in seconds"
+        )]
+        solve_time: Option<f64>,
+
+        #[clap(short, long, default_value = "split-fringe")]
+        label: LabelType,
+
+        #[clap(
+            long,
+            help = "Also save an SVG line chart of --chart-value against the move number, with \
+            the ends of the phases marked"
+        )]
+        chart: Option<String>,
+
+        #[clap(long, default_value = "optimal", requires = "chart")]
+        chart_value: ChartValue,
+
+        #[clap(
+            long,
+            help = "Also list the parts of the solution that return to an earlier state, and the \
+            windows of --window moves that can be replaced by fewer moves"
+        )]
+        wasted: bool,
+
+        #[clap(long, default_value_t = 8, requires = "wasted")]
+        window: usize,
+    },
+
     #[clap(about = "Applies algorithms to puzzle states")]
     #[clap(group(ArgGroup::new("group").multiple(true).required(true)))]
     Apply {
-        #[clap(short, long, group = "group")]
+        #[clap(short, long, group = "group", value_parser = notation::parse_state)]
         state: Option<Puzzle>,
 
-        #[clap(short, long, group = "group")]
+        #[clap(short, long, group = "group", value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
+
+        #[clap(
+            long,
+            group = "group",
+            conflicts_with_all = ["state", "alg"],
+            help = "Read a state and an algorithm from each input line, separated by a tab"
+        )]
+        pairs: bool,
+
+        #[clap(
+            long,
+            group = "group",
+            requires_all = ["states_file", "algs_file"],
+            conflicts_with_all = ["state", "alg", "pairs"],
+            help = "Apply every algorithm in --algs-file to every state in --states-file, printing \
+            the state, the algorithm and the result separated by tabs"
+        )]
+        cross: bool,
+
+        #[clap(long, requires = "cross")]
+        states_file: Option<String>,
+
+        #[clap(long, requires = "cross")]
+        algs_file: Option<String>,
     },
 
     #[clap(about = "Applies algorithms to the solved state")]
     ApplyToSolved {
-        #[clap(short, long)]
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
         #[clap(short, long)]
         size: Size,
     },
 
+    #[clap(
+        about = "Prints the current and best rolling averages of a list of times",
+        long_about = "Reads one time per line and prints the current and best average of each \
+        --window size. A time with a decimal point, e.g. 12.345 or 1:02.345, is in seconds, and \
+        a whole number is in milliseconds, as in session files. A + or +2 after a time adds two \
+        seconds, and DNF or DNF(...) is a DNF.\n\n\
+        As in WCA averages, 5% of the times at each end, rounded up, are left out of each \
+        average: the best and worst time of an ao5 or ao12, and the best and worst five of an \
+        ao100. An average is a DNF if there are more DNFs than are left out."
+    )]
+    Avg {
+        #[clap(short, long, value_delimiter = ',', default_value = "5,12,100")]
+        window: Vec<usize>,
+    },
+
+    #[clap(
+        about = "Times a command on random states, for comparing performance between versions",
+        long_about = "Times a command on random states, printing the mean, median and 95th \
+        percentile time per state, and the number of states per second. The states depend only \
+        on the size, the number of samples and the seed, so runs with the same options are \
+        comparable."
+    )]
+    Bench {
+        #[clap(short, long, default_value = "solve")]
+        command: BenchCommand,
+
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
+
+        #[clap(short = 'n', long, default_value_t = 100)]
+        samples: u64,
+
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+    },
+
+    #[clap(
+        about = "Prints how far the blank travels in an algorithm, and how often it turns",
+        long_about = "Prints how far the blank travels in an algorithm, which is its length in \
+        single tile moves, the number of turns, where the blank changes to a perpendicular \
+        direction, the number of reversals, where it goes back the way it came, and the longest \
+        run of moves in a straight line."
+    )]
+    BlankTravel {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
+
+    #[clap(
+        about = "Compares the optimal solvers on puzzle states",
+        long_about = "Solves each puzzle state with every method, printing a table of the \
+        solution length, the number of nodes expanded and the time taken by each. The methods \
+        are the IDA* search of watch-solve with each of --heuristics, which gives up after \
+        --max-nodes nodes, and the solver used by solve with each of --labels, which doesn't \
+        count its nodes. Every method finds optimal solutions, so a warning is printed if their \
+        lengths differ."
+    )]
+    CompareSolvers {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(long, value_delimiter = ',', default_value = "md,lc,wd")]
+        heuristics: Vec<HeuristicType>,
+
+        #[clap(
+            long,
+            value_delimiter = ',',
+            default_value = "row-grids,rows,fringe,split-fringe"
+        )]
+        labels: Vec<LabelType>,
+
+        #[clap(long, default_value_t = 10_000_000)]
+        max_nodes: u64,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
+
     #[clap(about = "Appends a prefix or suffix to an algorithm")]
     Concat {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
-        #[clap(short, long)]
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
         prefix: Algorithm,
 
-        #[clap(short, long)]
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
         suffix: Algorithm,
     },
 
+    #[clap(
+        about = "Converts algorithms between the tile and blank conventions",
+        long_about = "Converts algorithms between the tile and blank conventions. The input is \
+        read in the convention given by --convention (tile by default)."
+    )]
+    ConvertNotation {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(long)]
+        to: Convention,
+    },
+
+    #[clap(
+        about = "Swaps random pairs of non-gap pieces, making the state unsolvable if an odd \
+        number of swaps is made"
+    )]
+    Corrupt {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short = 'k', long, default_value_t = 1)]
+        swaps: u64,
+    },
+
+    #[clap(
+        about = "Stores and looks up solutions in an SQLite database",
+        long_about = "Stores solutions of puzzle states in an SQLite database file, with the \
+        method used to find them and any tags, so that they can be looked up later. solve --db \
+        uses the same database to avoid solving a state twice."
+    )]
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+
     #[clap(about = "Embeds a puzzle state into a larger puzzle")]
     #[clap(group(ArgGroup::new("group").multiple(true).required(true)))]
     #[clap(group(ArgGroup::new("target_type").multiple(false).required(false)))]
     Embed {
-        #[clap(group = "group")]
+        #[clap(group = "group", value_parser = notation::parse_state)]
         state: Option<Puzzle>,
 
-        #[clap(short, long, group = "group", group = "target_type")]
+        #[clap(short, long, group = "group", group = "target_type", value_parser = notation::parse_state)]
         target: Option<Puzzle>,
 
         #[clap(short, long, group = "group", group = "target_type")]
         size: Option<Size>,
     },
 
+    #[clap(
+        about = "Estimates the mean optimal solution length of random states of a size",
+        long_about = "Optimally solves random solvable states of the given size and prints the \
+        mean and standard deviation of their solution lengths, with a 95% confidence interval \
+        for the mean over all states of that size. The states depend only on the size, the \
+        number of samples and the seed, as in bench. If interrupted, the estimate uses the \
+        states solved so far."
+    )]
+    EstimateMean {
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
+
+        #[clap(short = 'n', long, default_value_t = 1000)]
+        samples: u64,
+
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+
+        #[clap(short, long, default_value = "row-grids")]
+        label: LabelType,
+    },
+
+    #[clap(
+        about = "Converts states, algorithms and times to the solve format of another program, \
+        or algorithms to keypress macros",
+        long_about = "Converts lines containing a state, an algorithm and optionally a time in \
+        milliseconds, separated by tabs, to the solve format of another program. This is the \
+        reverse of import. With the macro format, each line is an algorithm, which is converted \
+        to a macro pressing a key for each move."
+    )]
+    Export {
+        format: ExportFormat,
+
+        line: Option<String>,
+
+        #[clap(long, default_value = "keys", help = "How macros are written")]
+        macro_style: MacroStyle,
+
+        #[clap(
+            long,
+            value_parser = macros::parse_keys,
+            help = "The keys for up, left, down and right moves in macros, separated by commas \
+            [default: Up,Left,Down,Right]"
+        )]
+        keys: Option<[String; 4]>,
+
+        #[clap(
+            long,
+            default_value_t = 0,
+            help = "Milliseconds between key presses in macros"
+        )]
+        delay: u64,
+    },
+
     #[clap(about = "Filters out suboptimal solutions from a list of algorithms")]
     FilterOptimal {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
         #[clap(short, long)]
@@ -101,8 +544,21 @@ enum Command {
         keep_suboptimal: bool,
     },
 
+    #[clap(about = "Prints only the solvable puzzle states read from stdin")]
+    FilterSolvable {
+        #[clap(short, long)]
+        annotate: bool,
+    },
+
+    #[clap(about = "Prints only the unsolvable puzzle states read from stdin")]
+    FilterUnsolvable {
+        #[clap(short, long)]
+        annotate: bool,
+    },
+
     #[clap(about = "Formats algorithms using long or short notation, with or without spaces")]
     Format {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
         #[clap(short, long)]
@@ -110,11 +566,15 @@ enum Command {
 
         #[clap(short, long)]
         spaced: bool,
+
+        #[clap(long, conflicts_with = "alg", help = STREAM_HELP)]
+        stream: bool,
     },
 
     #[clap(about = "Formats puzzle states inline or in a grid layout")]
     #[clap(group(ArgGroup::new("formatter")))]
     FormatState {
+        #[clap(value_parser = notation::parse_state)]
         state: Option<Puzzle>,
 
         #[clap(short, long, default_value = "inline")]
@@ -123,6 +583,7 @@ enum Command {
 
     #[clap(about = "Prints the scramble state, given a solution and the size of the puzzle")]
     FromSolution {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
         #[clap(short, long)]
@@ -153,183 +614,1234 @@ enum Command {
 
         #[clap(short = 'i', long, requires = "random_moves")]
         allow_illegal_moves: bool,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
+
+    #[clap(
+        about = "Compares solutions of puzzle states to optimal solutions",
+        long_about = "Reads a puzzle state and a solution from each input line, separated by a \
+        tab, and prints the length of the solution, the optimal length, how many moves longer \
+        than optimal it is, and its efficiency: the optimal length as a percentage of its \
+        length. A summary of the batch is printed at the end, to stderr if --format is given. \
+        Solutions that don't solve their state are reported on stderr like other errors and \
+        counted in the summary. They don't stop the batch, even with --on-error stop."
+    )]
+    Grade {
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
+
+    #[clap(about = "Prints the value of a heuristic for puzzle states")]
+    Heuristic {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short = 't', long = "type", default_value = "md")]
+        heuristic: HeuristicType,
+
+        #[clap(short, long)]
+        all: bool,
+    },
+
+    #[clap(
+        about = "Converts solves exported by another program to states, algorithms and times",
+        long_about = "Converts solves exported by another program to lines containing the \
+        scramble, the solution and the time in milliseconds, separated by tabs. The lines can be \
+        used with apply --pairs and verify --pairs."
+    )]
+    Import {
+        format: SimFormat,
+
+        solve: Option<SlidysimSolve>,
     },
 
     #[clap(about = "Prints the inverse of an algorithm")]
-    Invert { alg: Option<Algorithm> },
+    Invert {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+    },
 
-    #[clap(about = "Prints the length of an algorithm in single tile moves")]
+    #[clap(
+        about = "Prints the length of an algorithm in single tile moves",
+        long_about = "Prints the length of an algorithm in single tile moves.\n\n\
+        With --weighted, each move costs the weight of the piece it moves instead, and the \
+        length is the total cost. The pieces that move depend on the state, so the algorithm is \
+        taken to be a solution of the scramble it solves on a puzzle of the given size."
+    )]
     Length {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
         #[clap(short, long, default_value = "stm")]
         metric: Metric,
+
+        #[clap(
+            long,
+            requires = "size",
+            conflicts_with_all = ["metric", "stream"],
+            help = "Print the total cost of the pieces moved"
+        )]
+        weighted: bool,
+
+        #[clap(long, requires = "weighted", help = WEIGHTS_HELP)]
+        weights: Option<Weights>,
+
+        #[clap(short, long, requires = "weighted")]
+        size: Option<Size>,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+
+        #[clap(long, conflicts_with_all = ["alg", "format"], help = STREAM_HELP)]
+        stream: bool,
+    },
+
+    #[clap(
+        hide = true,
+        about = "Writes man pages for slidy and each of its subcommands to a directory"
+    )]
+    Mangen {
+        #[clap(short, long, default_value = ".")]
+        output_dir: String,
     },
 
     #[clap(
         about = "Prints the sum of the Manhattan distances of all pieces from their solved \
         positions"
     )]
-    Md { state: Option<Puzzle> },
+    Md {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long, conflicts_with = "format")]
+        breakdown: bool,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
 
     #[clap(
-        about = "Finds the difference in length between an algorithm and the optimal solution \
-        of the scramble"
+        about = "Prints the Manhattan distance of the known pieces of partially known states, \
+        where unknown pieces are written as ?"
     )]
-    OptDiff {
-        alg: Option<Algorithm>,
+    MaskedMd {
+        state: Option<MaskedPuzzle>,
 
         #[clap(short, long)]
-        size: Size,
+        verbose: bool,
     },
 
     #[clap(
-        about = "Attempts to find a shorter equivalent algorithm by optimally solving all \
-        sub-algorithms of the given length"
+        about = "Finds an optimal algorithm that moves the known pieces of partially known states \
+        into their solved positions"
     )]
-    Optimize {
-        alg: Option<Algorithm>,
+    MaskedSolve {
+        state: Option<MaskedPuzzle>,
 
         #[clap(short, long)]
-        length: u64,
+        verbose: bool,
     },
 
-    #[clap(about = "Creates an SVG image of a puzzle state")]
-    Render {
-        state: Option<Puzzle>,
+    #[clap(
+        about = "Applies algorithms to puzzle states with multiple blanks",
+        long_about = "Applies algorithms to puzzle states with multiple blanks. Blanks are \
+        written as 0, and in algorithms [i] switches to moving the ith blank (in reading order \
+        before the algorithm is applied), e.g. [1]ULL[2]DR."
+    )]
+    #[clap(group(ArgGroup::new("group").multiple(true).required(true)))]
+    MultiApply {
+        #[clap(short, long, group = "group")]
+        state: Option<MultiBlankPuzzle>,
 
-        #[clap(short, long, default_value = "fringe")]
-        label: LabelType,
+        #[clap(short, long, group = "group")]
+        alg: Option<MultiBlankAlgorithm>,
+    },
 
-        #[clap(short, long, default_value = "rainbow-bright-full")]
-        coloring: ColoringType,
+    #[clap(about = "Generates random scrambles of puzzles with multiple blanks")]
+    MultiGenerate {
+        #[clap(short, long, default_value_t = 1)]
+        number: u64,
 
-        #[clap(short, long, default_value = "75.0")]
-        tile_size: f32,
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
 
-        #[clap(short, long)]
-        output: String,
+        #[clap(short, long, default_value_t = 2)]
+        blanks: u64,
     },
 
-    #[clap(about = "Simplifies algorithms by combining consecutive moves when possible")]
-    Simplify {
-        alg: Option<Algorithm>,
+    #[clap(
+        about = "Finds a (not necessarily optimal) solution to puzzle states with multiple blanks"
+    )]
+    MultiSolve {
+        state: Option<MultiBlankPuzzle>,
 
         #[clap(short, long)]
         verbose: bool,
     },
 
-    #[clap(about = "Prints a sub-algorithm between two moves")]
-    Slice {
+    #[clap(
+        about = "Finds the difference in length between an algorithm and the optimal solution \
+        of the scramble"
+    )]
+    OptDiff {
+        #[clap(value_parser = notation::parse_algorithm)]
         alg: Option<Algorithm>,
 
-        #[clap(short, long, default_value = "0")]
-        start: u64,
+        #[clap(short, long)]
+        size: Size,
+
+        #[clap(
+            long,
+            help = "Compare the total cost of the pieces moved with the cheapest solution, \
+            instead of the number of moves"
+        )]
+        weighted: bool,
+
+        #[clap(long, requires = "weighted", help = WEIGHTS_HELP)]
+        weights: Option<Weights>,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+    },
+
+    #[clap(
+        about = "Attempts to find a shorter equivalent algorithm by optimally solving all \
+        sub-algorithms of the given length"
+    )]
+    Optimize {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
 
         #[clap(short, long)]
-        end: Option<u64>,
+        length: u64,
     },
 
-    #[clap(about = "Checks if puzzle states are solvable")]
-    Solvable { state: Option<Puzzle> },
+    #[clap(
+        about = "Runs several commands in sequence, passing the results between them directly",
+        long_about = "Runs several commands in sequence, passing the results between them \
+        directly instead of printing and re-parsing them. Each stage is one argument, e.g. \
+        `slidy pipeline \"generate 10 4x4\" solve length`. The stages are generate [N] [SIZE], \
+        apply ALG, solve, invert, simplify, length, md and filter-solvable. If the first stage \
+        isn't generate, its inputs are read from stdin."
+    )]
+    Pipeline {
+        #[clap(required = true, value_parser = Stage::from_str)]
+        stages: Vec<Stage>,
+    },
 
-    #[clap(about = "Finds one optimal solution to a puzzle state")]
-    Solve {
+    #[clap(
+        about = "Solves puzzles interactively in the terminal",
+        long_about = "Solves puzzles interactively in the terminal. The arrow keys or WASD slide \
+        the tiles, following --convention. The timer starts with the first move and stops when \
+        the puzzle is solved. Backspace undoes a move and y redoes it, x goes back to the \
+        scramble, r switches to the next state (from --queue, or else a random one) and q \
+        quits.\n\n\
+        The keys can be changed in ~/.config/slidy/keys (or $XDG_CONFIG_HOME/slidy/keys, or the \
+        file in $SLIDY_KEYS), with lines such as \"preset = vim\", \"undo = u backspace\" or \
+        \"scramble = space\". The actions are up, left, down, right, undo, redo, reset, \
+        scramble and quit, and the presets for the movement keys are arrows, wasd and vim."
+    )]
+    Play {
+        #[clap(value_parser = notation::parse_state)]
         state: Option<Puzzle>,
 
-        #[clap(short, long, default_value = "row-grids")]
-        label: LabelType,
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
 
-        #[clap(short, long)]
-        verbose: bool,
+        #[clap(
+            long,
+            help = "File to append the moves of each solve to, with timestamps, in the format \
+            read by reconstruct"
+        )]
+        record: Option<String>,
+
+        #[clap(
+            long,
+            conflicts_with = "state",
+            help = "Play the states read from the input (stdin or --input) in order, e.g. a set of \
+            competition scrambles, before switching to random states"
+        )]
+        queue: bool,
+
+        #[command(flatten)]
+        options: RenderOptions,
     },
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
-enum LabelType {
-    RowGrids,
-    Rows,
-    Fringe,
-    SquareFringe,
-    SplitFringe,
-    SplitSquareFringe,
-    Diagonals,
-    Checkerboard,
-    Grids,
-}
+    #[clap(
+        about = "Practises one phase of a solve, e.g. the last 2 rows, with random scrambles",
+        long_about = "Practises one phase of a solve with random scrambles in which the rest of \
+        the puzzle is solved. The phase is the unsolved bottom right part of the puzzle: \
+        \"last N rows\", \"last N columns\" or \"last WxH\". The scrambles are solved in the \
+        terminal like in play, or with --paste by entering the solutions. The time and number \
+        of moves of each solve are tracked per phase."
+    )]
+    Practice {
+        #[clap(short, long, value_parser = Phase::from_str)]
+        phase: Phase,
 
-#[derive(Clone, Copy, Debug, ValueEnum)]
-enum ColoringType {
-    None,
-    Rainbow,
-}
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
-enum StateFormatter {
-    Inline,
-    Grid,
-}
+        #[clap(
+            long,
+            help = "Read a solution for each scramble from stdin instead of solving it \
+            interactively"
+        )]
+        paste: bool,
+
+        #[clap(
+            long,
+            help = "File that the solves are added to, with one line per solve. The statistics \
+            include the solves of the same phase already in the file"
+        )]
+        session: Option<String>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
-enum Metric {
-    Stm,
-    Mtm,
-}
+    #[clap(
+        about = "Prints the time, TPS, splits and pauses of solves annotated with timestamps",
+        long_about = "Prints the time, TPS, splits and pauses of solves annotated with timestamps. \
+        Each group of moves is followed by @ and the time in seconds since the start of the \
+        solve, and phases are separated by |, e.g. \"RDD@0.4 LU@0.9 | R3@1.8 DLU@2.5\"."
+    )]
+    Reconstruct {
+        reconstruction: Option<Reconstruction>,
+
+        #[clap(
+            long,
+            default_value_t = 0.5,
+            help = "The shortest gap in seconds between two timestamps that counts as a pause"
+        )]
+        pause: f64,
+    },
 
-fn apply(state: &mut Puzzle, alg: &Algorithm) {
-    if state.try_apply_alg(alg) {
-        println!("{state}");
-    } else {
-        println!("Invalid");
-    }
-}
+    #[clap(
+        about = "Runs commands on a puzzle state that is kept between them",
+        long_about = "Runs commands read from stdin on a puzzle state that is kept between \
+        them, e.g. \"apply RUL\", \"undo\", \"solve\" and \"render out.svg\". Enter help to \
+        list the commands.\n\n\
+        \"record\" starts recording the algorithms applied and \"stop\" prints them with \
+        timestamps, in the format read by reconstruct. With --record, recording starts \
+        straight away and each recording is appended to the file."
+    )]
+    Repl {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
 
-fn apply_to_solved(alg: &Algorithm, size: Size) -> Result<(), Box<dyn Error>> {
-    let mut state = Puzzle::new(size);
-    apply(&mut state, alg);
+        #[clap(
+            short,
+            long,
+            default_value_t = Size::new(4, 4).unwrap(),
+            value_parser = Size::from_str,
+            help = "Size of the solved state to start from if no state is given"
+        )]
+        size: Size,
 
-    Ok(())
-}
+        #[clap(long, help = "File to append recordings to")]
+        record: Option<String>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    #[clap(
+        about = "Steps through an algorithm applied to a puzzle state in the terminal",
+        long_about = "Steps through an algorithm applied to a puzzle state in the terminal, one \
+        single tile move at a time, with the moved tile highlighted. The left and right arrow keys \
+        step backwards and forwards, space plays and pauses the moves, and q quits."
+    )]
+    Replay {
+        #[clap(short, long, value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
+        alg: Algorithm,
+
+        #[clap(
+            long,
+            help = "Start playing the moves straight away, at this many moves per second"
+        )]
+        tps: Option<f64>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    #[clap(
+        about = "Creates an SVG image showing how many times a piece moves into each cell during \
+        an algorithm"
+    )]
+    RenderTraffic {
+        #[clap(short, long, value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
+        alg: Algorithm,
+
+        #[clap(short, long, default_value = "75.0")]
+        tile_size: f32,
+
+        #[clap(long, help = "Comma separated list of colors used for the gradient")]
+        palette: Option<Palette>,
+
+        #[clap(short, long)]
+        output: String,
+    },
+
+    #[clap(
+        about = "Solves the scrambles of relays, with the move count of each and in total",
+        long_about = "Reads relays, one per line, each with puzzle states of any sizes separated \
+        by tabs, and prints an optimal solution of each state with its size and length, and the \
+        total length of the relay. Relays are separated by blank lines, and blank lines in the \
+        input are skipped."
+    )]
+    RelaySolve {
+        #[clap(value_parser = Relay::from_str)]
+        relay: Option<Relay>,
+
+        #[clap(short, long, default_value = "row-grids")]
+        label: LabelType,
+    },
+
+    #[clap(
+        about = "Randomizes the pieces belonging to the given label classes, keeping all other \
+        pieces fixed"
+    )]
+    Rescramble {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long, default_value = "row-grids")]
+        label: LabelType,
+
+        #[clap(short, long, value_delimiter = ',', required = true)]
+        classes: Vec<u64>,
+    },
+
+    #[clap(about = "Creates an SVG image of a puzzle state")]
+    Render {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(short, long, required_unless_present_any = ["frames_dir", "term"])]
+        output: Option<String>,
+
+        #[clap(
+            long,
+            conflicts_with_all = ["frames_dir", "show_moves", "blank_path", "sheet"],
+            help = "Print the state to the terminal using colored blocks instead of creating an \
+            image"
+        )]
+        term: bool,
+
+        #[clap(
+            long,
+            requires = "alg",
+            help = "Write one image per single tile move of the algorithm to this directory"
+        )]
+        frames_dir: Option<String>,
+
+        #[clap(
+            long,
+            requires = "alg",
+            help = "Draw arrows on the tiles moved by the next N moves of the algorithm, instead \
+            of applying it"
+        )]
+        show_moves: Option<u64>,
+
+        #[clap(
+            long,
+            requires = "alg",
+            conflicts_with = "show_moves",
+            help = "Draw the path traced by the gap during the algorithm, instead of applying it"
+        )]
+        blank_path: bool,
+
+        #[clap(
+            long,
+            requires = "blank_path",
+            help = "Number the moves along the gap's path"
+        )]
+        number_moves: bool,
+
+        #[clap(
+            long,
+            conflicts_with = "frames_dir",
+            help = "Render every state read from stdin into a single image"
+        )]
+        sheet: bool,
+
+        #[clap(long, default_value_t = 4, requires = "sheet")]
+        columns: u64,
+
+        #[clap(long, conflicts_with = "term", help = "Text drawn beneath the puzzle")]
+        caption: Option<String>,
+
+        #[clap(
+            long,
+            conflicts_with_all = ["caption", "term"],
+            help = "Draw the input state beneath the puzzle"
+        )]
+        caption_from_input: bool,
+    },
+
+    #[clap(
+        about = "Creates an SVG image of a puzzle state before and after applying an algorithm, \
+        side by side"
+    )]
+    RenderBeforeAfter {
+        #[clap(short, long, value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
+        alg: Algorithm,
+
+        #[command(flatten)]
+        options: RenderOptions,
+
+        #[clap(short, long)]
+        output: String,
+    },
+
+    #[clap(
+        about = "Creates an SVG image of the solved state colored using every label, to show \
+        what each label looks like"
+    )]
+    RenderLabels {
+        #[clap(short, long)]
+        size: Size,
+
+        #[command(flatten)]
+        options: RenderOptions,
+
+        #[clap(long, default_value_t = 3)]
+        columns: u64,
+
+        #[clap(short, long)]
+        output: String,
+    },
+
+    #[clap(
+        about = "Prints the size of puzzle states, or the minimum size of puzzle that algorithms \
+        can be applied to"
+    )]
+    Size { state_or_alg: Option<String> },
+
+    #[clap(
+        about = "Creates an animated GIF, APNG or SVG image of an algorithm being applied to a \
+        puzzle state",
+        long_about = "Creates an animated image of an algorithm being applied to a puzzle state, \
+        with one frame per single tile move. The output is an APNG if the file name ends in .png \
+        or .apng, an SVG with SMIL animations of the tile movements if it ends in .svg, and a GIF \
+        otherwise."
+    )]
+    RenderSolve {
+        #[clap(short, long, value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(short, long, value_parser = notation::parse_algorithm)]
+        alg: Algorithm,
+
+        #[command(flatten)]
+        options: RenderOptions,
+
+        #[clap(short = 'd', long, default_value_t = 200)]
+        frame_duration: u16,
+
+        #[clap(short, long)]
+        output: String,
+    },
+
+    #[clap(
+        about = "Answers JSON-RPC requests read from stdin, for use as a long running subprocess",
+        long_about = "Reads JSON-RPC 2.0 requests from stdin, one per line, and writes one \
+        response per line to stdout. The methods are apply, heuristic, invert, length, simplify, \
+        solvable, solve and verify, and their parameters are named state, alg, type and label. \
+        Solvers and the tables used by the heuristics are kept between requests."
+    )]
+    Rpc,
+
+    #[clap(
+        about = "Prints the JSON Schema of the structured output of commands",
+        long_about = "Prints the JSON Schema of the records printed with --format ndjson, and of \
+        the errors written with --error-format json. Without a command, prints every schema \
+        along with the schema version."
+    )]
+    Schema {
+        #[clap(help = "A command name, or \"error\" for the error objects")]
+        command: Option<String>,
+    },
+
+    #[clap(
+        about = "Exports, imports and summarizes the session files written by timer and practice",
+        long_about = "Exports, imports and summarizes the session files written by timer and \
+        practice. `session export` prints the solves as CSV, TSV or NDJSON records with the \
+        columns time (in milliseconds, without the penalty), penalty, scramble, solution, moves, \
+        tps and phase, and `session import` adds such records to a session file. `session stats` \
+        prints the statistics of each phase, including the best averages and the mean number of \
+        moves and TPS."
+    )]
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+
+    #[clap(
+        about = "Creates a printable sheet of random scrambles, optionally with an answer key",
+        long_about = "Creates a printable sheet of random scrambles, laid out in a grid with each \
+        one numbered beneath it. With --answers, a page listing an optimal solution of each \
+        scramble and its length is added below the scrambles. The scrambles are the same for \
+        every run with the same --seed."
+    )]
+    Sheet {
+        #[clap(short = 'n', long, default_value_t = 25)]
+        count: u64,
+
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
+
+        #[clap(long, default_value_t = 5)]
+        columns: u64,
+
+        #[clap(long, help = "Add a page with an optimal solution of each scramble")]
+        answers: bool,
+
+        #[clap(long)]
+        seed: Option<u64>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+
+        #[clap(short, long)]
+        output: String,
+    },
+
+    #[clap(about = "Simplifies algorithms by combining consecutive moves when possible")]
+    Simplify {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(short, long)]
+        verbose: bool,
+    },
+
+    #[clap(about = "Prints a sub-algorithm between two moves")]
+    Slice {
+        #[clap(value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(short, long, default_value = "0")]
+        start: u64,
+
+        #[clap(short, long)]
+        end: Option<u64>,
+
+        #[clap(long, conflicts_with = "alg", help = STREAM_HELP)]
+        stream: bool,
+    },
+
+    #[clap(about = "Checks if puzzle states are solvable")]
+    #[clap(
+        long_about = "Checks if puzzle states are solvable. With --quiet, nothing is printed and \
+        the exit code is 0 if every state is solvable and 2 otherwise."
+    )]
+    Solvable {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long)]
+        filter: bool,
+    },
+
+    #[clap(about = "Finds one optimal solution to a puzzle state")]
+    Solve {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long, default_value = "row-grids")]
+        label: LabelType,
+
+        #[clap(short, long)]
+        verbose: bool,
+
+        #[clap(long, help = "Print one record per result, as CSV, TSV or JSON lines")]
+        format: Option<TableFormat>,
+
+        #[clap(
+            long,
+            help = "Remember the solutions of up to this many states, so that repeated states in a \
+            batch are only solved once"
+        )]
+        cache: Option<usize>,
+
+        #[clap(
+            long,
+            help = "Look up each state in this solution database before solving it, and add the \
+            solutions of the states that aren't in it"
+        )]
+        db: Option<String>,
+    },
+
+    #[clap(
+        about = "Prints statistics of a list of algorithms or puzzle states",
+        long_about = "Reads algorithms from the input and prints their number, the mean, \
+        median, minimum, maximum and percentiles of their lengths in both metrics, and a \
+        histogram of their lengths.\n\n\
+        With --states, reads puzzle states of one size instead, and prints the same statistics \
+        and a histogram of their Manhattan distances, how many have an even or odd permutation \
+        of the pieces, and how many have the gap in each position. --optimal also solves every \
+        state optimally and includes the solution lengths, which is slow for puzzles larger \
+        than 4x4.\n\n\
+        With --directions, the number and share of the moves in each direction over all of the \
+        algorithms are printed too, with the longest streak of moves in each direction and the \
+        algorithm it is in."
+    )]
+    Stats {
+        #[clap(
+            short,
+            long,
+            default_value = "stm",
+            help = "The metric of the histogram of algorithm lengths"
+        )]
+        metric: Metric,
+
+        #[clap(long, help = "Read puzzle states instead of algorithms")]
+        states: bool,
+
+        #[clap(
+            long,
+            requires = "states",
+            help = "Include the optimal solution lengths"
+        )]
+        optimal: bool,
+
+        #[clap(
+            long,
+            conflicts_with = "states",
+            help = "Include the balance of the move directions"
+        )]
+        directions: bool,
+    },
+
+    #[clap(
+        about = "Prints the positions a tile visits while an algorithm is applied to a state",
+        long_about = "Prints the positions a tile visits while an algorithm is applied to a \
+        puzzle state, as x,y coordinates from the top left starting at 0, separated by spaces. \
+        The first position is where the tile starts, and a position is added each time the tile \
+        moves."
+    )]
+    #[clap(group(ArgGroup::new("group").multiple(true).required(true)))]
+    TilePath {
+        #[clap(short, long, group = "group", value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long, group = "group", value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(
+            long,
+            group = "group",
+            conflicts_with_all = ["state", "alg"],
+            help = "Read a state and an algorithm from each input line, separated by a tab"
+        )]
+        pairs: bool,
+
+        #[clap(short, long)]
+        tile: u64,
+    },
+
+    #[clap(
+        about = "Times solves of random scrambles, with the best time, mean, ao5 and ao12",
+        long_about = "Times solves of random scrambles, with the best time, mean, ao5 and ao12. \
+        Space starts and stops the timer, and q quits. The averages of 5 and 12 leave out the \
+        best and worst time.\n\n\
+        After a solve, 2 gives it a +2 penalty and d makes it a DNF (pressing the key again \
+        takes the penalty back). With --inspection, the first space starts a countdown and the \
+        second starts the solve, and starting up to two seconds after the countdown ends is a \
+        +2 and starting later is a DNF, as in WCA competitions. An average with more than one \
+        DNF is a DNF."
+    )]
+    Timer {
+        #[clap(short, long, default_value_t = Size::new(4, 4).unwrap(), value_parser = Size::from_str)]
+        size: Size,
+
+        #[clap(
+            long,
+            help = "File that the solves are added to, with one line per solve. The statistics \
+            include the solves already in the file"
+        )]
+        session: Option<String>,
+
+        #[clap(
+            long,
+            value_name = "SECONDS",
+            help = "Inspect each scramble for up to this many seconds before the solve, e.g. 15"
+        )]
+        inspection: Option<u64>,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    #[clap(
+        about = "Creates links to web simulators with a puzzle state loaded, or reads the state \
+        from such a link",
+        long_about = "Creates links to web simulators with a puzzle state loaded, by filling in \
+        a template where {state} is replaced by the encoded state, and {width} and {height} by \
+        the size of the puzzle. `url parse` reads the state back from a link.",
+        args_conflicts_with_subcommands = true
+    )]
+    Url {
+        #[command(subcommand)]
+        action: Option<UrlAction>,
+
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(
+            short,
+            long,
+            help = "The link, e.g. https://example.com/?scramble={state}"
+        )]
+        template: Option<String>,
+    },
+
+    #[clap(
+        about = "Checks that algorithms solve puzzle states",
+        long_about = "Checks that algorithms solve puzzle states, printing true or false. The exit \
+        code is 3 if any algorithm doesn't solve its state."
+    )]
+    #[clap(group(ArgGroup::new("group").multiple(true).required(true)))]
+    Verify {
+        #[clap(short, long, group = "group", value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long, group = "group", value_parser = notation::parse_algorithm)]
+        alg: Option<Algorithm>,
+
+        #[clap(
+            long,
+            group = "group",
+            conflicts_with_all = ["state", "alg"],
+            help = "Read a state and an algorithm from each input line, separated by a tab"
+        )]
+        pairs: bool,
+    },
+
+    #[clap(about = "Explains why puzzle states are invalid")]
+    ValidateState {
+        state: Option<String>,
+
+        #[clap(short, long)]
+        size: Option<Size>,
+    },
+
+    #[clap(
+        about = "Shows the IDA* search for a solution of a puzzle state in the terminal",
+        long_about = "Shows the IDA* search for an optimal solution of a puzzle state in the \
+        terminal, one node at a time. Each iteration searches every path whose length plus \
+        heuristic is at most the bound, and the bound goes up to the smallest cost above it when \
+        an iteration finds nothing. The screen shows the bound, the number of nodes expanded, \
+        the path to the current node and the state there, and the path to the node closest to \
+        solved so far. Space pauses, the right arrow key expands one node, + and - change the \
+        speed, and q quits. This is much slower than solve, so use a puzzle that is close to \
+        solved."
+    )]
+    WatchSolve {
+        #[clap(value_parser = notation::parse_state)]
+        state: Puzzle,
+
+        #[clap(long, default_value = "md")]
+        heuristic: HeuristicType,
+
+        #[clap(
+            long,
+            default_value_t = 10.0,
+            help = "The number of nodes expanded per second"
+        )]
+        speed: f64,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    #[clap(
+        about = "Finds a solution of a puzzle state with the least total cost of the pieces moved",
+        long_about = "Finds a solution of a puzzle state with the least total cost, where each \
+        move costs the weight of the piece it moves. The search is A* with the weighted \
+        Manhattan distance, and it keeps every state it reaches in memory, so it is only \
+        practical for small puzzles. It stops at the --max-memory limit."
+    )]
+    WeightedSolve {
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(long, help = WEIGHTS_HELP)]
+        weights: Option<Weights>,
+
+        #[clap(short, long)]
+        verbose: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbAction {
+    #[clap(
+        about = "Adds solutions to a database",
+        long_about = "Reads a puzzle state and a solution from each input line, separated by a \
+        tab, and adds them to the database, which is created if it doesn't exist. Solutions \
+        that don't solve their state are reported as errors. Adding a solution that is already \
+        in the database replaces its method and tags."
+    )]
+    Add {
+        file: String,
+
+        #[clap(
+            short,
+            long,
+            default_value = "manual",
+            help = "How the solutions were found"
+        )]
+        method: String,
+
+        #[clap(short, long, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+
+    #[clap(about = "Prints the solutions in a database matching all of the given filters")]
+    Query {
+        file: String,
+
+        #[clap(value_parser = notation::parse_state)]
+        state: Option<Puzzle>,
+
+        #[clap(short, long)]
+        method: Option<String>,
+
+        #[clap(short, long)]
+        tag: Option<String>,
+
+        #[clap(short, long, value_enum, default_value_t = TableFormat::Tsv)]
+        format: TableFormat,
+    },
+
+    #[clap(about = "Prints every solution in a database as records")]
+    Export {
+        file: String,
+
+        #[clap(short, long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SessionAction {
+    #[clap(about = "Prints the solves of a session file as records")]
+    Export {
+        file: String,
+
+        #[clap(short, long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+    },
+
+    #[clap(about = "Adds records printed by session export to a session file")]
+    Import {
+        #[clap(help = "The session file to add the solves to. It is created if it doesn't exist")]
+        file: String,
+
+        #[clap(help = "The file to read the records from, instead of stdin")]
+        records: Option<String>,
+
+        #[clap(short, long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+    },
+
+    #[clap(about = "Prints the statistics of a session file")]
+    Stats {
+        file: String,
+
+        #[clap(
+            short,
+            long,
+            help = "Also print the statistics of each block of this many solves, to show progress"
+        )]
+        every: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum UrlAction {
+    #[clap(about = "Reads the puzzle state from a link")]
+    Parse {
+        link: Option<String>,
+
+        #[clap(
+            short,
+            long,
+            help = "The template the link was created from. Without a template, the state is read \
+            from the query parameter named state, scramble or puzzle"
+        )]
+        template: Option<String>,
+    },
+}
+
+fn apply(state: &mut Puzzle, alg: &Algorithm) -> Result<(), Box<dyn Error>> {
+    if !state.try_apply_alg(alg) {
+        return Err("the algorithm can not be applied to the state".into());
+    }
+
+    println!("{state}");
+
+    Ok(())
+}
+
+fn apply_cross(states_file: &str, algs_file: &str) -> Result<(), Box<dyn Error>> {
+    let states: Vec<Puzzle> = util::read_file(states_file)?;
+    let algs: Vec<Algorithm> = util::read_file(algs_file)?;
+
+    for state in &states {
+        for alg in &algs {
+            if util::interrupted() {
+                return Ok(());
+            }
+
+            try_func(
+                |s: &mut Puzzle| -> Result<(), Box<dyn Error>> {
+                    if !s.try_apply_alg(alg) {
+                        return Err(format!("\"{alg}\" can not be applied to \"{state}\"").into());
+                    }
+                    println!("{state}\t{alg}\t{s}");
+                    Ok(())
+                },
+                Some(state.clone()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `f` on a state and an algorithm, reading whichever of them isn't given from stdin, or
+/// both of them if `pairs` is true.
+fn for_each_pair<F: Fn(&mut Puzzle, &Algorithm) -> Result<(), Box<dyn Error>>>(
+    state: Option<Puzzle>,
+    alg: Option<Algorithm>,
+    pairs: bool,
+    f: F,
+) -> Result<(), Box<dyn Error>> {
+    match (state, alg) {
+        _ if pairs => loop_func(|p: &mut Pair| f(&mut p.state, &p.alg)),
+        (None, None) => unreachable!(),
+        (None, Some(alg)) => loop_func(|s| f(s, &alg)),
+        (Some(state), None) => loop_func(|a| f(&mut state.clone(), a)),
+        (Some(state), Some(alg)) => try_func(|s| f(s, &alg), Some(state)),
+    }
+}
+
+fn apply_to_solved(alg: &Algorithm, size: Size) -> Result<(), Box<dyn Error>> {
+    let mut state = goal::solved(size);
+    apply(&mut state, alg)
+}
+
+const BLANK_TRAVEL_COLUMNS: &[&str] = &["alg", "distance", "turns", "reversals", "longest_run"];
+
+fn blank_travel(alg: &mut Algorithm, format: Option<TableFormat>) -> String {
+    let start = Instant::now();
+    let directions = grid::directions(alg);
+
+    let (mut turns, mut reversals, mut run, mut longest_run) = (0, 0, 0, 0);
+    let mut previous = None;
+    for dir in directions.iter().copied() {
+        match previous {
+            Some(p) if p == dir => run += 1,
+            Some(p) if p == grid::opposite(dir) => {
+                reversals += 1;
+                run = 1;
+            }
+            Some(_) => {
+                turns += 1;
+                run = 1;
+            }
+            None => run = 1,
+        }
+        longest_run = longest_run.max(run);
+        previous = Some(dir);
+    }
+
+    let distance = directions.len();
+    match format {
+        Some(format) => {
+            let row = [
+                alg.to_string(),
+                distance.to_string(),
+                turns.to_string(),
+                reversals.to_string(),
+                longest_run.to_string(),
+            ];
+            format.row(BLANK_TRAVEL_COLUMNS, &row, start.elapsed())
+        }
+        None => format!(
+            "distance: {distance}  turns: {turns}  reversals: {reversals}  longest run: \
+            {longest_run}"
+        ),
+    }
+}
+
+const COMPARE_COLUMNS: &[&str] = &["state", "method", "length", "nodes", "time_us", "error"];
+
+fn compare_solvers(
+    state: &Puzzle,
+    solvers: &mut compare::Solvers,
+    format: Option<TableFormat>,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let comparison = solvers.compare(state)?;
+    if comparison.disagrees() {
+        util::set_status(Status::Rejected);
+    }
+
+    match format {
+        Some(format) => {
+            for run in &comparison.runs {
+                let row = [
+                    state.to_string(),
+                    run.method.clone(),
+                    run.length.as_ref().map_or(String::new(), u64::to_string),
+                    run.nodes.map_or(String::new(), |n| n.to_string()),
+                    run.time.as_micros().to_string(),
+                    run.length.as_ref().err().cloned().unwrap_or_default(),
+                ];
+                println!("{}", format.row(COMPARE_COLUMNS, &row, start.elapsed()));
+            }
+        }
+        None => println!("{state}\n{comparison}"),
+    }
+
+    Ok(())
+}
 
 fn concat(alg: &mut Algorithm, prefix: &Algorithm, suffix: &Algorithm) {
     println!("{prefix}{alg}{suffix}");
 }
 
-fn embed(state: &Puzzle, target: &mut Puzzle) {
-    if state.try_embed_into(target) {
-        println!("{target}");
-    } else {
-        println!("Invalid");
+fn convert_notation(alg: &mut Algorithm, to: Convention) {
+    // `alg` has already been converted from `--convention` to the tile convention
+    match to {
+        Convention::Tile => println!("{alg}"),
+        Convention::Blank => println!("{}", notation::flip(&alg.to_string())),
+    }
+}
+
+fn corrupt(state: &mut Puzzle, swaps: u64) -> Result<(), Box<dyn Error>> {
+    let gap = state.gap_position();
+    let positions: Vec<u64> = (0..state.area()).filter(|&i| i != gap).collect();
+    if positions.len() < 2 {
+        return Err("the puzzle must contain at least two non-gap pieces".into());
+    }
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..swaps {
+        let (a, b) = {
+            let mut pair = positions.choose_multiple(&mut rng, 2);
+            (*pair.next().unwrap(), *pair.next().unwrap())
+        };
+        state.swap_pieces(a, b);
+    }
+
+    println!("{state}");
+
+    Ok(())
+}
+
+fn embed(state: &Puzzle, target: &mut Puzzle) -> Result<(), Box<dyn Error>> {
+    if !state.try_embed_into(target) {
+        return Err("the state does not fit in the target".into());
+    }
+
+    println!("{target}");
+
+    Ok(())
+}
+
+fn export(
+    input: &str,
+    format: ExportFormat,
+    style: MacroStyle,
+    keys: &[String; 4],
+    delay: u64,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        ExportFormat::Slidysim => println!("{}", SlidysimSolve::from_tsv(input)?),
+        ExportFormat::Macro => {
+            let alg = notation::parse_algorithm(input)?;
+            println!("{}", macros::keypress_macro(&alg, style, keys, delay));
+        }
     }
+
+    Ok(())
 }
 
-fn filter_optimal(alg: &Algorithm, size: Size, keep_suboptimal: bool) {
-    let mut p = Puzzle::new(size);
+fn filter_optimal(
+    alg: &Algorithm,
+    size: Size,
+    solver: &mut SolveFn,
+    keep_suboptimal: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut p = goal::solved(size);
     let inverse = alg.inverse();
 
     if !p.try_apply_alg(&inverse) {
-        return;
+        return Err("the algorithm can not be applied to a puzzle of this size".into());
     }
 
-    let mut solver = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
-    let solution = solver.solve(&p).unwrap();
+    let solution = solver(&p)?;
 
     let alg_len = alg.len_stm::<u64>();
     let opt_len = solution.len_stm::<u64>();
 
     if (alg_len == opt_len) ^ keep_suboptimal {
         println!("{alg}");
+    } else {
+        util::set_status(Status::Rejected);
+    }
+
+    Ok(())
+}
+
+fn filter_solvable(state: &Puzzle, keep_solvable: bool, annotate: bool) {
+    let solvable = goal::is_solvable(state);
+    if solvable == keep_solvable {
+        if annotate {
+            let verdict = if solvable { "solvable" } else { "unsolvable" };
+            println!("{state}\t{verdict}");
+        } else {
+            println!("{state}");
+        }
     }
 }
 
-fn format(alg: &mut Algorithm, long: bool, spaced: bool) {
+fn format(alg: &mut Algorithm, long: bool, spaced: bool) -> String {
     let s = match (long, spaced) {
         (true, true) => alg.display_long_spaced().to_string(),
         (true, false) => alg.display_long_unspaced().to_string(),
         (false, true) => alg.display_short_spaced().to_string(),
         (false, false) => alg.display_short_unspaced().to_string(),
     };
-    println!("{s}");
+
+    match notation::convention() {
+        Convention::Tile => s,
+        Convention::Blank => notation::flip(&s),
+    }
 }
 
 fn format_state(state: &Puzzle, formatter: StateFormatter) {
@@ -339,143 +1851,787 @@ fn format_state(state: &Puzzle, formatter: StateFormatter) {
     }
 }
 
-fn from_solution(alg: &Algorithm, size: Size) {
-    let mut p = Puzzle::new(size);
-    if p.try_apply_alg(&alg.inverse()) {
-        println!("{p}");
-    } else {
-        println!("Invalid");
+fn from_solution(alg: &Algorithm, size: Size) -> Result<(), Box<dyn Error>> {
+    let mut p = goal::solved(size);
+    if !p.try_apply_alg(&alg.inverse()) {
+        return Err("the algorithm can not be applied to a puzzle of this size".into());
     }
+
+    println!("{p}");
+
+    Ok(())
 }
 
-fn generate(number: u64, size: Size, s: impl Scrambler) -> Result<(), Box<dyn Error>> {
+const GENERATE_COLUMNS: &[&str] = &["state"];
+
+fn generate(
+    number: u64,
+    size: Size,
+    s: impl Scrambler,
+    format: Option<TableFormat>,
+) -> Result<(), Box<dyn Error>> {
     let mut p = Puzzle::new(size);
+    let mut out = std::io::stdout().lock();
+
+    if let Some(header) = format.and_then(|f| f.header(GENERATE_COLUMNS)) {
+        writeln!(out, "{header}")?;
+    }
 
+    let mut progress = Progress::new("generate", Some(number));
     for _ in 0..number {
+        if util::interrupted() {
+            break;
+        }
+        let start = Instant::now();
         p.reset();
         s.scramble(&mut p);
-        println!("{p}");
+        // The scramblers start from the usual solved state
+        let state = goal::from_standard(&p);
+        match format {
+            Some(format) => writeln!(
+                out,
+                "{}",
+                format.row(GENERATE_COLUMNS, &[state.to_string()], start.elapsed())
+            )?,
+            None => writeln!(out, "{state}")?,
+        }
+        progress.inc();
     }
 
     Ok(())
 }
 
-fn invert(alg: &mut Algorithm) {
+fn heuristic(state: &Puzzle, heuristics: &mut Heuristics, heuristic: HeuristicType, all: bool) {
+    if !goal::is_solvable(state) {
+        util::set_status(Status::Unsolvable);
+        println!("Unsolvable");
+    } else if all {
+        let row: Vec<_> = HeuristicType::value_variants()
+            .iter()
+            .map(|&h| match heuristics.bound(h, state) {
+                Some(b) => format!("{}={b}", h.name()),
+                None => format!("{}=-", h.name()),
+            })
+            .collect();
+        println!("{}", row.join(" "));
+    } else {
+        match heuristics.bound(heuristic, state) {
+            Some(b) => println!("{b}"),
+            None => println!("Unavailable"),
+        }
+    }
+}
+
+fn import(solve: &SlidysimSolve, format: SimFormat) {
+    match format {
+        SimFormat::Slidysim => println!("{}", solve.to_tsv()),
+    }
+}
+
+fn invert(alg: &mut Algorithm) -> String {
     alg.invert();
-    println!("{alg}");
+    alg.to_string()
+}
+
+const LENGTH_COLUMNS: &[&str] = &["alg", "length"];
+
+fn length(
+    alg: &mut Algorithm,
+    metric: Metric,
+    weighted: Option<(&Weights, Size)>,
+    format: Option<TableFormat>,
+) -> Result<String, util::SendError> {
+    let start = Instant::now();
+    let len: u64 = match (weighted, metric) {
+        (Some((weights, size)), _) => weighted::solution_cost(alg, size, weights)?,
+        (None, Metric::Stm) => alg.len_stm(),
+        (None, Metric::Mtm) => alg.len_mtm(),
+    };
+
+    Ok(match format {
+        Some(format) => {
+            let row = [alg.to_string(), len.to_string()];
+            format.row(LENGTH_COLUMNS, &row, start.elapsed())
+        }
+        None => len.to_string(),
+    })
+}
+
+const MD_COLUMNS: &[&str] = &["state", "md"];
+
+fn md(state: &mut Puzzle, breakdown: bool, format: Option<TableFormat>) -> String {
+    if let Some(format) = format {
+        let start = Instant::now();
+        let md = if goal::is_solvable(state) {
+            heuristic::manhattan_distance(state).to_string()
+        } else {
+            util::set_status(Status::Unsolvable);
+            "Unsolvable".to_string()
+        };
+        let row = [state.to_string(), md];
+        return format.row(MD_COLUMNS, &row, start.elapsed());
+    }
+
+    if !goal::is_solvable(state) {
+        util::set_status(Status::Unsolvable);
+        return "Unsolvable".to_string();
+    }
+
+    let b = heuristic::manhattan_distance(state);
+    let mut lines = vec![b.to_string()];
+
+    if breakdown {
+        let size = state.size();
+        let (width, _) = size.into();
+        let distances: Vec<u64> = grid::pieces(state)
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| match p {
+                0 => 0,
+                _ => grid::manhattan(
+                    (i as u64 % width, i as u64 / width),
+                    goal::solved_pos_xy(p, size),
+                ),
+            })
+            .collect();
+
+        let pad = distances.iter().max().unwrap().to_string().len();
+        for row in distances.chunks(width as usize) {
+            let row: Vec<_> = row.iter().map(|d| format!("{d:>pad$}")).collect();
+            lines.push(row.join(" "));
+        }
+    }
+
+    lines.join("\n")
 }
 
-fn length(alg: &mut Algorithm, metric: Metric) {
-    let len: u64 = match metric {
-        Metric::Stm => alg.len_stm(),
-        Metric::Mtm => alg.len_mtm(),
+fn mangen(output_dir: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let write = |name: &str, command: clap::Command| -> Result<(), Box<dyn Error>> {
+        let path = Path::new(output_dir).join(format!("{name}.1"));
+        let mut file = std::fs::File::create(path)?;
+        clap_mangen::Man::new(command.name(name.to_string())).render(&mut file)?;
+        Ok(())
     };
-    println!("{len}");
+
+    let command = Args::command();
+    write("slidy", command.clone())?;
+    for subcommand in command.get_subcommands().filter(|c| !c.is_hide_set()) {
+        write(
+            &format!("slidy-{}", subcommand.get_name()),
+            subcommand.clone(),
+        )?;
+    }
+
+    Ok(())
 }
 
-fn md(state: &mut Puzzle) {
+fn masked_md(state: &MaskedPuzzle, verbose: bool) {
     if state.is_solvable() {
-        let b: u64 = ManhattanDistance(&RowGrids).bound(state);
-        println!("{b}");
+        println!("{}", state.known_manhattan_distance());
+        if verbose {
+            println!(
+                "{} known pieces, {} unknown pieces",
+                state.num_known(),
+                state.num_unknown()
+            );
+        }
     } else {
+        util::set_status(Status::Unsolvable);
         println!("Unsolvable");
     }
 }
 
-fn opt_diff(alg: &Algorithm, size: Size) {
-    let mut p = Puzzle::new(size);
-    p.apply_alg(&alg.inverse());
+fn masked_solve(state: &MaskedPuzzle, verbose: bool) {
+    let Some(a) = state.solve() else {
+        util::set_status(Status::Unsolvable);
+        println!("Unsolvable");
+        return;
+    };
+
+    println!("{a}");
+
+    if verbose {
+        println!("{} moves", a.len_stm::<u64>());
+    }
+}
+
+fn multi_apply(
+    state: &mut MultiBlankPuzzle,
+    alg: &MultiBlankAlgorithm,
+) -> Result<(), Box<dyn Error>> {
+    if !state.try_apply_alg(alg) {
+        return Err("the algorithm can not be applied to the state".into());
+    }
+
+    println!("{state}");
+
+    Ok(())
+}
+
+fn multi_generate(number: u64, size: Size, blanks: u64) {
+    let mut p = MultiBlankPuzzle::new(size, blanks);
+
+    for _ in 0..number {
+        loop {
+            p.randomize();
+            if p.is_solvable() {
+                break;
+            }
+        }
+        println!("{p}");
+    }
+}
+
+fn multi_solve(state: &MultiBlankPuzzle, verbose: bool) {
+    let Some(a) = state.solve() else {
+        util::set_status(Status::Unsolvable);
+        println!("Unsolvable");
+        return;
+    };
+
+    println!("{a}");
+
+    if verbose {
+        println!("{} moves", a.len_stm());
+    }
+}
+
+const OPT_DIFF_COLUMNS: &[&str] = &["alg", "length", "optimal", "diff"];
+
+fn opt_diff(
+    alg: &Algorithm,
+    size: Size,
+    solver: &mut SolveFn,
+    weights: Option<&Weights>,
+    format: Option<TableFormat>,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+
+    let mut p = goal::solved(size);
+    if !p.try_apply_alg(&alg.inverse()) {
+        return Err("the algorithm can not be applied to a puzzle of this size".into());
+    }
+
+    let (alg_len, opt_len) = match weights {
+        Some(weights) => (
+            weighted::solution_cost(alg, size, weights)?,
+            weighted::solve(&p, weights)?.cost,
+        ),
+        None => (alg.len_stm::<u64>(), solver(&p)?.len_stm::<u64>()),
+    };
+
+    match format {
+        Some(format) => println!(
+            "{}",
+            format.row(
+                OPT_DIFF_COLUMNS,
+                &[
+                    alg.to_string(),
+                    alg_len.to_string(),
+                    opt_len.to_string(),
+                    (alg_len - opt_len).to_string(),
+                ],
+                start.elapsed()
+            )
+        ),
+        None => println!("{}", alg_len - opt_len),
+    }
+
+    Ok(())
+}
+
+const GRADE_COLUMNS: &[&str] = &[
+    "state",
+    "solution",
+    "length",
+    "optimal",
+    "surplus",
+    "efficiency",
+];
+
+/// The totals of the solutions graded so far.
+#[derive(Default)]
+struct Grades {
+    count: u64,
+    optimal: u64,
+    invalid: u64,
+    surplus: u64,
+    efficiency: f64,
+}
+
+impl std::fmt::Display for Grades {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "graded: {}  optimal: {}", self.count, self.optimal)?;
+        if self.count > 0 {
+            write!(
+                f,
+                "  mean efficiency: {:.2}%  mean surplus: {:.2}  total surplus: {}",
+                self.efficiency / self.count as f64,
+                self.surplus as f64 / self.count as f64,
+                self.surplus,
+            )?;
+        }
+        if self.invalid > 0 {
+            write!(f, "  invalid: {}", self.invalid)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn grade(
+    pair: &Pair,
+    solver: &mut SolveFn,
+    grades: &mut Grades,
+    format: Option<TableFormat>,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let mut state = pair.state.clone();
+    if !state.try_apply_alg(&pair.alg) || !goal::is_solved(&state) {
+        // Counted rather than returned as an error, so that the summary is always printed
+        grades.invalid += 1;
+        let error: Box<dyn Error> = "the solution doesn't solve the state".into();
+        util::report_rejected(&pair.state.to_string(), error.as_ref());
+        return Ok(());
+    }
+
+    let length = pair.alg.len_stm::<u64>();
+    let optimal = solver(&pair.state)?.len_stm::<u64>();
+    let surplus = length - optimal;
+    let efficiency = if length == 0 {
+        100.0
+    } else {
+        optimal as f64 * 100.0 / length as f64
+    };
+
+    grades.count += 1;
+    grades.optimal += u64::from(surplus == 0);
+    grades.surplus += surplus;
+    grades.efficiency += efficiency;
+
+    match format {
+        Some(format) => {
+            let row = [
+                pair.state.to_string(),
+                pair.alg.to_string(),
+                length.to_string(),
+                optimal.to_string(),
+                surplus.to_string(),
+                format!("{efficiency:.2}"),
+            ];
+            println!("{}", format.row(GRADE_COLUMNS, &row, start.elapsed()));
+        }
+        None => println!(
+            "length: {length}  optimal: {optimal}  surplus: {surplus}  efficiency: {efficiency:.2}%"
+        ),
+    }
+
+    Ok(())
+}
 
+fn optimize(alg: &mut Algorithm, length: u64) -> Result<(), Box<dyn Error>> {
     let mut solver = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
-    let solution = solver.solve(&p).unwrap();
+    let mut progress = Progress::new("optimize", None);
+    let mut idx = 0;
+    while idx + length <= alg.len_stm() && !util::interrupted() {
+        progress.set(idx, Some(alg.len_stm::<u64>() - length + 1));
 
-    let alg_len = alg.len_stm::<u64>();
-    let opt_len = solution.len_stm::<u64>();
+        let slice = alg.try_slice(idx..idx + length)?;
+        let Some(size) = slice.min_applicable_size() else {
+            idx += 1;
+            continue;
+        };
+        let mut puzzle = Puzzle::new(size);
+        puzzle.apply_alg(&slice);
+
+        let solution = solver.solve(&puzzle)?;
+
+        if solution.len_stm::<u64>() == length {
+            idx += 1;
+        } else {
+            let mut start = Algorithm::from(alg.try_slice(0..idx)?);
+            let middle = solution.inverse();
+            let end = Algorithm::from(alg.try_slice(idx + length..alg.len_stm())?);
+            start += middle;
+            start += end;
+
+            *alg = start;
+        }
+    }
+    drop(progress);
+
+    println!("{alg}");
+
+    Ok(())
+}
+
+fn render(
+    state: &mut Puzzle,
+    alg: Option<&Algorithm>,
+    options: &RenderOptions,
+    output: Option<&str>,
+    frames_dir: Option<&str>,
+    overlay: Overlay,
+    caption: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let options = &options.fitted(state.size());
+
+    if let Some(dir) = frames_dir {
+        std::fs::create_dir_all(dir)?;
+
+        let mut frame = state.clone();
+        let directions = alg.map(grid::directions).unwrap_or_default();
+        for i in 0..=directions.len() {
+            if i > 0 && !frame.try_move_dir(directions[i - 1]) {
+                return Err("the algorithm can not be applied to the state".into());
+            }
+            let svg = render::document(&frame, options)?;
+            let extension = options.format.unwrap_or(OutputFormat::Svg).extension();
+            let path = Path::new(dir).join(format!("frame_{:04}.{extension}", i + 1));
+            render::save(path, &svg, options)?;
+        }
+    }
+
+    if let Some(output) = output {
+        let svg = match (alg, overlay) {
+            (Some(alg), Overlay::MoveArrows(n)) => {
+                let svg = render::document(state, options)?;
+                overlay::move_arrows(svg, state, alg, n, options)?
+            }
+            (Some(alg), Overlay::BlankPath { numbered }) => {
+                let svg = render::document(state, options)?;
+                overlay::blank_path(svg, state, alg, numbered, options)?
+            }
+            (Some(alg), Overlay::None) => {
+                if !state.try_apply_alg(alg) {
+                    return Err("the algorithm can not be applied to the state".into());
+                }
+                render::document(state, options)?
+            }
+            (None, _) => render::document(state, options)?,
+        };
+        let svg = match caption {
+            Some(caption) => render::caption(svg, caption, options),
+            None => svg,
+        };
+
+        render::save(output, &svg, options)?;
+    }
+
+    Ok(())
+}
+
+fn render_term(
+    state: &mut Puzzle,
+    alg: Option<&Algorithm>,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(alg) = alg {
+        if !state.try_apply_alg(alg) {
+            return Err("the algorithm can not be applied to the state".into());
+        }
+    }
+
+    println!("{}", render::terminal(state, options)?);
+
+    Ok(())
+}
+
+fn render_sheet(
+    states: &[Puzzle],
+    options: &RenderOptions,
+    columns: u64,
+    caption: Option<&str>,
+    caption_from_input: bool,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let documents = states
+        .iter()
+        .map(|s| {
+            let options = options.fitted(s.size());
+            let svg = render::document(s, &options)?;
+            Ok(match (caption, caption_from_input) {
+                (_, true) => render::caption(svg, &s.to_string(), &options),
+                (Some(caption), false) => render::caption(svg, caption, &options),
+                (None, false) => svg,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    let svg = render::sheet(documents, columns, options.tile_size / 2.0);
+    render::save(output, &svg, options)?;
+
+    Ok(())
+}
+
+fn render_before_after(
+    state: &mut Puzzle,
+    alg: &Algorithm,
+    options: &RenderOptions,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let options = &options.fitted(state.size());
+
+    let before = render::document(state, options)?;
+    if !state.try_apply_alg(alg) {
+        return Err("the algorithm can not be applied to the state".into());
+    }
+    let after = render::document(state, options)?;
+
+    let svg = overlay::before_after(before, after, options);
+    render::save(output, &svg, options)?;
+
+    Ok(())
+}
+
+fn render_labels(
+    size: Size,
+    options: &RenderOptions,
+    columns: u64,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let state = Puzzle::new(size);
+    let options = options.fitted(size);
+
+    // Labels that can't be used with this size (e.g. grids on a puzzle too small to split) are
+    // left out
+    let documents: Vec<_> = LabelType::value_variants()
+        .iter()
+        .filter_map(|&label| {
+            let options = RenderOptions {
+                label,
+                ..options.clone()
+            };
+            let svg = render::document(&state, &options).ok()?;
+            let name = label.to_possible_value()?.get_name().to_string();
+            Some(render::caption(svg, &name, &options))
+        })
+        .collect();
+
+    let svg = render::sheet(documents, columns, options.tile_size / 2.0);
+    render::save(output, &svg, &options)?;
+
+    Ok(())
+}
+
+fn practice_sheet(
+    count: u64,
+    size: Size,
+    columns: u64,
+    answers: bool,
+    seed: u64,
+    options: &RenderOptions,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let states = bench::states(size, count, seed)?;
+    let options = options.fitted(size);
+    let spacing = options.tile_size / 2.0;
+
+    let documents = states
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let svg = render::document(s, &options)?;
+            Ok(render::caption(svg, &(i + 1).to_string(), &options))
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    let svg = render::sheet(documents, columns, spacing);
+
+    let svg = if answers {
+        let mut solver = labelled_solver(LabelType::RowGrids)?;
+        let mut progress = Progress::new("solve", Some(count));
+        let mut lines = Vec::new();
+        for (i, s) in states.iter().enumerate() {
+            let solution = solver(s)?;
+            lines.push(format!(
+                "{}. {solution} ({} moves)",
+                i + 1,
+                solution.len_stm::<u64>()
+            ));
+            progress.inc();
+        }
+        drop(progress);
+
+        let (width, _) = render::document_size(&svg).unwrap_or_default();
+        let key = render::text_page(&lines, width, &options);
+        render::sheet(vec![svg, key], 1, spacing)
+    } else {
+        svg
+    };
+
+    render::save(output, &svg, &options)?;
+
+    Ok(())
+}
+
+fn render_solve(
+    state: &mut Puzzle,
+    alg: &Algorithm,
+    options: &RenderOptions,
+    frame_duration: u16,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let options = &options.fitted(state.size());
+
+    if output.ends_with(".svg") {
+        let svg = animated_svg::document(state, alg, options, frame_duration)?;
+        render::save(output, &svg, options)?;
+        return Ok(());
+    }
+
+    let mut frames = vec![render::document(state, options)?];
+    for dir in grid::directions(alg) {
+        if !state.try_move_dir(dir) {
+            return Err("the algorithm can not be applied to the state".into());
+        }
+        frames.push(render::document(state, options)?);
+    }
+
+    animate::save(output, &frames, frame_duration)?;
+
+    Ok(())
+}
+
+fn pipeline(stages: Vec<Stage>) -> Result<(), Box<dyn Error>> {
+    let pipeline = Pipeline::new(stages)?;
+
+    match pipeline.input() {
+        None => pipeline.run_generated(),
+        Some(Kind::State) => loop_func(|s: &mut Puzzle| pipeline.run(Value::State(s.clone()))),
+        Some(Kind::Alg) => loop_func(|a: &mut Algorithm| pipeline.run(Value::Alg(a.clone()))),
+        Some(Kind::Number) => unreachable!(),
+    }
+}
+
+fn reconstruct(reconstruction: &Reconstruction, pause: f64) {
+    let (time, moves) = (reconstruction.time(), reconstruction.moves());
+
+    println!("Solution: {}", reconstruction.solution());
+    println!("Time: {time:.3}");
+    println!("Moves: {moves}");
+    println!("TPS: {:.3}", reconstruct::tps(moves, time));
+
+    let splits = reconstruction.splits();
+    if splits.len() > 1 {
+        for (i, (time, moves)) in splits.into_iter().enumerate() {
+            let tps = reconstruct::tps(moves, time);
+            println!("Phase {}: {time:.3} ({moves} moves, {tps:.3} TPS)", i + 1);
+        }
+    }
+
+    let pauses = reconstruction.pauses(pause);
+    let total: f64 = pauses.iter().map(|p| p.length).sum();
+    println!("Pauses: {} ({total:.3})", pauses.len());
+    for p in pauses {
+        println!("  {p}");
+    }
+}
+
+fn render_traffic(
+    state: &Puzzle,
+    alg: &Algorithm,
+    tile_size: f32,
+    palette: Option<Palette>,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let counts = traffic::counts(state, alg)?;
+    let svg = traffic::document(state.size(), &counts, tile_size, palette.map(Gradient::new));
+    svg::save(output, &svg)?;
 
-    println!("{}", alg_len - opt_len);
+    Ok(())
 }
 
-fn optimize(alg: &mut Algorithm, length: u64) -> Result<(), Box<dyn Error>> {
-    let mut idx = 0;
-    while idx + length <= alg.len_stm() {
-        let slice = alg.try_slice(idx..idx + length)?;
-        let Some(size) = slice.min_applicable_size() else {
-            idx += 1;
-            continue;
-        };
-        let mut puzzle = Puzzle::new(size);
-        puzzle.apply_alg(&slice);
-
-        let mut solver = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
-        let solution = solver.solve(&puzzle)?;
+fn relay_solve(relay: &mut Relay, solver: &mut SolveFn) -> Result<(), Box<dyn Error>> {
+    if relay.states.is_empty() {
+        return Ok(());
+    }
 
-        if solution.len_stm::<u64>() == length {
-            idx += 1;
-        } else {
-            let mut start = Algorithm::from(alg.try_slice(0..idx)?);
-            let middle = solution.inverse();
-            let end = Algorithm::from(alg.try_slice(idx + length..alg.len_stm())?);
-            start += middle;
-            start += end;
+    if let Some(i) = relay.states.iter().position(|s| !goal::is_solvable(s)) {
+        util::set_status(Status::Unsolvable);
+        return Err(format!("puzzle {} is unsolvable", i + 1).into());
+    }
 
-            *alg = start;
-        }
+    let mut solutions = Vec::new();
+    for state in &relay.states {
+        solutions.push(solver(state)?);
     }
 
-    println!("{alg}");
+    let mut total = 0;
+    for (state, solution) in relay.states.iter().zip(&solutions) {
+        let length = solution.len_stm::<u64>();
+        total += length;
+        println!("{}: {solution} ({length} moves)", state.size());
+    }
+    println!("total: {total} moves\n");
 
     Ok(())
 }
 
-fn render(
-    state: &Puzzle,
+fn rescramble(
+    state: &mut Puzzle,
     label_type: LabelType,
-    coloring_type: ColoringType,
-    tile_size: f32,
-    output: &str,
+    classes: &[u64],
 ) -> Result<(), Box<dyn Error>> {
-    let grid_size = {
-        let (width, height) = state.size().into();
-        (width.div_ceil(2), height.div_ceil(2))
-    };
+    let size = state.size();
+    let label = boxed_label(label_type, size)?;
+    let mut pieces = grid::pieces(state);
+
+    let positions: Vec<usize> = pieces
+        .iter()
+        .enumerate()
+        .filter(|&(_, &p)| {
+            p != 0 && classes.contains(&label.position_label(size, goal::solved_pos_xy(p, size)))
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut selected: Vec<u64> = positions.iter().map(|&i| pieces[i]).collect();
+    selected.shuffle(&mut rand::thread_rng());
+    for (&i, p) in positions.iter().zip(selected) {
+        pieces[i] = p;
+    }
 
-    let label: Box<dyn Label> = match label_type {
-        LabelType::RowGrids => Box::new(RowGrids),
-        LabelType::Rows => Box::new(Rows),
-        LabelType::Fringe => Box::new(Fringe),
-        LabelType::SquareFringe => Box::new(SquareFringe),
-        LabelType::SplitFringe => Box::new(SplitFringe),
-        LabelType::SplitSquareFringe => Box::new(SplitSquareFringe),
-        LabelType::Diagonals => Box::new(Diagonals),
-        LabelType::Checkerboard => Box::new(Checkerboard),
-        LabelType::Grids => Box::new(Scaled::new(RowGrids, grid_size)?),
-    };
+    let mut new_state = grid::from_pieces(&pieces, size)?;
+    if new_state.is_solvable() != state.is_solvable() {
+        if let [a, b, ..] = positions[..] {
+            new_state.swap_pieces(a as u64, b as u64);
+        }
+    }
 
-    let coloring: Rc<dyn Coloring> = match coloring_type {
-        ColoringType::None => Rc::new(Monochrome::new(Rgba::new(0.0, 0.0, 0.0, 0.0))),
-        ColoringType::Rainbow => Rc::new(Rainbow::default()),
-    };
+    println!("{new_state}");
 
-    let mut schemes: Vec<Box<dyn ColorScheme>> =
-        vec![Box::new(Scheme::new(label, coloring.clone()))];
-    if label_type == LabelType::Grids {
-        let grid_size = Size::new(grid_size.0, grid_size.1)?;
+    Ok(())
+}
 
-        schemes.push(Box::new(Tiled::new(
-            Scheme::new(SplitFringe, coloring.clone()),
-            grid_size,
-        )))
+fn size(input: &str) -> Result<(), Box<dyn Error>> {
+    if let Ok(state) = notation::parse_state(input) {
+        let (width, height) = state.size().into();
+        println!("{width}x{height} ({} tiles)", state.num_pieces());
+    } else {
+        let alg = notation::parse_algorithm(input)?;
+        match alg.min_applicable_size() {
+            Some(size) => {
+                let (width, height) = size.into();
+                println!("{width}x{height}");
+            }
+            None => println!("Any"),
+        }
     }
 
-    let scheme_list = SchemeList::new(&schemes)?;
-
-    let renderer: Renderer<_, _, _> = RendererBuilder::with_scheme(&scheme_list)
-        .text(Text::default().font_size(tile_size * 30.0 / 75.0))
-        .tile_size(tile_size)
-        .build();
+    Ok(())
+}
 
-    let svg = renderer.render(state)?;
-    svg::save(output, &svg)?;
+fn print_schema(command: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match command {
+        Some(command) => {
+            let schema = schema::schema(command)
+                .ok_or_else(|| format!("{command} has no structured output"))?;
+            println!("{schema}");
+        }
+        None => println!("{}", schema::all()),
+    }
 
     Ok(())
 }
@@ -503,73 +2659,369 @@ fn slice(alg: &mut Algorithm, start: u64, end: Option<u64>) -> Result<(), Box<dy
     Ok(())
 }
 
-fn solvable(state: &mut Puzzle) {
-    println!("{}", state.is_solvable());
+fn solvable(state: &mut Puzzle, filter: bool) -> Option<String> {
+    let solvable = goal::is_solvable(state);
+    if !solvable {
+        util::set_status(Status::Unsolvable);
+    }
+
+    if filter {
+        solvable.then(|| state.to_string())
+    } else {
+        Some(solvable.to_string())
+    }
 }
 
-fn solve(state: &mut Puzzle, label: LabelType, verbose: bool) -> Result<(), Box<dyn Error>> {
-    let a = match label {
-        LabelType::RowGrids => {
-            let mut s = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
-            s.solve(state)?
-        }
-        LabelType::Rows => {
-            let mut s = Solver::new(&ManhattanDistance(&Rows), &Rows);
-            s.solve(state)?
+const SOLVE_COLUMNS: &[&str] = &["state", "solution", "length"];
+
+fn solve(
+    state: &mut Puzzle,
+    solver: &mut SolveFn,
+    verbose: bool,
+    format: Option<TableFormat>,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    if !goal::is_solvable(state) {
+        util::set_status(Status::Unsolvable);
+    }
+
+    let result = solver(state);
+
+    if let Some(format) = format {
+        // Unsolvable states still get a record, so that every input has one
+        let row = match &result {
+            Ok(a) => [
+                state.to_string(),
+                a.to_string(),
+                a.len_stm::<u64>().to_string(),
+            ],
+            Err(e) => [state.to_string(), e.to_string(), String::new()],
+        };
+        println!("{}", format.row(SOLVE_COLUMNS, &row, start.elapsed()));
+        return Ok(());
+    }
+
+    let a = result?;
+    println!("{a}");
+
+    if verbose {
+        println!("{} moves", a.len_stm::<u64>());
+    }
+
+    Ok(())
+}
+
+const DB_COLUMNS: &[&str] = &["state", "solution", "length", "method", "tags"];
+
+fn db_command(action: DbAction) -> Result<(), Box<dyn Error>> {
+    let print = |entries: Vec<db::Entry>, format: TableFormat| {
+        print_header(Some(format), DB_COLUMNS);
+        for entry in entries {
+            println!(
+                "{}",
+                format.row(DB_COLUMNS, &entry.record(), Duration::ZERO)
+            );
         }
-        LabelType::Fringe => {
-            let mut s = Solver::new(&ManhattanDistance(&Fringe), &Fringe);
-            s.solve(state)?
+    };
+
+    match action {
+        DbAction::Add { file, method, tags } => {
+            let db = db::Database::open(&file)?;
+            loop_func(|p: &mut Pair| -> Result<(), Box<dyn Error>> {
+                let mut state = p.state.clone();
+                if !state.try_apply_alg(&p.alg) || !goal::is_solved(&state) {
+                    util::set_status(Status::Rejected);
+                    return Err("the solution doesn't solve the state".into());
+                }
+                db.add(&db::Entry {
+                    state: p.state.clone(),
+                    solution: p.alg.clone(),
+                    method: method.clone(),
+                    tags: tags.clone(),
+                })
+            })?;
         }
-        LabelType::SquareFringe => {
-            let mut s = Solver::new(&ManhattanDistance(&SquareFringe), &SquareFringe);
-            s.solve(state)?
+        DbAction::Query {
+            file,
+            state,
+            method,
+            tag,
+            format,
+        } => {
+            let query = db::Query {
+                state: state.as_ref(),
+                method: method.as_deref(),
+                tag: tag.as_deref(),
+            };
+            print(db::Database::open(&file)?.query(&query)?, format);
         }
-        LabelType::SplitFringe => {
-            let mut s = Solver::new(&ManhattanDistance(&SplitFringe), &SplitFringe);
-            s.solve(state)?
+        DbAction::Export { file, format } => {
+            if !Path::new(&file).exists() {
+                return Err(format!("{file}: no such database").into());
+            }
+            print(
+                db::Database::open(&file)?.query(&db::Query::default())?,
+                format,
+            );
         }
-        LabelType::SplitSquareFringe => {
-            let mut s = Solver::new(&ManhattanDistance(&SplitSquareFringe), &SplitSquareFringe);
-            s.solve(state)?
+    }
+
+    Ok(())
+}
+
+fn session_command(action: SessionAction) -> Result<(), Box<dyn Error>> {
+    match action {
+        SessionAction::Export { file, format } => {
+            if !Path::new(&file).exists() {
+                return Err(format!("{file}: no such session file").into());
+            }
+            Session::open(Some(&file))?.export(format);
         }
-        LabelType::Diagonals => {
-            let mut s = Solver::new(&ManhattanDistance(&Diagonals), &Diagonals);
-            s.solve(state)?
+        SessionAction::Import {
+            file,
+            records,
+            format,
+        } => {
+            let mut session = Session::open(Some(&file))?;
+            match records {
+                Some(records) => {
+                    let reader = io::BufReader::new(
+                        std::fs::File::open(&records).map_err(|e| format!("{records}: {e}"))?,
+                    );
+                    session.import(reader, format)?;
+                }
+                None => session.import(io::stdin().lock(), format)?,
+            }
         }
-        LabelType::Checkerboard => {
-            let mut s = Solver::new(&ManhattanDistance(&Checkerboard), &Checkerboard);
-            s.solve(state)?
+        SessionAction::Stats { file, every } => {
+            if !Path::new(&file).exists() {
+                return Err(format!("{file}: no such session file").into());
+            }
+            println!("{}", Session::open(Some(&file))?.summary(every));
         }
-        LabelType::Grids => unimplemented!(),
+    }
+
+    Ok(())
+}
+
+fn print_header(format: Option<TableFormat>, columns: &[&str]) {
+    if let Some(header) = format.and_then(|f| f.header(columns)) {
+        println!("{header}");
+    }
+}
+
+fn tile_path(state: &mut Puzzle, alg: &Algorithm, tile: u64) -> Result<(), Box<dyn Error>> {
+    if tile == 0 || tile >= state.area() {
+        return Err(format!("there is no tile {tile} in a puzzle of this size").into());
+    }
+
+    let (width, _) = state.size().into();
+    let position = |state: &Puzzle| {
+        let idx = grid::pieces(state)
+            .iter()
+            .position(|&p| p == tile)
+            .unwrap_or(0) as u64;
+        (idx % width, idx / width)
     };
 
-    println!("{a}");
+    let mut path = vec![position(state)];
+    for dir in grid::directions(alg) {
+        if !state.try_move_dir(dir) {
+            return Err("the algorithm can not be applied to the state".into());
+        }
+        let pos = position(state);
+        if path.last() != Some(&pos) {
+            path.push(pos);
+        }
+    }
+
+    let path: Vec<String> = path.iter().map(|(x, y)| format!("{x},{y}")).collect();
+    println!("{}", path.join(" "));
+
+    Ok(())
+}
+
+fn verify(state: &mut Puzzle, alg: &Algorithm) -> Result<(), Box<dyn Error>> {
+    if !state.try_apply_alg(alg) {
+        return Err("the algorithm can not be applied to the state".into());
+    }
+
+    let solved = goal::is_solved(state);
+    if !solved {
+        util::set_status(Status::Rejected);
+    }
+    println!("{solved}");
+
+    Ok(())
+}
+
+fn url(state: &Puzzle, template: &str) {
+    println!("{}", url::link(template, state));
+}
+
+fn url_parse(link: &str, template: Option<&str>) -> Result<(), Box<dyn Error>> {
+    println!("{}", url::parse(link.trim(), template)?);
+
+    Ok(())
+}
+
+fn validate_state(state: &str, size: Option<Size>) {
+    let problems = validate::validate(state, size);
+    if problems.is_empty() {
+        println!("Valid");
+    } else {
+        println!("Invalid");
+        for problem in problems {
+            println!("  {problem}");
+        }
+    }
+}
+
+fn weighted_solve(state: &Puzzle, weights: &Weights, verbose: bool) -> Result<(), Box<dyn Error>> {
+    if !goal::is_solvable(state) {
+        util::set_status(Status::Unsolvable);
+        return Err("the state is unsolvable".into());
+    }
+
+    let solution = weighted::solve(state, weights)?;
+    println!("{}", solution.alg);
 
     if verbose {
-        println!("{} moves", a.len_stm::<u64>());
+        println!(
+            "cost {}, {} moves, {} states expanded",
+            solution.cost,
+            solution.alg.len_stm::<u64>(),
+            solution.nodes
+        );
     }
 
     Ok(())
 }
 
 fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let on_error = if args.skip_invalid {
+        ErrorPolicy::Continue
+    } else {
+        args.on_error
+    };
+    util::set_error_options(args.error_format, on_error);
+    util::set_inputs(args.input);
+    util::set_logging(args.verbose, args.time);
+    util::set_jobs(args.jobs);
+    if let Some(bytes) = args.max_memory {
+        memory::set_limit(bytes);
+    }
+    if args.progress {
+        progress::enable();
+    }
+    goal::set_home(args.blank_home);
+    // The notation options are read before the rest of the arguments, by `notation::configure`
+    debug_assert_eq!(notation::options(), args.notation);
+
     match args.command {
-        Command::Apply { state, alg } => match (state, alg) {
-            (None, None) => unreachable!(),
-            (None, Some(alg)) => loop_func(|s| apply(s, &alg)),
-            (Some(state), None) => loop_func(|a| apply(&mut state.clone(), a)),
-            (Some(mut state), Some(alg)) => {
-                apply(&mut state, &alg);
-                Ok(())
+        Command::Analyze {
+            state,
+            alg,
+            reconstruction,
+            solve_time,
+            label,
+            chart,
+            chart_value,
+            wasted,
+            window,
+        } => {
+            let label = boxed_label(label, state.size())?;
+            let (alg, analysis) = match (alg, reconstruction) {
+                (_, Some(r)) => {
+                    let alg = r.solution();
+                    let analysis = analyze::analyze(&state, &alg, label.as_ref())?;
+                    (alg, analysis.with_move_times(r.move_times()))
+                }
+                (Some(alg), None) => {
+                    let analysis = analyze::analyze(&state, &alg, label.as_ref())?;
+                    match solve_time {
+                        Some(time) => (alg, analysis.with_time(time)),
+                        None => (alg, analysis),
+                    }
+                }
+                (None, None) => unreachable!(),
+            };
+            println!("{analysis}");
+
+            if let Some(output) = chart {
+                let values = analyze::chart_values(&state, &alg, chart_value, label.as_ref())?;
+                let marks: Vec<usize> = analysis.phases.iter().map(|p| p.end).collect();
+                let svg = chart::line_chart(&values, chart_value.name(), &marks);
+                svg::save(output, &svg)?;
+            }
+
+            if wasted {
+                let waste = analyze::wasted(&state, &alg, window)?;
+                if waste.is_empty() {
+                    println!("\nno wasted moves found");
+                } else {
+                    println!("\nwasted moves:");
+                    for w in &waste {
+                        println!("{w}");
+                    }
+                    let saving: usize = waste.iter().map(analyze::Waste::saving).sum();
+                    println!("total saving: {saving} moves");
+                }
             }
+            Ok(())
+        }
+        Command::Apply {
+            state,
+            alg,
+            pairs,
+            cross,
+            states_file,
+            algs_file,
+        } => match (states_file, algs_file) {
+            (Some(states), Some(algs)) if cross => apply_cross(&states, &algs),
+            _ => for_each_pair(state, alg, pairs, apply),
         },
         Command::ApplyToSolved { alg, size } => try_func(|a| apply_to_solved(a, size), alg),
+        Command::Avg { window } => {
+            println!("{}", session::averages(&read_all::<Score>()?, &window));
+            Ok(())
+        }
+        Command::Bench {
+            command,
+            size,
+            samples,
+            seed,
+        } => {
+            let states = bench::states(size, samples, seed)?;
+            println!("{}", bench::report(&bench::run(command, &states)?));
+            Ok(())
+        }
+        Command::BlankTravel { alg, format } => {
+            print_header(format, BLANK_TRAVEL_COLUMNS);
+            par_try_func(|a| Ok(Some(blank_travel(a, format))), alg)
+        }
+        Command::CompareSolvers {
+            state,
+            heuristics,
+            labels,
+            max_nodes,
+            format,
+        } => {
+            print_header(format, COMPARE_COLUMNS);
+            let solvers = RefCell::new(compare::Solvers::new(&heuristics, &labels, max_nodes)?);
+            try_func(
+                |s| compare_solvers(s, &mut solvers.borrow_mut(), format),
+                state,
+            )
+        }
         Command::Concat {
             alg,
             prefix,
             suffix,
         } => try_func(|a| concat(a, &prefix, &suffix), alg),
+        Command::ConvertNotation { alg, to } => try_func(|a| convert_notation(a, to), alg),
+        Command::Corrupt { state, swaps } => try_func(|s| corrupt(s, swaps), state),
+        Command::Db { action } => db_command(action),
         Command::Embed {
             state,
             target,
@@ -581,18 +3033,65 @@ fn run(args: Args) -> Result<(), Box<dyn Error>> {
                 (None, None) => unreachable!(),
                 (None, Some(target)) => loop_func(|s| embed(s, &mut target.clone())),
                 (Some(state), None) => loop_func(|t| embed(&state.clone(), t)),
-                (Some(state), Some(mut target)) => {
-                    embed(&state, &mut target);
-                    Ok(())
-                }
+                (Some(state), Some(mut target)) => embed(&state, &mut target),
             }
         }
+        Command::EstimateMean {
+            size,
+            samples,
+            seed,
+            label,
+        } => {
+            let states = bench::states(size, samples, seed)?;
+            let mut solver = labelled_solver(label)?;
+            println!("{}", stats::estimate_mean(&states, &mut solver)?);
+            Ok(())
+        }
+        Command::Export {
+            format,
+            line,
+            macro_style,
+            keys,
+            delay,
+        } => {
+            let keys = keys.unwrap_or(DEFAULT_KEYS.map(String::from));
+            try_func(
+                |i: &mut String| export(i, format, macro_style, &keys, delay),
+                line,
+            )
+        }
         Command::FilterOptimal {
             alg,
             size,
             keep_suboptimal,
-        } => try_func(|a| filter_optimal(a, size, keep_suboptimal), alg),
-        Command::Format { alg, long, spaced } => try_func(|a| format(a, long, spaced), alg),
+        } => {
+            let solver = RefCell::new(labelled_solver(LabelType::RowGrids)?);
+            try_func(
+                |a| filter_optimal(a, size, &mut solver.borrow_mut(), keep_suboptimal),
+                alg,
+            )
+        }
+        Command::FilterSolvable { annotate } => {
+            loop_func(|s: &mut Puzzle| filter_solvable(s, true, annotate))
+        }
+        Command::FilterUnsolvable { annotate } => {
+            loop_func(|s: &mut Puzzle| filter_solvable(s, false, annotate))
+        }
+        Command::Format {
+            alg,
+            long,
+            spaced,
+            stream: true,
+        } => util::stream_func(|line| -> Result<(), StreamError> {
+            let mut out = io::stdout().lock();
+            let mut formatter = stream::Formatter::new(long, spaced, true);
+            let result = stream::format(Moves::new(line), &mut formatter, &mut out);
+            writeln!(out)?;
+            result
+        }),
+        Command::Format {
+            alg, long, spaced, ..
+        } => par_try_func(|a| Ok(Some(format(a, long, spaced))), alg),
         Command::FormatState { state, format } => try_func(|s| format_state(s, format), state),
         Command::FromSolution { alg, size } => try_func(|a| from_solution(a, size), alg),
         Command::Generate {
@@ -602,6 +3101,7 @@ fn run(args: Args) -> Result<(), Box<dyn Error>> {
             num_moves,
             allow_backtracking,
             allow_illegal_moves,
+            format,
             ..
         } => {
             if random_moves {
@@ -613,31 +3113,393 @@ fn run(args: Args) -> Result<(), Box<dyn Error>> {
                         allow_backtracking,
                         allow_illegal_moves,
                     },
+                    format,
                 )
             } else {
-                generate(number, size, RandomState)
+                generate(number, size, RandomState, format)
+            }
+        }
+        Command::Grade { format } => {
+            print_header(format, GRADE_COLUMNS);
+            let solver = RefCell::new(labelled_solver(LabelType::RowGrids)?);
+            let grades = RefCell::new(Grades::default());
+            loop_func(|p: &mut Pair| {
+                grade(
+                    p,
+                    &mut solver.borrow_mut(),
+                    &mut grades.borrow_mut(),
+                    format,
+                )
+            })?;
+
+            match format {
+                Some(_) => eprintln!("{}", grades.borrow()),
+                None => println!("\n{}", grades.borrow()),
             }
+            Ok(())
+        }
+        Command::Heuristic {
+            state,
+            heuristic: h,
+            all,
+        } => {
+            let heuristics = RefCell::new(Heuristics::default());
+            try_func(
+                |s| heuristic(s, &mut heuristics.borrow_mut(), h, all),
+                state,
+            )
+        }
+        Command::Import { format, solve } => try_func(|s| import(s, format), solve),
+        Command::Invert { alg } => par_try_func(|a| Ok(Some(invert(a))), alg),
+        Command::Length {
+            metric,
+            stream: true,
+            ..
+        } => util::stream_func(|line| -> Result<(), StreamError> {
+            let len = stream::length(Moves::new(line), metric == Metric::Mtm)?;
+            println!("{len}");
+            Ok(())
+        }),
+        Command::Length {
+            alg,
+            metric,
+            weighted,
+            weights,
+            size,
+            format,
+            ..
+        } => {
+            print_header(format, LENGTH_COLUMNS);
+            let weights = weights.unwrap_or_default();
+            let weighted = size.filter(|_| weighted).map(|size| (&weights, size));
+            par_try_func(|a| Ok(Some(length(a, metric, weighted, format)?)), alg)
+        }
+        Command::Mangen { output_dir } => mangen(&output_dir),
+        Command::Md {
+            state,
+            breakdown,
+            format,
+        } => {
+            print_header(format, MD_COLUMNS);
+            par_try_func(|s| Ok(Some(md(s, breakdown, format))), state)
+        }
+        Command::MaskedMd { state, verbose } => try_func(|s| masked_md(s, verbose), state),
+        Command::MaskedSolve { state, verbose } => try_func(|s| masked_solve(s, verbose), state),
+        Command::MultiApply { state, alg } => match (state, alg) {
+            (None, None) => unreachable!(),
+            (None, Some(alg)) => loop_func(|s| multi_apply(s, &alg)),
+            (Some(state), None) => loop_func(|a| multi_apply(&mut state.clone(), a)),
+            (Some(mut state), Some(alg)) => multi_apply(&mut state, &alg),
+        },
+        Command::MultiGenerate {
+            number,
+            size,
+            blanks,
+        } => {
+            multi_generate(number, size, blanks);
+            Ok(())
+        }
+        Command::MultiSolve { state, verbose } => try_func(|s| multi_solve(s, verbose), state),
+        Command::OptDiff {
+            alg,
+            size,
+            weighted,
+            weights,
+            format,
+        } => {
+            print_header(format, OPT_DIFF_COLUMNS);
+            let weights = weights.unwrap_or_default();
+            let weights = weighted.then_some(&weights);
+            let solver = RefCell::new(labelled_solver(LabelType::RowGrids)?);
+            try_func(
+                |a| opt_diff(a, size, &mut solver.borrow_mut(), weights, format),
+                alg,
+            )
         }
-        Command::Invert { alg } => try_func(invert, alg),
-        Command::Length { alg, metric } => try_func(|a| length(a, metric), alg),
-        Command::Md { state } => try_func(md, state),
-        Command::OptDiff { alg, size } => try_func(|a| opt_diff(a, size), alg),
         Command::Optimize { alg, length } => try_func(|a| optimize(a, length), alg),
+        Command::Pipeline { stages } => pipeline(stages),
+        Command::Play {
+            state,
+            size,
+            record,
+            queue,
+            options,
+        } => {
+            let queue: VecDeque<Puzzle> = match (state, queue) {
+                (Some(state), _) => VecDeque::from([state]),
+                (None, true) => read_all::<Puzzle>()?.into(),
+                (None, false) => VecDeque::new(),
+            };
+            // The random states after the queue have the same size as the states in it
+            let size = queue.front().map_or(size, |s| s.size());
+            play::play(
+                queue,
+                size,
+                &options,
+                &Keybindings::load()?,
+                record.as_deref(),
+            )
+        }
+        Command::Practice {
+            phase,
+            size,
+            paste,
+            session,
+            options,
+        } => {
+            let session = Session::open(session.as_deref())?;
+            if paste {
+                practice::practice_pasted(phase, size, session, &options)
+            } else {
+                practice::practice(phase, size, session, &options, &Keybindings::load()?)
+            }
+        }
+        Command::Reconstruct {
+            reconstruction,
+            pause,
+        } => try_func(|r| reconstruct(r, pause), reconstruction),
+        Command::Repl {
+            state,
+            size,
+            record,
+            options,
+        } => repl::repl(state.unwrap_or_else(|| goal::solved(size)), options, record),
+        Command::Replay {
+            state,
+            alg,
+            tps,
+            options,
+        } => replay::replay(&state, &alg, tps, &options),
         Command::Render {
             state,
-            label,
-            coloring,
+            options,
+            alg,
+            output,
+            term,
+            frames_dir,
+            show_moves,
+            blank_path,
+            number_moves,
+            sheet,
+            columns,
+            caption,
+            caption_from_input,
+        } => {
+            let overlay = match (show_moves, blank_path) {
+                (Some(n), _) => Overlay::MoveArrows(n),
+                (None, true) => Overlay::BlankPath {
+                    numbered: number_moves,
+                },
+                (None, false) => Overlay::None,
+            };
+
+            if term {
+                return try_func(|s| render_term(s, alg.as_ref(), &options), state);
+            }
+
+            if sheet {
+                let states = match state {
+                    Some(state) => vec![state],
+                    None => read_all()?,
+                };
+                let output = output.ok_or("--output is required with --sheet")?;
+                return render_sheet(
+                    &states,
+                    &options,
+                    columns,
+                    caption.as_deref(),
+                    caption_from_input,
+                    &output,
+                );
+            }
+
+            try_func_once(
+                |s: &mut Puzzle| {
+                    let caption = if caption_from_input {
+                        Some(s.to_string())
+                    } else {
+                        caption.clone()
+                    };
+                    render(
+                        s,
+                        alg.as_ref(),
+                        &options,
+                        output.as_deref(),
+                        frames_dir.as_deref(),
+                        overlay,
+                        caption.as_deref(),
+                    )
+                },
+                state,
+            )
+        }
+        Command::RenderBeforeAfter {
+            mut state,
+            alg,
+            options,
+            output,
+        } => render_before_after(&mut state, &alg, &options, &output),
+        Command::RenderLabels {
+            size,
+            options,
+            columns,
+            output,
+        } => render_labels(size, &options, columns, &output),
+        Command::RenderSolve {
+            mut state,
+            alg,
+            options,
+            frame_duration,
+            output,
+        } => render_solve(&mut state, &alg, &options, frame_duration, &output),
+        Command::RenderTraffic {
+            state,
+            alg,
             tile_size,
+            palette,
+            output,
+        } => render_traffic(&state, &alg, tile_size, palette, &output),
+        Command::RelaySolve { relay, label } => {
+            let solver = RefCell::new(labelled_solver(label)?);
+            try_func(|r| relay_solve(r, &mut solver.borrow_mut()), relay)
+        }
+        Command::Rescramble {
+            state,
+            label,
+            classes,
+        } => try_func(|s| rescramble(s, label, &classes), state),
+        Command::Size { state_or_alg } => try_func(|i| size(i), state_or_alg),
+        Command::Rpc => rpc::serve(),
+        Command::Schema { command } => print_schema(command.as_deref()),
+        Command::Session { action } => session_command(action),
+        Command::Sheet {
+            count,
+            size,
+            columns,
+            answers,
+            seed,
+            options,
             output,
-        } => try_func_once(|s| render(s, label, coloring, tile_size, &output), state),
+        } => practice_sheet(
+            count,
+            size,
+            columns,
+            answers,
+            seed.unwrap_or_else(rand::random),
+            &options,
+            &output,
+        ),
         Command::Simplify { alg, verbose } => try_func(|a| simplify(a, verbose), alg),
-        Command::Slice { alg, start, end } => try_func(|a| slice(a, start, end), alg),
-        Command::Solvable { state } => try_func(solvable, state),
+        Command::Slice {
+            start,
+            end,
+            stream: true,
+            ..
+        } => util::stream_func(|line| -> Result<(), StreamError> {
+            let mut out = io::stdout().lock();
+            let result = stream::slice(Moves::new(line), start, end, &mut out);
+            writeln!(out)?;
+            result
+        }),
+        Command::Slice {
+            alg, start, end, ..
+        } => try_func(|a| slice(a, start, end), alg),
+        Command::Solvable { state, filter } => par_try_func(|s| Ok(solvable(s, filter)), state),
         Command::Solve {
             state,
             label,
             verbose,
-        } => try_func(|s| solve(s, label, verbose), state),
+            format,
+            cache,
+            db,
+        } => {
+            print_header(format, SOLVE_COLUMNS);
+            let solver = match cache {
+                Some(capacity) => solvers::cached(labelled_solver(label)?, capacity),
+                None => labelled_solver(label)?,
+            };
+            let solver = match db {
+                Some(path) => db::cached(solver, db::Database::open(&path)?),
+                None => solver,
+            };
+            let solver = RefCell::new(solver);
+            let progress = RefCell::new(Progress::new("solve", None));
+            try_func(
+                |s| {
+                    let result = solve(s, &mut solver.borrow_mut(), verbose, format);
+                    progress.borrow_mut().inc();
+                    result
+                },
+                state,
+            )
+        }
+        Command::Stats {
+            states: true,
+            optimal,
+            ..
+        } => {
+            let mut solver = optimal
+                .then(|| labelled_solver(LabelType::RowGrids))
+                .transpose()?;
+            println!(
+                "{}",
+                stats::states(&read_all::<Puzzle>()?, solver.as_mut())?
+            );
+            Ok(())
+        }
+        Command::Stats {
+            metric, directions, ..
+        } => {
+            let algs = read_all::<Algorithm>()?;
+            println!("{}", stats::algorithms(&algs, metric));
+            if directions {
+                println!("\n{}", stats::directions(&algs));
+            }
+            Ok(())
+        }
+        Command::TilePath {
+            state,
+            alg,
+            pairs,
+            tile,
+        } => for_each_pair(state, alg, pairs, |s, a| tile_path(s, a, tile)),
+        Command::Timer {
+            size,
+            session,
+            inspection,
+            options,
+        } => timer::timer(
+            size,
+            Session::open(session.as_deref())?,
+            inspection,
+            &options,
+        ),
+        Command::Url {
+            action: Some(UrlAction::Parse { link, template }),
+            ..
+        } => try_func(|l: &mut String| url_parse(l, template.as_deref()), link),
+        Command::Url {
+            state, template, ..
+        } => {
+            let template = template.ok_or("a --template is needed to create links")?;
+            try_func(|s| url(s, &template), state)
+        }
+        Command::Verify { state, alg, pairs } => for_each_pair(state, alg, pairs, verify),
+        Command::ValidateState { state, size } => try_func(|s| validate_state(s, size), state),
+        Command::WatchSolve {
+            state,
+            heuristic,
+            speed,
+            options,
+        } => watch::watch_solve(state, heuristic, speed, &options),
+        Command::WeightedSolve {
+            state,
+            weights,
+            verbose,
+        } => {
+            let weights = weights.unwrap_or_default();
+            try_func(|s| weighted_solve(s, &weights, verbose), state)
+        }
     }
 }
 
@@ -646,6 +3508,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     unsafe {
         libc::signal(libc::SIGPIPE, libc::SIG_DFL);
     }
+    util::handle_interrupts();
+
+    notation::configure(Args::command());
+    let args = Args::parse();
+
+    if args.quiet {
+        output::quiet()?;
+    } else if let Some(path) = &args.output_file {
+        output::to_file(path)?;
+    }
+
+    let start = Instant::now();
+    let result = run(args);
+    util::log_summary(start.elapsed());
+    output::finish(result.is_ok())?;
+    result?;
 
-    run(Args::parse())
+    std::process::exit(util::exit_code())
 }
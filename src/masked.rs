@@ -0,0 +1,247 @@
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+use slidy::{
+    algorithm::{algorithm::Algorithm, direction::Direction, r#move::r#move::Move},
+    puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle},
+};
+use thiserror::Error;
+
+use crate::{
+    goal,
+    grid::{self, DIRECTIONS},
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum MaskedPuzzleError {
+    #[error("Empty: the state contains no pieces")]
+    Empty,
+
+    #[error("ParseError: failed to parse piece \"{0}\"")]
+    ParsePiece(String),
+
+    #[error("RowLength: row {row} has length {len}, expected {expected}")]
+    RowLength {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+
+    #[error("InvalidPiece: piece {0} is out of range for the puzzle size")]
+    InvalidPiece(u64),
+
+    #[error("DuplicatePiece: piece {0} appears more than once")]
+    DuplicatePiece(u64),
+
+    #[error("UnknownGap: the position of the gap must be known")]
+    UnknownGap,
+}
+
+/// A puzzle state where some of the pieces are unknown, written as `?`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaskedPuzzle {
+    size: Size,
+    pieces: Vec<Option<u64>>,
+}
+
+impl FromStr for MaskedPuzzle {
+    type Err = MaskedPuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .trim()
+            .split('/')
+            .map(|row| {
+                row.split_whitespace()
+                    .map(|p| match p {
+                        "?" => Ok(None),
+                        _ => p
+                            .parse::<u64>()
+                            .map(Some)
+                            .map_err(|_| MaskedPuzzleError::ParsePiece(p.to_string())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if width == 0 {
+            return Err(MaskedPuzzleError::Empty);
+        }
+
+        if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != width) {
+            return Err(MaskedPuzzleError::RowLength {
+                row,
+                len: r.len(),
+                expected: width,
+            });
+        }
+
+        let size =
+            Size::new(width as u64, rows.len() as u64).map_err(|_| MaskedPuzzleError::Empty)?;
+        let pieces: Vec<Option<u64>> = rows.into_iter().flatten().collect();
+
+        let mut seen = HashSet::new();
+        for &p in pieces.iter().flatten() {
+            if p >= size.area() {
+                return Err(MaskedPuzzleError::InvalidPiece(p));
+            }
+            if !seen.insert(p) {
+                return Err(MaskedPuzzleError::DuplicatePiece(p));
+            }
+        }
+
+        if !seen.contains(&0) {
+            return Err(MaskedPuzzleError::UnknownGap);
+        }
+
+        Ok(Self { size, pieces })
+    }
+}
+
+impl Display for MaskedPuzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = grid::format_rows(&self.pieces, self.size, |p| match p {
+            Some(p) => p.to_string(),
+            None => "?".to_string(),
+        });
+        write!(f, "{s}")
+    }
+}
+
+impl MaskedPuzzle {
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn num_known(&self) -> u64 {
+        self.pieces.iter().filter(|p| p.is_some()).count() as u64 - 1
+    }
+
+    pub fn num_unknown(&self) -> u64 {
+        self.pieces.iter().filter(|p| p.is_none()).count() as u64
+    }
+
+    fn gap_position(&self) -> usize {
+        self.pieces.iter().position(|&p| p == Some(0)).unwrap()
+    }
+
+    /// Sum of the Manhattan distances of the known pieces from their solved positions.
+    pub fn known_manhattan_distance(&self) -> u64 {
+        let (width, _) = self.size.into();
+        self.pieces
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.filter(|&p| p != 0).map(|p| (i as u64, p)))
+            .map(|(i, p)| {
+                grid::manhattan((i % width, i / width), goal::solved_pos_xy(p, self.size))
+            })
+            .sum()
+    }
+
+    /// Fills in the unknown pieces with the missing numbers, choosing an order that makes the
+    /// resulting state solvable if possible.
+    pub fn complete(&self) -> Puzzle {
+        let known: HashSet<u64> = self.pieces.iter().flatten().copied().collect();
+        let mut missing = (1..self.size.area()).filter(|p| !known.contains(p));
+        let pieces: Vec<u64> = self
+            .pieces
+            .iter()
+            .map(|p| p.unwrap_or_else(|| missing.next().unwrap()))
+            .collect();
+
+        let mut puzzle = grid::from_pieces(&pieces, self.size).unwrap();
+        if !goal::is_solvable(&puzzle) {
+            let mut unknown = self.pieces.iter().enumerate().filter(|(_, p)| p.is_none());
+            if let (Some((a, _)), Some((b, _))) = (unknown.next(), unknown.next()) {
+                puzzle.swap_pieces(a as u64, b as u64);
+            }
+        }
+
+        puzzle
+    }
+
+    /// Returns true if some assignment of the unknown pieces is solvable.
+    pub fn is_solvable(&self) -> bool {
+        goal::is_solvable(&self.complete())
+    }
+
+    /// Finds an optimal algorithm that moves every known piece into its solved position. The
+    /// unknown pieces and the gap may end up anywhere.
+    pub fn solve(&self) -> Option<Algorithm> {
+        if !self.is_solvable() {
+            return None;
+        }
+
+        let mut search = Search {
+            size: self.size,
+            pieces: self.pieces.clone(),
+            gap: self.gap_position(),
+            path: Vec::new(),
+        };
+
+        let mut bound = self.known_manhattan_distance();
+        loop {
+            if search.dfs(0, bound, self.known_manhattan_distance(), None) {
+                let moves = search.path.into_iter().map(|d| Move::new(d, 1)).collect();
+                let mut alg = Algorithm::with_moves(moves);
+                alg.simplify();
+                return Some(alg);
+            }
+            bound += 1;
+        }
+    }
+}
+
+struct Search {
+    size: Size,
+    pieces: Vec<Option<u64>>,
+    gap: usize,
+    path: Vec<Direction>,
+}
+
+impl Search {
+    fn dfs(&mut self, depth: u64, bound: u64, h: u64, last: Option<Direction>) -> bool {
+        if h == 0 {
+            return true;
+        }
+        if depth + h > bound {
+            return false;
+        }
+
+        let (width, _) = self.size.into();
+        let gap_xy = (self.gap as u64 % width, self.gap as u64 / width);
+
+        for dir in DIRECTIONS {
+            if last == Some(dir.inverse()) {
+                continue;
+            }
+            let Some(from) = grid::moved_piece_pos(gap_xy, dir, self.size) else {
+                continue;
+            };
+            let from_idx = (from.0 + from.1 * width) as usize;
+
+            let delta = match self.pieces[from_idx] {
+                Some(p) => {
+                    let home = goal::solved_pos_xy(p, self.size);
+                    grid::manhattan(gap_xy, home) as i64 - grid::manhattan(from, home) as i64
+                }
+                None => 0,
+            };
+            let new_h = (h as i64 + delta) as u64;
+
+            self.pieces.swap(self.gap, from_idx);
+            let old_gap = std::mem::replace(&mut self.gap, from_idx);
+            self.path.push(dir);
+
+            if self.dfs(depth + 1, bound, new_h, Some(dir)) {
+                return true;
+            }
+
+            self.path.pop();
+            self.gap = old_gap;
+            self.pieces.swap(self.gap, from_idx);
+        }
+
+        false
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Parses an amount of memory in bytes, with an optional `K`, `M` or `G` suffix for powers of
+/// 1024, e.g. `512M` or `2G`.
+pub fn parse_memory(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&s[..s.len() - 1], 1 << 10),
+        Some('M') => (&s[..s.len() - 1], 1 << 20),
+        Some('G') => (&s[..s.len() - 1], 1 << 30),
+        _ => (s, 1),
+    };
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("\"{s}\" is not an amount of memory"))
+}
+
+/// Sets the amount of memory that tables and caches may use in total.
+pub fn set_limit(bytes: u64) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub fn limit() -> u64 {
+    LIMIT.load(Ordering::Relaxed)
+}
+
+/// The number of entries of `entry_size` bytes that fit within the limit.
+pub fn max_entries(entry_size: u64) -> usize {
+    (limit() / entry_size.max(1))
+        .try_into()
+        .unwrap_or(usize::MAX)
+}
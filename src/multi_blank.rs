@@ -0,0 +1,297 @@
+use std::{fmt::Display, str::FromStr};
+
+use rand::seq::SliceRandom;
+use slidy::{
+    algorithm::direction::Direction,
+    puzzle::{label::label::RowGrids, size::Size, sliding_puzzle::SlidingPuzzle},
+    solver::{heuristic::manhattan::ManhattanDistance, solver::Solver},
+};
+use thiserror::Error;
+
+use crate::{
+    goal,
+    grid::{self, DIRECTIONS},
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum MultiBlankError {
+    #[error("Empty: the state contains no pieces")]
+    Empty,
+
+    #[error("ParseError: failed to parse \"{0}\"")]
+    Parse(String),
+
+    #[error("RowLength: row {row} has length {len}, expected {expected}")]
+    RowLength {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+
+    #[error("InvalidPieces: the pieces must be 1 to n and at least one 0")]
+    InvalidPieces,
+}
+
+/// A puzzle state with any number of blanks, written as `0`. The solved state is the goal with
+/// the pieces numbered above the number of tiles replaced by blanks, so with the usual goal it
+/// has the pieces in order followed by all of the blanks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiBlankPuzzle {
+    size: Size,
+    pieces: Vec<u64>,
+}
+
+impl FromStr for MultiBlankPuzzle {
+    type Err = MultiBlankError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .trim()
+            .split('/')
+            .map(|row| {
+                row.split_whitespace()
+                    .map(|p| {
+                        p.parse::<u64>()
+                            .map_err(|_| MultiBlankError::Parse(p.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if width == 0 {
+            return Err(MultiBlankError::Empty);
+        }
+
+        if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != width) {
+            return Err(MultiBlankError::RowLength {
+                row,
+                len: r.len(),
+                expected: width,
+            });
+        }
+
+        let size =
+            Size::new(width as u64, rows.len() as u64).map_err(|_| MultiBlankError::Empty)?;
+        let pieces: Vec<u64> = rows.into_iter().flatten().collect();
+
+        let mut tiles: Vec<u64> = pieces.iter().copied().filter(|&p| p != 0).collect();
+        tiles.sort_unstable();
+        if tiles.len() == pieces.len() || tiles.iter().zip(1..).any(|(&a, b)| a != b) {
+            return Err(MultiBlankError::InvalidPieces);
+        }
+
+        Ok(Self { size, pieces })
+    }
+}
+
+impl Display for MultiBlankPuzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            grid::format_rows(&self.pieces, self.size, u64::to_string)
+        )
+    }
+}
+
+impl MultiBlankPuzzle {
+    pub fn new(size: Size, blanks: u64) -> Self {
+        let tiles = size.area() - blanks.clamp(1, size.area());
+        let pieces = grid::pieces(&goal::solved(size))
+            .into_iter()
+            .map(|p| if p > tiles { 0 } else { p })
+            .collect();
+        Self { size, pieces }
+    }
+
+    pub fn num_blanks(&self) -> u64 {
+        self.pieces.iter().filter(|&&p| p == 0).count() as u64
+    }
+
+    /// The positions of the blanks in row-major order. Blank `i` of an algorithm is the `i`th
+    /// blank in this order (starting from 1) at the time the algorithm is applied.
+    fn blank_positions(&self) -> Vec<usize> {
+        (0..self.pieces.len())
+            .filter(|&i| self.pieces[i] == 0)
+            .collect()
+    }
+
+    fn xy(&self, idx: usize) -> (u64, u64) {
+        let (width, _) = self.size.into();
+        (idx as u64 % width, idx as u64 / width)
+    }
+
+    fn idx(&self, (x, y): (u64, u64)) -> usize {
+        let (width, _) = self.size.into();
+        (x + y * width) as usize
+    }
+
+    /// Applies `alg`, returning false (and leaving the state unchanged) if any move is not
+    /// possible.
+    pub fn try_apply_alg(&mut self, alg: &MultiBlankAlgorithm) -> bool {
+        let mut pieces = self.pieces.clone();
+        let mut blanks = self.blank_positions();
+
+        for &(blank, dir) in &alg.moves {
+            let Some(&gap) = blanks.get(blank) else {
+                return false;
+            };
+            let Some(from) = grid::moved_piece_pos(self.xy(gap), dir, self.size) else {
+                return false;
+            };
+            let from = self.idx(from);
+            if pieces[from] == 0 {
+                return false;
+            }
+
+            pieces.swap(gap, from);
+            blanks[blank] = from;
+        }
+
+        self.pieces = pieces;
+        true
+    }
+
+    pub fn randomize(&mut self) {
+        self.pieces.shuffle(&mut rand::thread_rng());
+    }
+
+    /// With two or more blanks every state is solvable, unless the puzzle only has one row or
+    /// column. With one blank, this is the usual parity condition.
+    pub fn is_solvable(&self) -> bool {
+        let (width, height) = self.size.into();
+        if width == 1 || height == 1 {
+            let tiles: Vec<u64> = self.pieces.iter().copied().filter(|&p| p != 0).collect();
+            tiles.windows(2).all(|w| w[0] < w[1])
+        } else {
+            self.num_blanks() > 1
+                || goal::is_solvable(&grid::from_pieces(&self.pieces, self.size).unwrap())
+        }
+    }
+
+    /// Finds a (not necessarily optimal) solution by treating all but one blank as the pieces
+    /// that belong in the extra blank positions, solving the resulting single blank puzzle, and
+    /// dropping the moves that swap two blanks.
+    pub fn solve(&self) -> Option<MultiBlankAlgorithm> {
+        if !self.is_solvable() {
+            return None;
+        }
+
+        let blanks = self.blank_positions();
+        let (&main, extra) = blanks.split_last()?;
+
+        // Which blank (in the numbering used by `try_apply_alg`) is at each position
+        let mut identity: Vec<Option<usize>> = vec![None; self.pieces.len()];
+        for (i, &idx) in blanks.iter().enumerate() {
+            identity[idx] = Some(i);
+        }
+
+        let tiles = self.size.area() - blanks.len() as u64;
+        let mut pieces = self.pieces.clone();
+        for (&idx, p) in extra.iter().zip(tiles + 1..) {
+            pieces[idx] = p;
+        }
+
+        let mut moves = Vec::new();
+
+        let mut puzzle = grid::from_pieces(&pieces, self.size).ok()?;
+        if !goal::is_solvable(&puzzle) {
+            // Moving a tile into one of the extra blanks is a swap of two non-gap pieces in the
+            // single blank puzzle, which makes it solvable
+            let (blank, dir, from) = extra.iter().find_map(|&idx| {
+                DIRECTIONS.into_iter().find_map(|dir| {
+                    let from = self.idx(grid::moved_piece_pos(self.xy(idx), dir, self.size)?);
+                    (self.pieces[from] != 0).then_some((idx, dir, from))
+                })
+            })?;
+
+            moves.push((identity[blank]?, dir));
+            identity.swap(blank, from);
+            pieces.swap(blank, from);
+            puzzle = grid::from_pieces(&pieces, self.size).ok()?;
+        }
+
+        let mut solver = Solver::new(&ManhattanDistance(&RowGrids), &RowGrids);
+        let solution = goal::reflect_alg(&solver.solve(&goal::to_standard(&puzzle)).ok()?);
+
+        let mut gap = main;
+        for dir in grid::directions(&solution) {
+            let from = self.idx(grid::moved_piece_pos(self.xy(gap), dir, self.size)?);
+            // Swapping the gap with one of the extra blanks doesn't move anything
+            if pieces[from] <= tiles {
+                moves.push((identity[gap]?, dir));
+                identity.swap(gap, from);
+            }
+            pieces.swap(gap, from);
+            gap = from;
+        }
+
+        Some(MultiBlankAlgorithm { moves })
+    }
+}
+
+/// A sequence of moves, each made by a particular blank. Written as a sequence of directions,
+/// where `[i]` switches to moving blank `i`, e.g. `[1]ULL[2]DR`. The first blank is used until
+/// a switch is made.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultiBlankAlgorithm {
+    moves: Vec<(usize, Direction)>,
+}
+
+impl FromStr for MultiBlankAlgorithm {
+    type Err = MultiBlankError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut moves = Vec::new();
+        let mut blank = 0;
+        let mut chars = s.chars().filter(|c| !c.is_whitespace()).peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '[' {
+                let n: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                blank = match n.parse::<usize>() {
+                    Ok(n) if n > 0 => n - 1,
+                    _ => return Err(MultiBlankError::Parse(n)),
+                };
+                continue;
+            }
+
+            let dir = grid::parse_direction(c).ok_or(MultiBlankError::Parse(c.to_string()))?;
+
+            let mut amount = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                amount.push(d);
+            }
+            let amount = if amount.is_empty() {
+                1
+            } else {
+                amount.parse().map_err(|_| MultiBlankError::Parse(amount))?
+            };
+
+            moves.extend(std::iter::repeat((blank, dir)).take(amount));
+        }
+
+        Ok(Self { moves })
+    }
+}
+
+impl Display for MultiBlankAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut blank = 0;
+        for &(b, dir) in &self.moves {
+            if b != blank {
+                write!(f, "[{}]", b + 1)?;
+                blank = b;
+            }
+            write!(f, "{}", grid::direction_char(dir))?;
+        }
+        Ok(())
+    }
+}
+
+impl MultiBlankAlgorithm {
+    pub fn len_stm(&self) -> u64 {
+        self.moves.len() as u64
+    }
+}
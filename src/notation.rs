@@ -0,0 +1,321 @@
+use std::{any::TypeId, str::FromStr, sync::OnceLock};
+
+use clap::{builder::ValueParser, Command};
+use slidy::{algorithm::algorithm::Algorithm, puzzle::puzzle::Puzzle};
+use thiserror::Error;
+
+use crate::enums::Convention;
+
+/// How algorithms are read, set by the global `--lenient`, `--notation` and `--convention`
+/// options.
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NotationOptions {
+    #[clap(
+        long,
+        global = true,
+        help = "Accept algorithms with lowercase directions, commas and any spacing"
+    )]
+    lenient: bool,
+
+    // The characters used for up, left, down and right moves
+    #[clap(
+        long = "notation",
+        global = true,
+        value_parser = parse_notation,
+        help = "The characters used for up, left, down and right moves in algorithms, e.g. ulmr"
+    )]
+    directions: Option<[char; 4]>,
+
+    #[clap(
+        long,
+        global = true,
+        default_value = "tile",
+        help = "Whether move directions in algorithms are the directions the tiles move in, or the \
+        directions the blank moves in. Applies to algorithms that are read, and to the output of \
+        format"
+    )]
+    convention: Convention,
+}
+
+static OPTIONS: OnceLock<NotationOptions> = OnceLock::new();
+
+/// Parses the characters used for up, left, down and right moves, e.g. `ulmr`.
+pub fn parse_notation(s: &str) -> Result<[char; 4], String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut sorted = chars.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    match chars.try_into() {
+        Ok(chars) if sorted.len() == 4 => Ok(chars),
+        _ => Err(format!("\"{s}\" is not 4 distinct characters")),
+    }
+}
+
+/// Reads `--lenient`, `--notation` and `--convention` from the command line of `command`.
+/// Algorithm and state arguments are parsed with these options while the rest of the command
+/// line is, so they have to be known before the arguments are parsed into `Args`.
+pub fn configure(command: Command) {
+    let matches = with_string_values(command).get_matches();
+
+    let _ = OPTIONS.set(NotationOptions {
+        lenient: matches.get_one("lenient").copied().unwrap_or_default(),
+        directions: matches.get_one("directions").copied(),
+        convention: matches.get_one("convention").copied().unwrap_or_default(),
+    });
+}
+
+/// `command` with the values of every option other than the global ones read as plain strings,
+/// so that reading the notation options doesn't stop at an algorithm that only parses with them.
+/// Any other errors are ignored, and reported when the arguments are parsed into `Args`.
+fn with_string_values(command: Command) -> Command {
+    let args: Vec<String> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_global_set() && arg.get_action().takes_values())
+        .map(|arg| arg.get_id().to_string())
+        .collect();
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+
+    let mut command = command.ignore_errors(true);
+    for id in args {
+        command = command.mut_arg(id, |arg| arg.value_parser(ValueParser::string()));
+    }
+    for name in subcommands {
+        command = command.mut_subcommand(name, with_string_values);
+    }
+    command
+}
+
+/// Rewrites an algorithm into the standard notation, or returns `None` if no changes are
+/// needed.
+fn normalize(s: &str) -> Option<String> {
+    let options = OPTIONS.get()?;
+    if !options.lenient && options.directions.is_none() && options.convention == Convention::Tile {
+        return None;
+    }
+
+    Some(s.chars().filter_map(normalize_char).collect())
+}
+
+/// Rewrites one character of an algorithm into the standard notation, or returns `None` if
+/// `--lenient` drops it.
+pub fn normalize_char(c: char) -> Option<char> {
+    let Some(options) = OPTIONS.get() else {
+        return Some(c);
+    };
+
+    let c = match options.directions {
+        Some(directions) => {
+            let matches = |d: char| {
+                d == c || (options.lenient && d.to_ascii_lowercase() == c.to_ascii_lowercase())
+            };
+            match directions.iter().position(|&d| matches(d)) {
+                Some(i) => ['U', 'L', 'D', 'R'][i],
+                None => c,
+            }
+        }
+        None => c,
+    };
+
+    // Drop separators and spacing, and accept lowercase directions
+    if options.lenient && (c.is_whitespace() || matches!(c, ',' | ';' | '.')) {
+        return None;
+    }
+    let c = if options.lenient {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    };
+
+    match options.convention {
+        Convention::Tile => Some(c),
+        Convention::Blank => Some(flip_char(c)),
+    }
+}
+
+/// Reverses the direction of every move in an algorithm written in the standard notation,
+/// converting between the tile and blank conventions.
+pub fn flip(s: &str) -> String {
+    s.chars().map(flip_char).collect()
+}
+
+pub fn flip_char(c: char) -> char {
+    match c {
+        'U' => 'D',
+        'D' => 'U',
+        'L' => 'R',
+        'R' => 'L',
+        c => c,
+    }
+}
+
+/// The options set by [`configure`].
+pub fn options() -> NotationOptions {
+    OPTIONS.get().cloned().unwrap_or_default()
+}
+
+/// The convention that algorithms are read and formatted in.
+pub fn convention() -> Convention {
+    OPTIONS.get().map(|o| o.convention).unwrap_or_default()
+}
+
+/// Parses an algorithm, applying `--lenient` and `--notation`.
+pub fn parse_algorithm(s: &str) -> Result<Algorithm, String> {
+    let s = normalize(s).unwrap_or_else(|| s.to_string());
+    Algorithm::from_str(&s).map_err(|e| e.to_string())
+}
+
+/// Parses an input line, applying `--lenient` and `--notation` if it is an algorithm, and
+/// accepting the alternative notations if it is a puzzle state.
+pub fn parse_input<T: FromStr + 'static>(s: &str) -> Result<T, T::Err> {
+    let normalized = if TypeId::of::<T>() == TypeId::of::<Algorithm>() {
+        normalize(s)
+    } else if TypeId::of::<T>() == TypeId::of::<Puzzle>() {
+        normalize_state(s)
+    } else {
+        None
+    };
+
+    T::from_str(normalized.as_deref().unwrap_or(s))
+}
+
+/// Characters that stand for the blank in the alternative state notations.
+const BLANK_CHARS: [char; 4] = ['_', '.', '-', '*'];
+
+/// Rewrites a state written in one of the alternative notations into the standard one, or returns
+/// `None` if it is already standard. The alternative notations are detected automatically:
+///
+/// - Rows separated by `;`, `,` or newlines instead of `/`.
+/// - Letters `A`, `B`, ... for the pieces, with or without spaces between them, and `0` or one of
+///   `_ . - *` for the blank.
+/// - Zero-indexed numbers, where the pieces are numbered from 0 and the blank is one of
+///   `_ . - *`.
+fn normalize_state(s: &str) -> Option<String> {
+    let s = s.trim();
+    let standard = |c: char| c.is_ascii_digit() || c.is_whitespace() || c == '/';
+    if s.chars().all(standard) {
+        return None;
+    }
+
+    let rows: Vec<&str> = s.split(['/', ';', ',', '\n']).map(str::trim).collect();
+    let is_blank = |t: &str| t.len() == 1 && t.chars().all(|c| BLANK_CHARS.contains(&c));
+
+    let mut tokens: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            let words: Vec<&str> = row.split_whitespace().collect();
+            // A row of letters may be written without spaces, e.g. ABCD
+            if words.len() == 1
+                && words[0]
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || c == '_')
+            {
+                words[0].chars().map(String::from).collect()
+            } else {
+                words.into_iter().map(String::from).collect()
+            }
+        })
+        .collect();
+
+    let all = || tokens.iter().flatten();
+    let letters = all().all(|t| {
+        t == "0" || is_blank(t) || (t.len() == 1 && t.chars().all(|c| c.is_ascii_alphabetic()))
+    }) && all().any(|t| t.chars().all(|c| c.is_ascii_alphabetic()) && !is_blank(t));
+    let zero_indexed = all().any(|t| t == "0") && all().any(|t| is_blank(t));
+
+    for token in tokens.iter_mut().flatten() {
+        *token = if is_blank(token) || (letters && token == "0") {
+            "0".to_string()
+        } else if letters {
+            let c = token.chars().next()?.to_ascii_uppercase();
+            (c as u64 - 'A' as u64 + 1).to_string()
+        } else if zero_indexed {
+            (token.parse::<u64>().ok()? + 1).to_string()
+        } else {
+            token.clone()
+        };
+    }
+
+    Some(
+        tokens
+            .iter()
+            .map(|row| row.join(" "))
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// Parses a puzzle state, accepting the alternative notations described in [`normalize_state`].
+pub fn parse_state(s: &str) -> Result<Puzzle, String> {
+    let s = normalize_state(s).unwrap_or_else(|| s.to_string());
+    Puzzle::from_str(&s).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PairError {
+    #[error("MissingTab: expected a state and an algorithm separated by a tab")]
+    MissingTab,
+
+    #[error("InvalidState: {0}")]
+    State(String),
+
+    #[error("InvalidAlgorithm: {0}")]
+    Alg(String),
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RelayError {
+    #[error("InvalidState: puzzle {0}: {1}")]
+    State(usize, String),
+}
+
+/// The scrambles of a relay on one input line: puzzle states of any sizes, separated by tabs.
+/// Blank lines have no states, so that the blank lines between relays can be skipped.
+#[derive(Clone, Debug)]
+pub struct Relay {
+    pub states: Vec<Puzzle>,
+}
+
+impl FromStr for Relay {
+    type Err = RelayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let states = s
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .flat_map(|line| line.split('\t'))
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .enumerate()
+            .map(|(i, f)| parse_state(f).map_err(|e| RelayError::State(i + 1, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { states })
+    }
+}
+
+/// A puzzle state and an algorithm on one input line, separated by a tab. Any fields after the
+/// algorithm are ignored.
+#[derive(Clone, Debug)]
+pub struct Pair {
+    pub state: Puzzle,
+    pub alg: Algorithm,
+}
+
+impl FromStr for Pair {
+    type Err = PairError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split('\t');
+        let (Some(state), Some(alg)) = (fields.next(), fields.next()) else {
+            return Err(PairError::MissingTab);
+        };
+        Ok(Self {
+            state: parse_state(state).map_err(PairError::State)?,
+            alg: parse_algorithm(alg).map_err(PairError::Alg)?,
+        })
+    }
+}
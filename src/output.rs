@@ -0,0 +1,72 @@
+use std::{
+    error::Error,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The temporary file that stdout is written to, and the path it is moved to once the command
+/// has finished.
+static PENDING: Mutex<Option<(PathBuf, PathBuf)>> = Mutex::new(None);
+
+#[cfg(unix)]
+fn redirect_stdout(file: &File) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::dup2(file.as_raw_fd(), libc::STDOUT_FILENO) } == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn redirect_stdout(_file: &File) -> Result<(), Box<dyn Error>> {
+    Err("redirecting the output is only supported on unix".into())
+}
+
+/// Discards everything written to stdout.
+pub fn quiet() -> Result<(), Box<dyn Error>> {
+    redirect_stdout(&OpenOptions::new().write(true).open("/dev/null")?)
+}
+
+/// Writes stdout to a temporary file next to `path`, which replaces `path` when [`finish`] is
+/// called. If the program stops early because of an error, `path` is left unchanged, but if it
+/// is interrupted, the output so far is kept.
+pub fn to_file(path: &str) -> Result<(), Box<dyn Error>> {
+    let path = Path::new(path).to_path_buf();
+    let name = path.file_name().ok_or("the output path is not a file")?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+
+    redirect_stdout(&File::create(&temp)?)?;
+    *PENDING.lock().unwrap() = Some((temp, path));
+
+    Ok(())
+}
+
+/// Moves the output file into place if the command completed, or removes it otherwise.
+pub fn finish(completed: bool) -> Result<(), Box<dyn Error>> {
+    let Some((temp, path)) = PENDING.lock().unwrap().take() else {
+        return Ok(());
+    };
+
+    std::io::stdout().flush()?;
+    if completed {
+        std::fs::rename(temp, path)?;
+    } else {
+        std::fs::remove_file(temp)?;
+    }
+
+    Ok(())
+}
+
+/// Exits the program with the given code, without replacing the output file.
+pub fn abort(code: i32) -> ! {
+    let _ = finish(false);
+    std::process::exit(code)
+}
@@ -0,0 +1,201 @@
+use std::error::Error;
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+use svg::{
+    node::{
+        self,
+        element::{Definitions, Line, Marker, Path, Polyline, Text},
+    },
+    Document,
+};
+
+use crate::{
+    grid,
+    render::{self, RenderOptions},
+};
+
+/// Annotations drawn on top of a rendered state to illustrate an algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    None,
+    MoveArrows(u64),
+    BlankPath { numbered: bool },
+}
+
+const ARROW_COLOR: &str = "#000000";
+const ARROW_OPACITY: f32 = 0.7;
+
+fn arrowhead() -> Definitions {
+    Definitions::new().add(
+        Marker::new()
+            .set("id", "arrowhead")
+            .set("viewBox", "0 0 10 10")
+            .set("refX", 5)
+            .set("refY", 5)
+            .set("markerWidth", 4)
+            .set("markerHeight", 4)
+            .set("orient", "auto")
+            .add(
+                Path::new()
+                    .set("d", "M 0 0 L 10 5 L 0 10 z")
+                    .set("fill", ARROW_COLOR),
+            ),
+    )
+}
+
+fn arrow(from: (f32, f32), to: (f32, f32), options: &RenderOptions) -> Line {
+    // Shorten the arrow so that it stays mostly within the tile being moved
+    let shorten = |a: f32, b: f32| a + (b - a) * 0.6;
+    Line::new()
+        .set("x1", from.0)
+        .set("y1", from.1)
+        .set("x2", shorten(from.0, to.0))
+        .set("y2", shorten(from.1, to.1))
+        .set("stroke", ARROW_COLOR)
+        .set("stroke-opacity", ARROW_OPACITY)
+        .set("stroke-width", options.tile_size / 15.0)
+        .set("marker-end", "url(#arrowhead)")
+}
+
+/// Draws an arrow on each tile moved by the first `n` single tile moves of `alg`, showing the
+/// direction it moves in. When more than one move is shown, the arrows are numbered.
+pub fn move_arrows(
+    mut document: Document,
+    state: &Puzzle,
+    alg: &Algorithm,
+    n: u64,
+    options: &RenderOptions,
+) -> Result<Document, Box<dyn Error>> {
+    let size = state.size();
+    let mut gap = state.gap_position_xy();
+
+    document = document.add(arrowhead());
+
+    for (i, dir) in grid::directions(alg)
+        .into_iter()
+        .take(n as usize)
+        .enumerate()
+    {
+        let from = grid::moved_piece_pos(gap, dir, size)
+            .ok_or("the algorithm can not be applied to the state")?;
+        let (start, end) = (
+            render::tile_center(from, options),
+            render::tile_center(gap, options),
+        );
+
+        document = document.add(arrow(start, end, options));
+
+        if n > 1 {
+            document = document.add(
+                Text::new()
+                    .set("x", start.0 - options.tile_size / 4.0)
+                    .set("y", start.1 - options.tile_size / 4.0)
+                    .set("font-size", options.tile_size / 5.0)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "central")
+                    .set("fill", ARROW_COLOR)
+                    .add(node::Text::new((i + 1).to_string())),
+            );
+        }
+
+        gap = from;
+    }
+
+    Ok(document)
+}
+
+/// Draws the path traced by the gap while `alg` is applied, as a line through the centers of the
+/// cells it visits. If `numbered` is true, each cell along the path is labeled with the number
+/// of moves made when the gap reaches it.
+pub fn blank_path(
+    mut document: Document,
+    state: &Puzzle,
+    alg: &Algorithm,
+    numbered: bool,
+    options: &RenderOptions,
+) -> Result<Document, Box<dyn Error>> {
+    let size = state.size();
+    let mut gap = state.gap_position_xy();
+    let mut path = vec![gap];
+
+    for dir in grid::directions(alg) {
+        gap = grid::moved_piece_pos(gap, dir, size)
+            .ok_or("the algorithm can not be applied to the state")?;
+        path.push(gap);
+    }
+
+    let points: Vec<_> = path
+        .iter()
+        .map(|&pos| {
+            let (x, y) = render::tile_center(pos, options);
+            format!("{x},{y}")
+        })
+        .collect();
+
+    document = document.add(
+        Polyline::new()
+            .set("points", points.join(" "))
+            .set("fill", "none")
+            .set("stroke", ARROW_COLOR)
+            .set("stroke-opacity", ARROW_OPACITY)
+            .set("stroke-width", options.tile_size / 15.0)
+            .set("stroke-linejoin", "round")
+            .set("stroke-linecap", "round"),
+    );
+
+    if numbered {
+        for (i, &pos) in path.iter().enumerate().skip(1) {
+            let (x, y) = render::tile_center(pos, options);
+            document = document.add(
+                Text::new()
+                    .set("x", x + options.tile_size / 4.0)
+                    .set("y", y + options.tile_size / 4.0)
+                    .set("font-size", options.tile_size / 6.0)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "central")
+                    .set("fill", ARROW_COLOR)
+                    .add(node::Text::new(i.to_string())),
+            );
+        }
+    }
+
+    Ok(document)
+}
+
+/// Places two rendered states next to each other, with an arrow pointing from the first to the
+/// second.
+pub fn before_after(before: Document, after: Document, options: &RenderOptions) -> Document {
+    let (before_width, before_height) = render::document_size(&before).unwrap_or_default();
+    let (after_width, after_height) = render::document_size(&after).unwrap_or_default();
+    let height = before_height.max(after_height);
+
+    let arrow_width = options.tile_size;
+    let spacing = options.tile_size / 4.0;
+    let arrow_start = before_width + spacing;
+    let after_x = arrow_start + arrow_width + spacing;
+
+    Document::new()
+        .set("width", after_x + after_width)
+        .set("height", height)
+        .add(arrowhead())
+        .add(before.set("y", (height - before_height) / 2.0))
+        .add(
+            Line::new()
+                .set("x1", arrow_start)
+                .set("y1", height / 2.0)
+                .set("x2", arrow_start + arrow_width * 0.8)
+                .set("y2", height / 2.0)
+                .set("stroke", ARROW_COLOR)
+                .set("stroke-opacity", ARROW_OPACITY)
+                .set("stroke-width", options.tile_size / 15.0)
+                .set("marker-end", "url(#arrowhead)"),
+        )
+        .add(
+            after
+                .set("x", after_x)
+                .set("y", (height - after_height) / 2.0),
+        )
+}
@@ -0,0 +1,283 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{
+        puzzle::Puzzle,
+        scrambler::{RandomState, Scrambler},
+        size::Size,
+        sliding_puzzle::SlidingPuzzle,
+    },
+};
+use thiserror::Error;
+
+use crate::{
+    enums::LabelType,
+    goal, heuristic, notation, solvers,
+    util::{self, Status},
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PipelineError {
+    #[error("EmptyStage: a stage has no command")]
+    EmptyStage,
+
+    #[error("UnknownStage: \"{0}\" is not a pipeline stage")]
+    UnknownStage(String),
+
+    #[error("InvalidArguments: {0}")]
+    InvalidArguments(String),
+
+    #[error("TypeMismatch: stage {stage} ({name}) expects {expected}, but the previous stage produces {found}")]
+    TypeMismatch {
+        stage: usize,
+        name: &'static str,
+        expected: Kind,
+        found: Kind,
+    },
+
+    #[error("MisplacedGenerate: generate can only be the first stage")]
+    MisplacedGenerate,
+}
+
+/// The type of the values passed between stages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    State,
+    Alg,
+    Number,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::State => write!(f, "a state"),
+            Kind::Alg => write!(f, "an algorithm"),
+            Kind::Number => write!(f, "a number"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    State(Puzzle),
+    Alg(Algorithm),
+    Number(u64),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::State(state) => write!(f, "{state}"),
+            Value::Alg(alg) => write!(f, "{alg}"),
+            Value::Number(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+/// One step of a pipeline, written like the command it corresponds to, e.g. `generate 10 4x4`,
+/// `apply ULDR` or `solve`.
+#[derive(Clone, Debug)]
+pub enum Stage {
+    Generate { number: u64, size: Size },
+    Apply(Algorithm),
+    Solve,
+    Invert,
+    Simplify,
+    Length,
+    Md,
+    FilterSolvable,
+}
+
+impl FromStr for Stage {
+    type Err = PipelineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        let invalid = |e: String| PipelineError::InvalidArguments(format!("{name}: {e}"));
+
+        let stage = match name {
+            "" => return Err(PipelineError::EmptyStage),
+            "generate" => {
+                let number = args
+                    .first()
+                    .map(|n| {
+                        n.parse()
+                            .map_err(|_| invalid(format!("\"{n}\" is not a number")))
+                    })
+                    .transpose()?
+                    .unwrap_or(1);
+                let size = args
+                    .get(1)
+                    .map(|s| Size::from_str(s).map_err(|e| invalid(e.to_string())))
+                    .transpose()?
+                    .unwrap_or(Size::new(4, 4).unwrap());
+                Stage::Generate { number, size }
+            }
+            // The algorithm may contain spaces, so everything after the name is used
+            "apply" => Stage::Apply(notation::parse_algorithm(rest).map_err(invalid)?),
+            "solve" => Stage::Solve,
+            "invert" => Stage::Invert,
+            "simplify" => Stage::Simplify,
+            "length" => Stage::Length,
+            "md" => Stage::Md,
+            "filter-solvable" => Stage::FilterSolvable,
+            _ => return Err(PipelineError::UnknownStage(name.to_string())),
+        };
+
+        Ok(stage)
+    }
+}
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        match self {
+            Stage::Generate { .. } => "generate",
+            Stage::Apply(_) => "apply",
+            Stage::Solve => "solve",
+            Stage::Invert => "invert",
+            Stage::Simplify => "simplify",
+            Stage::Length => "length",
+            Stage::Md => "md",
+            Stage::FilterSolvable => "filter-solvable",
+        }
+    }
+
+    /// The type of value the stage takes, or `None` if it produces values without any input.
+    fn input(&self) -> Option<Kind> {
+        match self {
+            Stage::Generate { .. } => None,
+            Stage::Apply(_) | Stage::Solve | Stage::Md | Stage::FilterSolvable => Some(Kind::State),
+            Stage::Invert | Stage::Simplify | Stage::Length => Some(Kind::Alg),
+        }
+    }
+
+    fn output(&self) -> Kind {
+        match self {
+            Stage::Generate { .. } | Stage::Apply(_) | Stage::FilterSolvable => Kind::State,
+            Stage::Solve | Stage::Invert | Stage::Simplify => Kind::Alg,
+            Stage::Length | Stage::Md => Kind::Number,
+        }
+    }
+
+    /// Runs the stage on one value. Returns `None` if the value was filtered out.
+    fn run(&self, value: Value) -> Result<Option<Value>, Box<dyn Error>> {
+        let value = match (self, value) {
+            (Stage::Apply(alg), Value::State(mut state)) => {
+                if !state.try_apply_alg(alg) {
+                    return Err("the algorithm can not be applied to the state".into());
+                }
+                Value::State(state)
+            }
+            (Stage::Solve, Value::State(state)) => {
+                if !goal::is_solvable(&state) {
+                    util::set_status(Status::Unsolvable);
+                }
+                let mut solver = solvers::labelled_solver(LabelType::RowGrids)?;
+                Value::Alg(solver(&state)?)
+            }
+            (Stage::Md, Value::State(state)) => {
+                if !goal::is_solvable(&state) {
+                    util::set_status(Status::Unsolvable);
+                    return Err("the state is unsolvable".into());
+                }
+                Value::Number(heuristic::manhattan_distance(&state))
+            }
+            (Stage::FilterSolvable, Value::State(state)) => {
+                if !goal::is_solvable(&state) {
+                    return Ok(None);
+                }
+                Value::State(state)
+            }
+            (Stage::Invert, Value::Alg(mut alg)) => {
+                alg.invert();
+                Value::Alg(alg)
+            }
+            (Stage::Simplify, Value::Alg(mut alg)) => {
+                alg.simplify();
+                Value::Alg(alg)
+            }
+            (Stage::Length, Value::Alg(alg)) => Value::Number(alg.len_stm()),
+            // The stages are type checked when the pipeline is created
+            _ => unreachable!(),
+        };
+
+        Ok(Some(value))
+    }
+}
+
+/// A sequence of stages, each run on the output of the previous one, without converting the
+/// values to text in between.
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    pub fn new(stages: Vec<Stage>) -> Result<Self, PipelineError> {
+        let mut found = None;
+        for (i, stage) in stages.iter().enumerate() {
+            match (stage.input(), found) {
+                (None, Some(_)) => return Err(PipelineError::MisplacedGenerate),
+                (Some(expected), Some(found)) if expected != found => {
+                    return Err(PipelineError::TypeMismatch {
+                        stage: i + 1,
+                        name: stage.name(),
+                        expected,
+                        found,
+                    })
+                }
+                _ => {}
+            }
+            found = Some(stage.output());
+        }
+
+        Ok(Self { stages })
+    }
+
+    /// The type of the inputs read by the pipeline, or `None` if the first stage generates them.
+    pub fn input(&self) -> Option<Kind> {
+        self.stages.first().and_then(Stage::input)
+    }
+
+    /// Runs every stage on one input and prints the result, unless it was filtered out.
+    pub fn run(&self, value: Value) -> Result<(), Box<dyn Error>> {
+        let mut value = value;
+        for stage in &self.stages {
+            match stage.run(value)? {
+                Some(v) => value = v,
+                None => return Ok(()),
+            }
+        }
+
+        println!("{value}");
+
+        Ok(())
+    }
+
+    /// Runs the pipeline on the states produced by the first stage, which must be `generate`.
+    pub fn run_generated(&self) -> Result<(), Box<dyn Error>> {
+        let Some(Stage::Generate { number, size }) = self.stages.first() else {
+            return Err("the first stage does not generate states".into());
+        };
+
+        let rest = Self {
+            stages: self.stages[1..].to_vec(),
+        };
+        let mut state = Puzzle::new(*size);
+        for _ in 0..*number {
+            if util::interrupted() {
+                break;
+            }
+
+            state.reset();
+            RandomState.scramble(&mut state);
+            util::try_func(
+                |s| rest.run(Value::State(s.clone())),
+                Some(goal::from_standard(&state)),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use slidy::{
+    algorithm::{algorithm::Algorithm, direction::Direction, r#move::r#move::Move},
+    puzzle::{
+        puzzle::Puzzle,
+        scrambler::{RandomState, Scrambler},
+        size::Size,
+        sliding_puzzle::SlidingPuzzle,
+    },
+};
+
+use crate::{
+    goal, grid,
+    keys::{Action, Keybindings},
+    reconstruct::Reconstruction,
+    render::{self, RenderOptions},
+    terminal::Screen,
+    util,
+};
+
+/// One interactive solve. The timer starts with the first move and stops when the puzzle is
+/// solved.
+pub struct Game {
+    scramble: Puzzle,
+    state: Puzzle,
+    /// The single tile moves leading to the current state, without any that were undone.
+    path: Vec<Direction>,
+    /// The moves that were undone, most recent last, until another move is made.
+    undone: Vec<Direction>,
+    /// Every move made, including undos, with the time since the first move.
+    record: Reconstruction,
+    start: Option<Instant>,
+    time: Option<Duration>,
+}
+
+impl Game {
+    pub fn new(scramble: Puzzle) -> Self {
+        Self {
+            state: scramble.clone(),
+            scramble,
+            path: Vec::new(),
+            undone: Vec::new(),
+            record: Reconstruction::default(),
+            start: None,
+            time: None,
+        }
+    }
+
+    pub fn scramble(&self) -> &Puzzle {
+        &self.scramble
+    }
+
+    /// The time taken to solve the puzzle, or `None` if it hasn't been solved yet.
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// The moves done so far.
+    pub fn solution(&self) -> Algorithm {
+        Algorithm::with_moves(self.path.iter().map(|&d| Move::new(d, 1)).collect())
+    }
+
+    /// Every move made so far, including undos, with timestamps.
+    pub fn record(&self) -> &Reconstruction {
+        &self.record
+    }
+
+    fn push_record(&mut self, dir: Direction) {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let alg = Algorithm::with_moves(vec![Move::new(dir, 1)]);
+        self.record.push(alg, start.elapsed().as_secs_f64());
+    }
+
+    /// Moves a tile, unless the move isn't possible or the puzzle has already been solved.
+    /// Returns whether this move solved the puzzle.
+    pub fn slide(&mut self, dir: Direction) -> bool {
+        if self.time.is_some() || !self.state.try_move_dir(dir) {
+            return false;
+        }
+
+        self.undone.clear();
+        self.moved(dir)
+    }
+
+    /// Keeps track of a move that has been made. Returns whether it solved the puzzle.
+    fn moved(&mut self, dir: Direction) -> bool {
+        self.path.push(dir);
+        self.push_record(dir);
+        let start = *self.start.get_or_insert_with(Instant::now);
+        if goal::is_solved(&self.state) {
+            self.time = Some(start.elapsed());
+        }
+
+        self.time.is_some()
+    }
+
+    /// Takes back the last move, unless the puzzle has already been solved.
+    pub fn undo(&mut self) {
+        if self.time.is_some() {
+            return;
+        }
+
+        if let Some(dir) = self.path.pop() {
+            self.undone.push(dir);
+            let dir = grid::opposite(dir);
+            self.state.try_move_dir(dir);
+            self.push_record(dir);
+        }
+    }
+
+    /// Makes the last undone move again. Returns whether this solved the puzzle.
+    pub fn redo(&mut self) -> bool {
+        if self.time.is_some() {
+            return false;
+        }
+
+        match self.undone.pop() {
+            Some(dir) if self.state.try_move_dir(dir) => self.moved(dir),
+            _ => false,
+        }
+    }
+
+    /// Goes back to the scramble, resetting the timer.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.scramble.clone());
+    }
+
+    /// Handles the keys shared by the interactive modes: moves, undo, redo and reset. Returns
+    /// whether a move solved the puzzle.
+    pub fn handle(&mut self, action: Action) -> bool {
+        match action {
+            Action::Move(dir) => return self.slide(dir),
+            Action::Undo => self.undo(),
+            Action::Redo => return self.redo(),
+            Action::Reset => self.reset(),
+            Action::Scramble | Action::Quit => {}
+        }
+
+        false
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match (self.time, self.start) {
+            (Some(time), _) => time,
+            (None, Some(start)) => start.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    /// The puzzle followed by the move count, time and TPS.
+    pub fn view(&self, options: &RenderOptions) -> Result<String, Box<dyn Error>> {
+        let moves = self.path.len();
+        let elapsed = self.elapsed().as_secs_f64();
+        let tps = if elapsed > 0.0 {
+            moves as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        let status = if self.time.is_some() { "Solved!" } else { "" };
+
+        Ok(format!(
+            "{}\n\nMoves: {moves}  Time: {elapsed:.3}s  TPS: {tps:.2}  {status}\n",
+            render::terminal(&self.state, options)?,
+        ))
+    }
+}
+
+/// Lets the user solve the states in `queue` in order in the terminal, and then random states of
+/// the given size. The scramble key switches to the next state. If `record` is given, the moves
+/// of each solve are appended to it with timestamps.
+pub fn play(
+    mut queue: VecDeque<Puzzle>,
+    size: Size,
+    options: &RenderOptions,
+    keys: &Keybindings,
+    record: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    // The number of the state in the queue, or `None` for a random state
+    let total = queue.len();
+    let mut next = || match queue.pop_front() {
+        Some(state) => (Game::new(state), Some(total - queue.len())),
+        None => {
+            let mut state = Puzzle::new(size);
+            RandomState.scramble(&mut state);
+            (Game::new(goal::from_standard(&state)), None)
+        }
+    };
+
+    let mut screen = Screen::new()?;
+    let (mut game, mut number) = next();
+    let help = format!(
+        "{}: move  {}: undo  {}: redo  {}: reset  {}: next scramble  {}: quit",
+        keys.describe_moves(),
+        keys.describe(Action::Undo),
+        keys.describe(Action::Redo),
+        keys.describe(Action::Reset),
+        keys.describe(Action::Scramble),
+        keys.describe(Action::Quit),
+    );
+
+    while !util::interrupted() {
+        let position = match number {
+            Some(n) if total > 1 => format!("Scramble {n}/{total}\n\n"),
+            _ => String::new(),
+        };
+        screen.draw(&format!("{position}{}\n{help}\n", game.view(options)?))?;
+
+        let Some(key) = screen.read_key()? else {
+            continue;
+        };
+        match keys.action(key) {
+            Some(Action::Quit) => break,
+            Some(Action::Scramble) => (game, number) = next(),
+            Some(action) => {
+                if game.handle(action) {
+                    if let Some(path) = record {
+                        game.record().append_to(path)?;
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
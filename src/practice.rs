@@ -0,0 +1,277 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead, Write},
+    str::FromStr,
+    time::Instant,
+};
+
+use rand::seq::SliceRandom;
+use slidy::puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle};
+use thiserror::Error;
+
+use crate::{
+    grid,
+    keys::{Action, Keybindings},
+    notation,
+    play::Game,
+    render::{self, RenderOptions},
+    session::{Penalty, Session, Solve},
+    terminal::{Key, Screen},
+    util,
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PhaseError {
+    #[error(
+        "UnknownPhase: \"{0}\" is not a phase, expected e.g. \"last 2 rows\", \"last 2 columns\" \
+        or \"last 3x3\""
+    )]
+    Unknown(String),
+
+    #[error("PhaseTooSmall: the unsolved part of the puzzle must be at least 2x2")]
+    TooSmall,
+
+    #[error("PhaseTooLarge: \"{phase}\" does not fit in a {width}x{height} puzzle")]
+    TooLarge {
+        phase: String,
+        width: u64,
+        height: u64,
+    },
+}
+
+/// The part of a solve that is practised. The rest of the puzzle starts solved, and the unsolved
+/// part is always in the bottom right corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Rows(u64),
+    Columns(u64),
+    /// The last `width` columns of the last `height` rows.
+    Block(u64, u64),
+}
+
+impl FromStr for Phase {
+    type Err = PhaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || PhaseError::Unknown(s.to_string());
+        let words: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let phase = match words[..] {
+            ["last", n, "rows" | "row"] => Phase::Rows(n.parse().map_err(|_| unknown())?),
+            ["last", n, "columns" | "column" | "cols" | "col"] => {
+                Phase::Columns(n.parse().map_err(|_| unknown())?)
+            }
+            ["last", block] => {
+                let (width, height) = Size::from_str(block).map_err(|_| unknown())?.into();
+                Phase::Block(width, height)
+            }
+            _ => return Err(unknown()),
+        };
+
+        Ok(phase)
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Rows(n) => write!(f, "last {n} rows"),
+            Phase::Columns(n) => write!(f, "last {n} columns"),
+            Phase::Block(width, height) => write!(f, "last {width}x{height}"),
+        }
+    }
+}
+
+impl Phase {
+    /// The width and height of the unsolved part of a puzzle of the given size.
+    fn region(&self, size: Size) -> Result<(u64, u64), PhaseError> {
+        let (width, height) = size.into();
+        let region = match *self {
+            Phase::Rows(n) => (width, n),
+            Phase::Columns(n) => (n, height),
+            Phase::Block(w, h) => (w, h),
+        };
+
+        if region.0 < 2 || region.1 < 2 {
+            return Err(PhaseError::TooSmall);
+        }
+        if region.0 > width || region.1 > height {
+            return Err(PhaseError::TooLarge {
+                phase: self.to_string(),
+                width,
+                height,
+            });
+        }
+
+        Ok(region)
+    }
+
+    /// A random solvable state in which everything outside of the phase is solved.
+    pub fn scramble(&self, size: Size) -> Result<Puzzle, Box<dyn Error>> {
+        let (width, height) = size.into();
+        let (w, h) = self.region(size)?;
+
+        let positions: Vec<usize> = (height - h..height)
+            .flat_map(|y| (width - w..width).map(move |x| (x + y * width) as usize))
+            .collect();
+        let mut pieces = grid::pieces(&Puzzle::new(size));
+
+        loop {
+            let mut selected: Vec<u64> = positions.iter().map(|&i| pieces[i]).collect();
+            selected.shuffle(&mut rand::thread_rng());
+            for (&i, p) in positions.iter().zip(selected) {
+                pieces[i] = p;
+            }
+
+            let mut state = grid::from_pieces(&pieces, size)?;
+            if !state.is_solvable() {
+                // Swapping two pieces other than the gap fixes the parity
+                let mut tiles = positions.iter().filter(|&&i| pieces[i] != 0);
+                if let (Some(&a), Some(&b)) = (tiles.next(), tiles.next()) {
+                    state.swap_pieces(a as u64, b as u64);
+                }
+            }
+
+            if !state.is_solved() {
+                return Ok(state);
+            }
+        }
+    }
+}
+
+fn solve_line(solve: &Solve) -> String {
+    let moves = solve.solution.as_ref().map_or(0, |a| a.len_stm::<u64>());
+    format!("last: {}s, {moves} moves", solve.format_score())
+}
+
+fn stats_line(session: &Session, phase: Phase) -> String {
+    let (stats, mean_moves) = session.phase_stats(&phase.to_string());
+    let mean_moves = mean_moves.map_or("-".to_string(), |m| format!("{m:.1}"));
+    format!("{stats}  mean moves: {mean_moves}")
+}
+
+/// Repeatedly scrambles one phase of a puzzle of the given size and lets the user solve it in
+/// the terminal. The solves are added to `session`.
+pub fn practice(
+    phase: Phase,
+    size: Size,
+    mut session: Session,
+    options: &RenderOptions,
+    keys: &Keybindings,
+) -> Result<(), Box<dyn Error>> {
+    let mut game = Game::new(phase.scramble(size)?);
+    let mut screen = Screen::new()?;
+
+    while !util::interrupted() {
+        let last = session.solves.last().map(solve_line).unwrap_or_default();
+        let help = if game.time().is_some() {
+            format!(
+                "space: next scramble  {}: quit",
+                keys.describe(Action::Quit)
+            )
+        } else {
+            format!(
+                "{}: move  {}: undo  {}: redo  {}: reset  {}: skip scramble  {}: quit",
+                keys.describe_moves(),
+                keys.describe(Action::Undo),
+                keys.describe(Action::Redo),
+                keys.describe(Action::Reset),
+                keys.describe(Action::Scramble),
+                keys.describe(Action::Quit),
+            )
+        };
+        screen.draw(&format!(
+            "{phase}\n\n{}\n{}\n{last}\n\n{help}\n",
+            game.view(options)?,
+            stats_line(&session, phase),
+        ))?;
+
+        let Some(key) = screen.read_key()? else {
+            continue;
+        };
+        match keys.action(key) {
+            Some(Action::Quit) => break,
+            Some(Action::Scramble) => game = Game::new(phase.scramble(size)?),
+            _ if key == Key::Char(' ') && game.time().is_some() => {
+                game = Game::new(phase.scramble(size)?)
+            }
+            Some(action) => {
+                if game.handle(action) {
+                    session.add(Solve {
+                        time: game.time().unwrap_or_default().as_millis() as u64,
+                        penalty: Penalty::None,
+                        scramble: game.scramble().clone(),
+                        solution: Some(game.solution()),
+                        phase: Some(phase.to_string()),
+                    })?;
+                }
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Like [`practice`], but the solutions are read as lines from stdin, e.g. pasted from another
+/// simulator. The time of each solve is from when the scramble is printed until its solution is
+/// entered. An empty line or the end of the input stops.
+pub fn practice_pasted(
+    phase: Phase,
+    size: Size,
+    mut session: Session,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut lines = io::stdin().lock().lines();
+    let mut out = io::stdout().lock();
+
+    'scrambles: while !util::interrupted() {
+        let scramble = phase.scramble(size)?;
+        writeln!(out, "{}\n{scramble}", render::terminal(&scramble, options)?)?;
+        let start = Instant::now();
+
+        loop {
+            write!(out, "solution: ")?;
+            out.flush()?;
+
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break 'scrambles,
+            };
+            if line.trim().is_empty() {
+                break 'scrambles;
+            }
+            let time = start.elapsed().as_millis() as u64;
+
+            let solution = match notation::parse_algorithm(line.trim()) {
+                Ok(solution) => solution,
+                Err(e) => {
+                    writeln!(out, "error: {e}")?;
+                    continue;
+                }
+            };
+
+            let mut state = scramble.clone();
+            if !state.try_apply_alg(&solution) || !state.is_solved() {
+                writeln!(out, "that doesn't solve the scramble, try again")?;
+                continue;
+            }
+
+            let solve = Solve {
+                time,
+                penalty: Penalty::None,
+                scramble,
+                solution: Some(solution),
+                phase: Some(phase.to_string()),
+            };
+            writeln!(out, "{}", solve_line(&solve))?;
+            session.add(solve)?;
+            writeln!(out, "{}\n", stats_line(&session, phase))?;
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The minimum time between redraws of a progress bar.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+const BAR_WIDTH: u64 = 30;
+
+/// Enables progress bars. They are only drawn if stderr is a terminal, so that they don't end up
+/// in logs.
+pub fn enable() {
+    ENABLED.store(std::io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+/// A progress bar drawn on stderr, showing the number of items done, the rate, and the remaining
+/// time if the total number of items is known.
+pub struct Progress {
+    label: &'static str,
+    total: Option<u64>,
+    done: u64,
+    start: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: Option<u64>) -> Self {
+        Self {
+            label,
+            total,
+            done: 0,
+            start: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    pub fn inc(&mut self) {
+        self.set(self.done + 1, self.total);
+    }
+
+    /// Sets the number of items done, and the total, which may change as the work progresses.
+    pub fn set(&mut self, done: u64, total: Option<u64>) {
+        self.done = done;
+        self.total = total;
+
+        if ENABLED.load(Ordering::Relaxed)
+            && self
+                .last_draw
+                .map_or(true, |t| t.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw();
+        }
+    }
+
+    fn draw(&mut self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        let line = match self.total {
+            Some(total) => {
+                let done = self.done.min(total);
+                let filled = (BAR_WIDTH * done).checked_div(total).unwrap_or(BAR_WIDTH);
+                let eta = if rate > 0.0 {
+                    format_duration((total - done) as f64 / rate)
+                } else {
+                    "?".to_string()
+                };
+                format!(
+                    "{} [{}{}] {done}/{total} {rate:.1}/s ETA {eta}",
+                    self.label,
+                    "#".repeat(filled as usize),
+                    "-".repeat((BAR_WIDTH - filled) as usize)
+                )
+            }
+            None => format!("{} {} {rate:.1}/s", self.label, self.done),
+        };
+
+        // Return to the start of the line and clear it before redrawing
+        eprint!("\r\x1b[K{line}");
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.last_draw.is_some() {
+            self.draw();
+            eprintln!();
+        }
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
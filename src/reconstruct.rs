@@ -0,0 +1,233 @@
+use std::{error::Error, fmt::Display, fs::OpenOptions, io::Write, str::FromStr};
+
+use slidy::algorithm::algorithm::Algorithm;
+use thiserror::Error;
+
+use crate::{enums::Convention, notation};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ReconstructionError {
+    #[error("Empty: the reconstruction contains no moves")]
+    Empty,
+
+    #[error("MissingTime: \"{0}\" has no timestamp")]
+    MissingTime(String),
+
+    #[error("InvalidTime: \"{0}\" is not a time")]
+    Time(String),
+
+    #[error("DecreasingTime: {time} is earlier than the previous timestamp {previous}")]
+    DecreasingTime { time: String, previous: String },
+
+    #[error("InvalidMoves: {0}")]
+    Moves(String),
+}
+
+/// A group of moves, and the time in seconds at which the last of them was made.
+#[derive(Clone, Debug)]
+struct Timed {
+    alg: Algorithm,
+    time: f64,
+}
+
+/// A solve annotated with timestamps, written as groups of moves each followed by `@` and the time
+/// in seconds since the solve started, with phases separated by `|`:
+///
+/// ```text
+/// RDD@0.4 LU@0.9 | R3@1.8 DLU@2.5
+/// ```
+///
+/// A timestamp can be given after every move, or only after the last move of each phase.
+#[derive(Clone, Debug, Default)]
+pub struct Reconstruction {
+    phases: Vec<Vec<Timed>>,
+}
+
+impl FromStr for Reconstruction {
+    type Err = ReconstructionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut previous = 0.0;
+        let phases = s
+            .split('|')
+            .map(|phase| {
+                phase
+                    .split_whitespace()
+                    .map(|token| {
+                        let (moves, time) = token
+                            .rsplit_once('@')
+                            .ok_or_else(|| ReconstructionError::MissingTime(token.to_string()))?;
+                        let time = time
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|t| t.is_finite() && *t >= 0.0)
+                            .ok_or_else(|| ReconstructionError::Time(time.to_string()))?;
+                        if time < previous {
+                            return Err(ReconstructionError::DecreasingTime {
+                                time: format!("{time:.3}"),
+                                previous: format!("{previous:.3}"),
+                            });
+                        }
+                        previous = time;
+
+                        let alg =
+                            notation::parse_algorithm(moves).map_err(ReconstructionError::Moves)?;
+                        Ok(Timed { alg, time })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let reconstruction = Self { phases };
+        if reconstruction.is_empty() {
+            return Err(ReconstructionError::Empty);
+        }
+
+        Ok(reconstruction)
+    }
+}
+
+/// A pause between two consecutive timestamps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pause {
+    /// The time at which the pause started.
+    pub start: f64,
+    pub length: f64,
+}
+
+impl Display for Reconstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|phase| {
+                phase
+                    .iter()
+                    .map(|t| {
+                        let moves = t.alg.display_short_unspaced().to_string();
+                        let moves = match notation::convention() {
+                            Convention::Tile => moves,
+                            Convention::Blank => notation::flip(&moves),
+                        };
+                        format!("{moves}@{:.3}", t.time)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+
+        write!(f, "{}", phases.join(" | "))
+    }
+}
+
+impl Reconstruction {
+    /// Adds moves made `time` seconds after the start of the solve, for recording a solve as it
+    /// is done.
+    pub fn push(&mut self, alg: Algorithm, time: f64) {
+        if alg.len_stm::<u64>() == 0 {
+            return;
+        }
+
+        match self.phases.last_mut() {
+            Some(phase) => phase.push(Timed { alg, time }),
+            None => self.phases.push(vec![Timed { alg, time }]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.iter().all(Vec::is_empty)
+    }
+
+    /// Appends the reconstruction to a file as one line, in the format read by `reconstruct`.
+    pub fn append_to(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("{path}: {e}"))?;
+        writeln!(file, "{self}")?;
+
+        Ok(())
+    }
+
+    pub fn time(&self) -> f64 {
+        self.phases.iter().flatten().last().map_or(0.0, |t| t.time)
+    }
+
+    pub fn moves(&self) -> u64 {
+        self.phases
+            .iter()
+            .flatten()
+            .map(|t| t.alg.len_stm::<u64>())
+            .sum()
+    }
+
+    /// The solution without the timestamps.
+    pub fn solution(&self) -> Algorithm {
+        let mut solution = Algorithm::new();
+        for t in self.phases.iter().flatten() {
+            solution += t.alg.clone();
+        }
+        solution
+    }
+
+    /// The time at which each single tile move was made. Moves in a group with one timestamp are
+    /// spread evenly over the time since the previous timestamp.
+    pub fn move_times(&self) -> Vec<f64> {
+        let mut start = 0.0;
+        let mut times = Vec::new();
+        for t in self.phases.iter().flatten() {
+            let n = t.alg.len_stm::<u64>();
+            times.extend((1..=n).map(|k| start + (t.time - start) * k as f64 / n as f64));
+            start = t.time;
+        }
+        times
+    }
+
+    /// The time and number of moves of each phase.
+    pub fn splits(&self) -> Vec<(f64, u64)> {
+        let mut start = 0.0;
+        self.phases
+            .iter()
+            .filter(|phase| !phase.is_empty())
+            .map(|phase| {
+                let end = phase.last().unwrap().time;
+                let moves = phase.iter().map(|t| t.alg.len_stm::<u64>()).sum();
+                let split = (end - start, moves);
+                start = end;
+                split
+            })
+            .collect()
+    }
+
+    /// The gaps between consecutive timestamps that are at least `threshold` seconds long.
+    pub fn pauses(&self, threshold: f64) -> Vec<Pause> {
+        let mut start = 0.0;
+        let mut pauses = Vec::new();
+        for t in self.phases.iter().flatten() {
+            if t.time - start >= threshold {
+                pauses.push(Pause {
+                    start,
+                    length: t.time - start,
+                });
+            }
+            start = t.time;
+        }
+        pauses
+    }
+}
+
+/// Turns per second, or 0 if no time has passed.
+pub fn tps(moves: u64, time: f64) -> f64 {
+    if time > 0.0 {
+        moves as f64 / time
+    } else {
+        0.0
+    }
+}
+
+impl Display for Pause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.3} at {:.3}", self.length, self.start)
+    }
+}
@@ -0,0 +1,720 @@
+use std::{error::Error, path::Path, rc::Rc, str::FromStr};
+
+use base64::Engine;
+use clap::Args;
+use palette::{rgb::Rgba, Srgb};
+use resvg::usvg::{self, fontdb, TreeParsing, TreeTextToPath, TreeWriting};
+use slidy::puzzle::{
+    color_scheme::{tiled::Tiled, ColorScheme, Scheme, SchemeList},
+    coloring::{Coloring, Monochrome, Rainbow},
+    label::label::{Label, SplitFringe},
+    puzzle::Puzzle,
+    render::{Borders, Renderer, RendererBuilder, Text},
+    size::Size,
+    sliding_puzzle::SlidingPuzzle,
+};
+use svg::{
+    node::{
+        self,
+        element::{Rectangle, Style, Text as TextElement},
+    },
+    Document,
+};
+
+use crate::{
+    animate::{self, Rasterizer},
+    coloring::{
+        parse_color, Alternating, DistanceHeatmap, Gradient, Lightened, Palette, RainbowFull,
+        SolvedStatus, Stroke,
+    },
+    enums::{boxed_label, grid_size, ColoringType, LabelType, OutputFormat, Theme},
+    goal, grid,
+};
+
+/// Space around the edge of the image.
+pub const PADDING: f32 = 2.0;
+
+/// Space between adjacent tiles.
+pub const TILE_GAP: f32 = 2.0;
+
+#[derive(Args, Clone, Debug)]
+pub struct RenderOptions {
+    #[clap(short, long, default_value = "fringe")]
+    pub label: LabelType,
+
+    #[clap(short, long, default_value = "rainbow-bright-full")]
+    pub coloring: ColoringType,
+
+    #[clap(
+        long,
+        help = "Comma separated list of colors (#rrggbb or #rrggbbaa) used by --coloring custom, \
+        gradient and heatmap"
+    )]
+    pub palette: Option<Palette>,
+
+    #[clap(
+        long,
+        conflicts_with = "palette",
+        help = "File containing the colors used by --coloring custom, gradient and heatmap"
+    )]
+    pub palette_file: Option<String>,
+
+    #[clap(
+        long,
+        help = "Label of a subscheme drawn over each block of --subgrid pieces [default: \
+        split-fringe with --label grids]"
+    )]
+    pub sublabel: Option<LabelType>,
+
+    #[clap(long, help = "Coloring of the subscheme [default: --coloring]")]
+    pub subcoloring: Option<ColoringType>,
+
+    #[clap(
+        long,
+        help = "Size of the blocks the subscheme is repeated over [default: half the puzzle \
+        size, rounded up]"
+    )]
+    pub subgrid: Option<Size>,
+
+    #[clap(
+        long,
+        help = "With --coloring solved-status, only color pieces as solved once every piece with \
+        the same label is solved"
+    )]
+    pub per_label: bool,
+
+    #[clap(long, default_value = "#4caf50", value_parser = parse_color)]
+    pub solved_color: Rgba,
+
+    #[clap(long, default_value = "#9e9e9e", value_parser = parse_color)]
+    pub unsolved_color: Rgba,
+
+    #[clap(short, long, default_value = "75.0")]
+    pub tile_size: f32,
+
+    #[clap(
+        long,
+        help = "Width of the image in pixels. The tile size is chosen so that the puzzle fits, \
+        and the puzzle is centered"
+    )]
+    pub width: Option<f32>,
+
+    #[clap(
+        long,
+        help = "Height of the image in pixels. The tile size is chosen so that the puzzle fits, \
+        and the puzzle is centered"
+    )]
+    pub height: Option<f32>,
+
+    #[clap(
+        long,
+        help = "Font size of the text on the tiles [default: 40% of the tile size]"
+    )]
+    pub font_size: Option<f32>,
+
+    #[clap(
+        long,
+        help = "Shrink the text so that the largest number fits on a tile"
+    )]
+    pub auto_fit_text: bool,
+
+    #[clap(long, help = "Font family used for the text on the tiles")]
+    pub font_family: Option<String>,
+
+    #[clap(
+        long,
+        help = "Font file (ttf, otf, woff or woff2) to embed in the image"
+    )]
+    pub embed_font: Option<String>,
+
+    #[clap(
+        long,
+        help = "Convert text to paths, so the image looks the same without the font installed"
+    )]
+    pub text_to_paths: bool,
+
+    #[clap(long, help = "Leave out the numbers on the tiles")]
+    pub no_text: bool,
+
+    #[clap(long, help = "Radius of the rounded corners of the tiles")]
+    pub corner_radius: Option<f32>,
+
+    #[clap(
+        long,
+        value_parser = Stroke::from_str,
+        help = "Outline drawn around each tile, written as <color>:<width>, e.g. #000000:2"
+    )]
+    pub tile_stroke: Option<Stroke>,
+
+    #[clap(
+        long,
+        value_parser = parse_color,
+        help = "Background color of the image (#rrggbb or #rrggbbaa) [default: transparent]"
+    )]
+    pub background: Option<Rgba>,
+
+    #[clap(long, value_enum, default_value = "light")]
+    pub theme: Theme,
+
+    #[clap(
+        long,
+        help = "Stylesheet to include in the image. The tiles are drawn with the classes \
+        \"tile\", \"label-N\" and \"piece-N\" so that they can be styled"
+    )]
+    pub css: Option<String>,
+
+    #[clap(
+        long,
+        help = "SVG file to insert the image into, in place of the text {{puzzle}}"
+    )]
+    pub template: Option<String>,
+
+    #[clap(
+        long,
+        help = "Format of the output file. EPS files contain a raster image [default: from the \
+        file extension, or svg]"
+    )]
+    pub format: Option<OutputFormat>,
+}
+
+impl RenderOptions {
+    /// The options with the tile size replaced by the largest one for which a puzzle of the given
+    /// size fits in `--width` and `--height`, if either is set.
+    pub fn fitted(&self, size: Size) -> Self {
+        let (width, height) = size.into();
+        let fit = |pixels: f32, tiles: u64| {
+            (pixels - 2.0 * PADDING - (tiles - 1) as f32 * TILE_GAP) / tiles as f32
+        };
+
+        let tile_size = match (self.width, self.height) {
+            (Some(w), Some(h)) => fit(w, width).min(fit(h, height)),
+            (Some(w), None) => fit(w, width),
+            (None, Some(h)) => fit(h, height),
+            (None, None) => return self.clone(),
+        };
+
+        Self {
+            tile_size: tile_size.max(1.0),
+            ..self.clone()
+        }
+    }
+}
+
+/// The color schemes used for `options`, in the order they are layered.
+pub fn schemes(
+    state: &Puzzle,
+    options: &RenderOptions,
+) -> Result<Vec<Box<dyn ColorScheme>>, Box<dyn Error>> {
+    let size = state.size();
+    let grid_size = grid_size(size);
+    let label = boxed_label(options.label, size)?;
+
+    let palette = match (&options.palette, &options.palette_file) {
+        (Some(palette), _) => Some(palette.clone()),
+        (None, Some(path)) => Some(Palette::from_file(path)?),
+        (None, None) => None,
+    };
+
+    if options.coloring == ColoringType::SolvedStatus {
+        let per_label = options.per_label.then_some(label.as_ref());
+        return Ok(vec![Box::new(SolvedStatus::new(
+            state,
+            per_label,
+            options.solved_color,
+            options.unsolved_color,
+        ))]);
+    }
+
+    if options.coloring == ColoringType::Heatmap {
+        let gradient = palette.map_or_else(DistanceHeatmap::default_gradient, Gradient::new);
+        return Ok(vec![Box::new(DistanceHeatmap::new(state, gradient))]);
+    }
+
+    let coloring = boxed_coloring(options.coloring, palette.clone())?;
+
+    let mut schemes: Vec<Box<dyn ColorScheme>> =
+        vec![Box::new(Scheme::new(label, coloring.clone()))];
+
+    let subgrid = match options.subgrid {
+        Some(subgrid) => subgrid,
+        None => Size::new(grid_size.0, grid_size.1)?,
+    };
+    let sublabel = match (options.sublabel, options.label) {
+        (Some(sublabel), _) => Some(boxed_label(sublabel, subgrid)?),
+        (None, LabelType::Grids) => Some(Box::new(SplitFringe) as Box<dyn Label>),
+        (None, _) => None,
+    };
+
+    if let Some(sublabel) = sublabel {
+        let subcoloring = match options.subcoloring {
+            Some(subcoloring) => boxed_coloring(subcoloring, palette)?,
+            None => coloring,
+        };
+
+        schemes.push(Box::new(Tiled::new(
+            Scheme::new(sublabel, subcoloring),
+            subgrid,
+        )))
+    }
+
+    Ok(schemes)
+}
+
+fn boxed_coloring(
+    coloring: ColoringType,
+    palette: Option<Palette>,
+) -> Result<Rc<dyn Coloring>, Box<dyn Error>> {
+    Ok(match coloring {
+        ColoringType::None => Rc::new(Monochrome::new(Rgba::new(0.0, 0.0, 0.0, 0.0))),
+        ColoringType::Black => Rc::new(Monochrome::new(Rgba::new(0.0, 0.0, 0.0, 1.0))),
+        ColoringType::Rainbow => Rc::new(Rainbow::default()),
+        ColoringType::RainbowBright => Rc::new(Lightened {
+            coloring: Rainbow::default(),
+            amount: 0.3,
+        }),
+        ColoringType::RainbowFull => Rc::new(RainbowFull { lightness: 0.5 }),
+        ColoringType::RainbowBrightFull => Rc::new(RainbowFull { lightness: 0.75 }),
+        ColoringType::Alternating => Rc::new(Alternating {
+            coloring: Rainbow::default(),
+        }),
+        ColoringType::Gradient => Rc::new(palette.map(Gradient::new).unwrap_or_default()),
+        ColoringType::Custom => {
+            Rc::new(palette.ok_or("--coloring custom requires --palette or --palette-file")?)
+        }
+        ColoringType::OkabeIto => Rc::new(Palette::okabe_ito()),
+        ColoringType::Viridis => Rc::new(Gradient::viridis()),
+        ColoringType::SolvedStatus | ColoringType::Heatmap => {
+            return Err("solved-status and heatmap can not be used as a subcoloring".into())
+        }
+    })
+}
+
+/// The color of each piece, from the color of its solved position, indexed by the piece minus
+/// one.
+pub fn piece_colors(state: &Puzzle, options: &RenderOptions) -> Result<Vec<Rgba>, Box<dyn Error>> {
+    let schemes = schemes(state, options)?;
+    let scheme_list = SchemeList::new(&schemes)?;
+
+    let size = state.size();
+    Ok((1..size.area())
+        .map(|p| scheme_list.color(size, goal::solved_pos_xy(p, size)))
+        .collect())
+}
+
+/// Draws the state as colored blocks using 24-bit ANSI escape codes, one line per row.
+pub fn terminal(state: &Puzzle, options: &RenderOptions) -> Result<String, Box<dyn Error>> {
+    terminal_highlighted(state, options, None)
+}
+
+/// Like [`terminal`], with the number on the `highlight` piece in bold and underlined.
+pub fn terminal_highlighted(
+    state: &Puzzle,
+    options: &RenderOptions,
+    highlight: Option<u64>,
+) -> Result<String, Box<dyn Error>> {
+    let colors = piece_colors(state, options)?;
+    let size = state.size();
+    let (width, _) = size.into();
+    let cell_width = (size.area() - 1).to_string().len() + 2;
+
+    let rows = grid::pieces(state)
+        .chunks(width as usize)
+        .map(|row| {
+            let mut line = String::new();
+            for &piece in row {
+                if piece == 0 {
+                    line += &" ".repeat(cell_width);
+                    continue;
+                }
+
+                let c: Rgba<Srgb, u8> = colors[piece as usize - 1].into_format();
+                // Black or white text, whichever is more readable on the tile
+                let luma = 0.299 * c.red as f32 + 0.587 * c.green as f32 + 0.114 * c.blue as f32;
+                let text = if luma > 128.0 { 30 } else { 97 };
+                let style = if highlight == Some(piece) {
+                    "\x1b[1;4m"
+                } else {
+                    ""
+                };
+                let number = if options.no_text {
+                    String::new()
+                } else {
+                    piece.to_string()
+                };
+
+                line += &format!(
+                    "\x1b[48;2;{};{};{}m\x1b[{text}m{style}{number:^cell_width$}\x1b[0m",
+                    c.red, c.green, c.blue
+                );
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+
+    Ok(rows.join("\n"))
+}
+
+/// Formats a color as a hex string, ignoring the alpha channel.
+pub fn hex(color: Rgba) -> String {
+    let c: Rgba<Srgb, u8> = color.into_format();
+    format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue)
+}
+
+pub fn document(state: &Puzzle, options: &RenderOptions) -> Result<Document, Box<dyn Error>> {
+    if options.css.is_some() {
+        return classed_document(state, options);
+    }
+
+    let schemes = schemes(state, options)?;
+    let scheme_list = SchemeList::new(&schemes)?;
+
+    let (background, text_color, border) = theme_colors(options);
+
+    let mut builder = RendererBuilder::with_scheme(&scheme_list)
+        .text(
+            Text::default()
+                .font_size(font_size(state.size(), options))
+                .color(text_color),
+        )
+        .tile_size(options.tile_size)
+        .tile_gap(TILE_GAP)
+        .padding(PADDING);
+
+    if let Some(background) = background {
+        builder = builder.background_color(background);
+    }
+
+    if let Some(radius) = options.corner_radius {
+        builder = builder.tile_rounding(radius);
+    }
+
+    if let Some(stroke) = border {
+        builder = builder.borders(Borders::new().color(stroke.color).thickness(stroke.width));
+    }
+
+    let renderer: Renderer<_, _, _> = builder.build();
+
+    style(fit(renderer.render(state)?, options), options)
+}
+
+/// Draws the same layout as [`document`], but with each tile tagged with classes for its label
+/// and piece number so that it can be styled by `--css`.
+fn classed_document(state: &Puzzle, options: &RenderOptions) -> Result<Document, Box<dyn Error>> {
+    let size = state.size();
+    let (width, height) = size.into();
+    let label = boxed_label(options.label, size)?;
+    let colors = piece_colors(state, options)?;
+    let (background, text_color, border) = theme_colors(options);
+    let tile_size = options.tile_size;
+
+    let extent = |tiles: u64| 2.0 * PADDING + tiles as f32 * (tile_size + TILE_GAP) - TILE_GAP;
+    let mut document = Document::new()
+        .set("width", extent(width))
+        .set("height", extent(height));
+
+    if let Some(background) = background {
+        document = document.add(
+            Rectangle::new()
+                .set("class", "background")
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", hex(background)),
+        );
+    }
+
+    for (i, piece) in grid::pieces(state).into_iter().enumerate() {
+        if piece == 0 {
+            continue;
+        }
+
+        let (x, y) = tile_center((i as u64 % width, i as u64 / width), options);
+        let label = label.position_label(size, goal::solved_pos_xy(piece, size));
+
+        let mut rect = Rectangle::new()
+            .set("class", format!("tile label-{label} piece-{piece}"))
+            .set("x", x - tile_size / 2.0)
+            .set("y", y - tile_size / 2.0)
+            .set("width", tile_size)
+            .set("height", tile_size)
+            .set("rx", options.corner_radius.unwrap_or(0.0))
+            .set("fill", hex(colors[piece as usize - 1]));
+        if let Some(stroke) = border {
+            rect = rect
+                .set("stroke", hex(stroke.color))
+                .set("stroke-width", stroke.width);
+        }
+
+        document = document.add(rect).add(
+            TextElement::new()
+                .set("class", format!("label-{label} piece-{piece}"))
+                .set("x", x)
+                .set("y", y)
+                .set("font-size", font_size(size, options))
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", hex(text_color))
+                .add(node::Text::new(piece.to_string())),
+        );
+    }
+
+    style(fit(document, options), options)
+}
+
+/// Sets the size of the image to `--width` and `--height`, if either is set, with the puzzle
+/// centered in the extra space.
+pub fn fit(document: Document, options: &RenderOptions) -> Document {
+    if options.width.is_none() && options.height.is_none() {
+        return document;
+    }
+
+    let Some((width, height)) = document_size(&document) else {
+        return document;
+    };
+    let (target_width, target_height) = (
+        options.width.unwrap_or(width),
+        options.height.unwrap_or(height),
+    );
+
+    document
+        .set("width", target_width)
+        .set("height", target_height)
+        .set(
+            "viewBox",
+            format!(
+                "{} {} {target_width} {target_height}",
+                (width - target_width) / 2.0,
+                (height - target_height) / 2.0
+            ),
+        )
+}
+
+/// The center of the tile at `pos` in an image created by [`document`].
+pub fn tile_center(pos: (u64, u64), options: &RenderOptions) -> (f32, f32) {
+    let offset =
+        |i: u64| PADDING + i as f32 * (options.tile_size + TILE_GAP) + options.tile_size / 2.0;
+    (offset(pos.0), offset(pos.1))
+}
+
+/// The background, text and border colors after applying the theme. `None` means the renderer
+/// default.
+pub fn theme_colors(options: &RenderOptions) -> (Option<Rgba>, Rgba, Option<Stroke>) {
+    let (background, text, border) = match options.theme {
+        Theme::Light => (None, Rgba::new(0.0, 0.0, 0.0, 1.0), None),
+        Theme::Dark => (
+            Some(parse_color("#121212").unwrap()),
+            Rgba::new(1.0, 1.0, 1.0, 1.0),
+            Some(Stroke {
+                color: parse_color("#e0e0e0").unwrap(),
+                width: 1.0,
+            }),
+        ),
+    };
+
+    (
+        options.background.or(background),
+        text,
+        options.tile_stroke.or(border),
+    )
+}
+
+/// The font size of the text on the tiles of a puzzle of the given size.
+pub fn font_size(size: Size, options: &RenderOptions) -> f32 {
+    let font_size = options.font_size.unwrap_or(options.tile_size * 30.0 / 75.0);
+
+    if options.auto_fit_text {
+        // Digits are roughly 0.6em wide in most fonts, and we leave a margin of 10% of the tile
+        // size on each side
+        let digits = (size.area() - 1).to_string().len() as f32;
+        font_size.min(options.tile_size * 0.8 / (0.6 * digits))
+    } else {
+        font_size
+    }
+}
+
+/// Adds a stylesheet setting the font family of all text (or hiding it), and embedding the font
+/// file if there is one.
+pub fn style(document: Document, options: &RenderOptions) -> Result<Document, Box<dyn Error>> {
+    let mut css = String::new();
+    let mut family = options.font_family.clone();
+
+    if let Some(path) = &options.embed_font {
+        let path = Path::new(path);
+        let mime = match path.extension().and_then(|e| e.to_str()) {
+            Some("otf") => "font/otf",
+            Some("woff") => "font/woff",
+            Some("woff2") => "font/woff2",
+            _ => "font/ttf",
+        };
+        let data = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+        let name = family.get_or_insert_with(|| "embedded".to_string());
+        css += &format!(
+            "@font-face {{ font-family: \"{name}\"; src: url(data:{mime};base64,{data}); }}\n"
+        );
+    }
+
+    if let Some(family) = family {
+        css += &format!("text {{ font-family: \"{family}\"; }}\n");
+    }
+
+    if options.no_text {
+        css += "text { display: none; }\n";
+    }
+
+    if let Some(path) = &options.css {
+        css += &std::fs::read_to_string(path)?;
+    }
+
+    if css.is_empty() {
+        Ok(document)
+    } else {
+        Ok(document.add(Style::new(css)))
+    }
+}
+
+/// Saves a document as an SVG, PDF or EPS file, converting text to paths first if requested.
+/// The format is taken from `--format`, or from the file extension if it isn't set.
+pub fn save<P: AsRef<Path>>(
+    path: P,
+    document: &Document,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let format = options
+        .format
+        .unwrap_or_else(|| OutputFormat::from_path(path.as_ref()));
+
+    if let Some(template) = &options.template {
+        let template = std::fs::read_to_string(template)?;
+        // Drop the XML declaration, since the image is no longer at the start of a file
+        let image = document.to_string();
+        let image = image
+            .strip_prefix("<?xml")
+            .and_then(|s| s.split_once("?>"))
+            .map_or(image.as_str(), |(_, s)| s);
+        std::fs::write(path, template.replace("{{puzzle}}", image))?;
+        return Ok(());
+    }
+
+    if format == OutputFormat::Svg && !options.text_to_paths {
+        svg::save(path, document)?;
+        return Ok(());
+    }
+
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_system_fonts();
+    if let Some(font) = &options.embed_font {
+        fontdb.load_font_file(font)?;
+    }
+
+    if format == OutputFormat::Eps {
+        let image = Rasterizer::with_fontdb(fontdb).rasterize(document)?;
+        return animate::save_eps(path, &image);
+    }
+
+    let mut tree = usvg::Tree::from_str(&document.to_string(), &usvg::Options::default())?;
+    tree.convert_text(&fontdb);
+
+    match format {
+        OutputFormat::Svg => std::fs::write(path, tree.to_string(&usvg::XmlOptions::default()))?,
+        OutputFormat::Pdf => std::fs::write(
+            path,
+            svg2pdf::convert_tree(&tree, svg2pdf::Options::default()),
+        )?,
+        OutputFormat::Eps => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// The width and height of a rendered document, read from its attributes.
+pub fn document_size(document: &Document) -> Option<(f32, f32)> {
+    let attributes = document.get_attributes();
+    let get = |name: &str| attributes.get(name)?.to_string().parse::<f32>().ok();
+    Some((get("width")?, get("height")?))
+}
+
+/// Adds a line of text centered beneath a document.
+pub fn caption(document: Document, text: &str, options: &RenderOptions) -> Document {
+    let (width, height) = document_size(&document).unwrap_or_default();
+    let font_size = options.tile_size / 4.0;
+    let (_, text_color, _) = theme_colors(options);
+
+    Document::new()
+        .set("width", width)
+        .set("height", height + font_size * 1.5)
+        .add(document)
+        .add(
+            TextElement::new()
+                .set("x", width / 2.0)
+                .set("y", height + font_size * 0.75)
+                .set("font-size", font_size)
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", hex(text_color))
+                .add(node::Text::new(text)),
+        )
+}
+
+/// Lays out `documents` in a grid with `columns` columns, with `spacing` pixels between
+/// adjacent images.
+pub fn sheet(documents: Vec<Document>, columns: u64, spacing: f32) -> Document {
+    let sizes: Vec<_> = documents
+        .iter()
+        .map(|d| document_size(d).unwrap_or_default())
+        .collect();
+    let cell_width = sizes.iter().map(|s| s.0).fold(0.0, f32::max);
+    let cell_height = sizes.iter().map(|s| s.1).fold(0.0, f32::max);
+
+    let columns = columns.max(1).min(documents.len().max(1) as u64);
+    let rows = (documents.len() as u64).div_ceil(columns);
+
+    let width = columns as f32 * (cell_width + spacing) - spacing;
+    let height = rows as f32 * (cell_height + spacing) - spacing;
+
+    let mut sheet = Document::new()
+        .set("width", width.max(0.0))
+        .set("height", height.max(0.0));
+
+    for (i, document) in documents.into_iter().enumerate() {
+        let (column, row) = (i as u64 % columns, i as u64 / columns);
+        sheet = sheet.add(
+            document
+                .set("x", column as f32 * (cell_width + spacing))
+                .set("y", row as f32 * (cell_height + spacing)),
+        );
+    }
+
+    sheet
+}
+
+/// A page of text with one line per entry of `lines`, as wide as `width`, e.g. the answers to a
+/// [`sheet`] of scrambles.
+pub fn text_page(lines: &[String], width: f32, options: &RenderOptions) -> Document {
+    let font_size = options.tile_size / 4.0;
+    let line_height = font_size * 1.5;
+    let (background, text_color, _) = theme_colors(options);
+    let height = 2.0 * PADDING + lines.len() as f32 * line_height;
+
+    let mut document = Document::new().set("width", width).set("height", height);
+    if let Some(background) = background {
+        document = document.add(
+            Rectangle::new()
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", hex(background)),
+        );
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        document = document.add(
+            TextElement::new()
+                .set("x", PADDING)
+                .set("y", PADDING + (i as f32 + 0.5) * line_height)
+                .set("font-size", font_size)
+                .set("dominant-baseline", "central")
+                .set("fill", hex(text_color))
+                .add(node::Text::new(line.as_str())),
+        );
+    }
+
+    document
+}
@@ -0,0 +1,325 @@
+use std::{
+    error::Error,
+    io::{self, BufRead, IsTerminal, Write},
+    str::FromStr,
+    time::Instant,
+};
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{
+        puzzle::Puzzle,
+        scrambler::{RandomState, Scrambler},
+        size::Size,
+        sliding_puzzle::SlidingPuzzle,
+    },
+};
+use thiserror::Error;
+
+use crate::{
+    enums::LabelType,
+    goal, heuristic, notation,
+    reconstruct::Reconstruction,
+    render::{self, RenderOptions},
+    solvers::{self, SolveFn},
+    util,
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ReplError {
+    #[error("UnknownCommand: \"{0}\" is not a command, enter help to list them")]
+    UnknownCommand(String),
+
+    #[error("MissingArgument: {0} needs an argument")]
+    MissingArgument(&'static str),
+
+    #[error("InvalidArgument: {0}")]
+    InvalidArgument(String),
+}
+
+/// A line entered in the REPL.
+#[derive(Clone, Debug)]
+pub enum ReplCommand {
+    Apply(Algorithm),
+    Undo,
+    Redo,
+    Solve,
+    Render(String),
+    Set(Puzzle),
+    Reset(Option<Size>),
+    Scramble,
+    Md,
+    Print,
+    Record,
+    Stop,
+    Help,
+    Quit,
+}
+
+impl FromStr for ReplCommand {
+    type Err = ReplError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        let rest = rest.trim();
+        let required = |name: &'static str| {
+            if rest.is_empty() {
+                Err(ReplError::MissingArgument(name))
+            } else {
+                Ok(rest)
+            }
+        };
+
+        let command = match name {
+            // The algorithm may contain spaces, so everything after the name is used
+            "apply" => ReplCommand::Apply(
+                notation::parse_algorithm(required("apply")?)
+                    .map_err(ReplError::InvalidArgument)?,
+            ),
+            "undo" => ReplCommand::Undo,
+            "redo" => ReplCommand::Redo,
+            "solve" => ReplCommand::Solve,
+            "render" => ReplCommand::Render(required("render")?.to_string()),
+            "set" => ReplCommand::Set(
+                notation::parse_state(required("set")?).map_err(ReplError::InvalidArgument)?,
+            ),
+            "reset" => ReplCommand::Reset(
+                (!rest.is_empty())
+                    .then(|| Size::from_str(rest))
+                    .transpose()
+                    .map_err(|e| ReplError::InvalidArgument(e.to_string()))?,
+            ),
+            "scramble" => ReplCommand::Scramble,
+            "md" => ReplCommand::Md,
+            "print" | "state" => ReplCommand::Print,
+            "record" => ReplCommand::Record,
+            "stop" => ReplCommand::Stop,
+            "help" | "?" => ReplCommand::Help,
+            "quit" | "exit" => ReplCommand::Quit,
+            _ => return Err(ReplError::UnknownCommand(name.to_string())),
+        };
+
+        Ok(command)
+    }
+}
+
+const HELP: &str = "\
+apply ALG      apply an algorithm to the state
+undo           go back to the state before the last change, as many times as needed
+redo           make the last undone change again
+solve          print an optimal solution of the state
+render FILE    save an image of the state
+set STATE      replace the state
+reset [SIZE]   go back to the solved state, optionally of another size
+scramble       replace the state with a random state of the same size
+md             print the Manhattan distance of the state
+print          print the state
+record         start recording the algorithms applied, with timestamps
+stop           stop recording and print the recording
+quit           stop";
+
+/// The algorithms applied since `record`, with the time since then.
+struct Recording {
+    start: Instant,
+    reconstruction: Reconstruction,
+}
+
+/// The state kept between the lines entered in the REPL.
+pub struct Repl {
+    state: Puzzle,
+    /// The states before each change, and the algorithm applied if the change was `apply`, most
+    /// recent last.
+    history: Vec<(Puzzle, Option<Algorithm>)>,
+    /// The states after each undone change, and the algorithm applied, most recently undone
+    /// last. This is cleared by any other change.
+    undone: Vec<(Puzzle, Option<Algorithm>)>,
+    solver: SolveFn,
+    options: RenderOptions,
+    recording: Option<Recording>,
+    /// The file that recordings are appended to when they are stopped.
+    record_path: Option<String>,
+}
+
+impl Repl {
+    pub fn new(
+        state: Puzzle,
+        options: RenderOptions,
+        record_path: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut repl = Self {
+            state,
+            history: Vec::new(),
+            undone: Vec::new(),
+            solver: solvers::labelled_solver(LabelType::RowGrids)?,
+            options,
+            recording: None,
+            record_path,
+        };
+        if repl.record_path.is_some() {
+            repl.record();
+        }
+
+        Ok(repl)
+    }
+
+    fn change(&mut self, state: Puzzle, alg: Option<Algorithm>) {
+        self.undone.clear();
+        self.go_to(state, alg);
+    }
+
+    /// Replaces the state, keeping the old one in the history.
+    fn go_to(&mut self, state: Puzzle, alg: Option<Algorithm>) {
+        if let Some(alg) = &alg {
+            self.push_record(alg.clone());
+        }
+
+        let before = std::mem::replace(&mut self.state, state);
+        self.history.push((before, alg));
+        println!("{}", self.state);
+    }
+
+    fn push_record(&mut self, alg: Algorithm) {
+        if let Some(recording) = &mut self.recording {
+            let time = recording.start.elapsed().as_secs_f64();
+            recording.reconstruction.push(alg, time);
+        }
+    }
+
+    fn record(&mut self) {
+        self.recording = Some(Recording {
+            start: Instant::now(),
+            reconstruction: Reconstruction::default(),
+        });
+    }
+
+    /// Ends the recording, printing it and appending it to the `--record` file.
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        let recording = self.recording.take().ok_or("nothing is being recorded")?;
+        let reconstruction = recording.reconstruction;
+        if reconstruction.is_empty() {
+            return Err("no moves were recorded".into());
+        }
+
+        println!("{reconstruction}");
+        if let Some(path) = &self.record_path {
+            reconstruction.append_to(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs one command, returning `false` if the REPL should stop.
+    fn run(&mut self, command: ReplCommand) -> Result<bool, Box<dyn Error>> {
+        match command {
+            ReplCommand::Apply(alg) => {
+                let mut state = self.state.clone();
+                if !state.try_apply_alg(&alg) {
+                    return Err("the algorithm can not be applied to the state".into());
+                }
+                self.change(state, Some(alg));
+            }
+            ReplCommand::Undo => {
+                let (state, alg) = self.history.pop().ok_or("there is nothing to undo")?;
+                // An applied algorithm is undone as a whole, and recorded as its inverse
+                if let Some(alg) = &alg {
+                    self.push_record(alg.inverse());
+                }
+                let after = std::mem::replace(&mut self.state, state);
+                self.undone.push((after, alg));
+                println!("{}", self.state);
+            }
+            ReplCommand::Redo => {
+                let (state, alg) = self.undone.pop().ok_or("there is nothing to redo")?;
+                self.go_to(state, alg);
+            }
+            ReplCommand::Solve => {
+                if !goal::is_solvable(&self.state) {
+                    return Err("the state is unsolvable".into());
+                }
+                println!("{}", (self.solver)(&self.state)?);
+            }
+            ReplCommand::Render(path) => {
+                let options = self.options.fitted(self.state.size());
+                let svg = render::document(&self.state, &options)?;
+                render::save(&path, &svg, &options)?;
+            }
+            ReplCommand::Set(state) => self.change(state, None),
+            ReplCommand::Reset(size) => {
+                self.change(goal::solved(size.unwrap_or(self.state.size())), None);
+            }
+            ReplCommand::Scramble => {
+                let mut state = Puzzle::new(self.state.size());
+                RandomState.scramble(&mut state);
+                self.change(goal::from_standard(&state), None);
+            }
+            ReplCommand::Md => println!("{}", heuristic::manhattan_distance(&self.state)),
+            ReplCommand::Print => {
+                println!("{}", self.state);
+                println!("{}", render::terminal(&self.state, &self.options)?);
+            }
+            ReplCommand::Record => self.record(),
+            ReplCommand::Stop => self.stop()?,
+            ReplCommand::Help => println!("{HELP}"),
+            ReplCommand::Quit => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Reads commands from stdin, one per line, and runs them on a state that is kept between them,
+/// until `quit` or the end of the input. An error in one command doesn't stop the REPL.
+///
+/// If `record` is given, recording starts straight away, and each recording is appended to the
+/// file when it is stopped, including by the REPL ending.
+pub fn repl(
+    state: Puzzle,
+    options: RenderOptions,
+    record: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut repl = Repl::new(state, options, record)?;
+    let interactive = io::stdin().is_terminal();
+    let prompt = || -> io::Result<()> {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        Ok(())
+    };
+
+    prompt()?;
+    for line in io::stdin().lock().lines() {
+        if util::interrupted() {
+            break;
+        }
+
+        let line = line?;
+        if !line.trim().is_empty() {
+            let result = line
+                .parse::<ReplCommand>()
+                .map_err(Box::<dyn Error>::from)
+                .and_then(|command| repl.run(command));
+
+            match result {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    util::set_status(util::Status::Invalid);
+                    eprintln!("error: {e}");
+                }
+            }
+        }
+
+        prompt()?;
+    }
+
+    let recorded = repl
+        .recording
+        .as_ref()
+        .is_some_and(|r| !r.reconstruction.is_empty());
+    if recorded {
+        repl.stop()?;
+    }
+
+    Ok(())
+}
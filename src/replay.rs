@@ -0,0 +1,140 @@
+use std::{error::Error, time::Instant};
+
+use slidy::{
+    algorithm::{algorithm::Algorithm, direction::Direction},
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+
+use crate::{
+    enums::Convention,
+    grid, notation,
+    render::{self, RenderOptions},
+    terminal::{Key, Screen},
+    util,
+};
+
+/// The speed used when autoplay is started with space and `--tps` wasn't given.
+const DEFAULT_TPS: f64 = 4.0;
+
+/// Every state of a solution, one per single tile move, with the piece moved to reach it.
+struct Steps {
+    states: Vec<Puzzle>,
+    directions: Vec<Direction>,
+    moved: Vec<u64>,
+}
+
+impl Steps {
+    fn new(state: &Puzzle, alg: &Algorithm) -> Result<Self, Box<dyn Error>> {
+        let directions = grid::directions(alg);
+        let mut states = vec![state.clone()];
+        let mut moved = Vec::with_capacity(directions.len());
+
+        let mut state = state.clone();
+        for &dir in &directions {
+            let (x, y) = state.gap_position_xy();
+            let (width, _) = state.size().into();
+            if !state.try_move_dir(dir) {
+                return Err("the algorithm can not be applied to the state".into());
+            }
+            // The moved piece is now where the gap was
+            moved.push(grid::pieces(&state)[(x + y * width) as usize]);
+            states.push(state.clone());
+        }
+
+        Ok(Self {
+            states,
+            directions,
+            moved,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.directions.len()
+    }
+
+    fn view(&self, step: usize, options: &RenderOptions) -> Result<String, Box<dyn Error>> {
+        let highlight = step.checked_sub(1).map(|i| self.moved[i]);
+        let puzzle = render::terminal_highlighted(&self.states[step], options, highlight)?;
+
+        let moves: String = self
+            .directions
+            .iter()
+            .map(|&d| grid::direction_char(d))
+            .collect();
+        let moves = match notation::convention() {
+            Convention::Tile => moves,
+            Convention::Blank => notation::flip(&moves),
+        };
+        let (done, rest) = moves.split_at(step);
+
+        Ok(format!(
+            "{puzzle}\n\nMove {step}/{}\n{done}\x1b[1m|\x1b[0m{rest}\n\n\
+            left/right: step  space: play/pause  home/end: jump  q: quit\n",
+            self.len(),
+        ))
+    }
+}
+
+/// Steps through the single tile moves of `alg` applied to `state`, with the moved tile
+/// highlighted. If `tps` is given, the moves are played at that speed until paused.
+pub fn replay(
+    state: &Puzzle,
+    alg: &Algorithm,
+    tps: Option<f64>,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let steps = Steps::new(state, alg)?;
+    let mut screen = Screen::new()?;
+    let speed = tps.unwrap_or(DEFAULT_TPS);
+
+    let mut step = 0;
+    // The step and time autoplay was started at
+    let mut playing = tps.map(|_| (0, Instant::now()));
+
+    while !util::interrupted() {
+        if let Some((from, start)) = playing {
+            step = (from + (start.elapsed().as_secs_f64() * speed) as usize).min(steps.len());
+            if step == steps.len() {
+                playing = None;
+            }
+        }
+
+        screen.draw(&steps.view(step, options)?)?;
+
+        let Some(key) = screen.read_key()? else {
+            continue;
+        };
+
+        match key {
+            Key::Escape | Key::Char('q') => break,
+            Key::Char(' ') => {
+                playing = match playing {
+                    Some(_) => None,
+                    // Start again from the beginning if the end was reached
+                    None if step == steps.len() => Some((0, Instant::now())),
+                    None => Some((step, Instant::now())),
+                }
+            }
+            // Stepping by hand pauses autoplay
+            Key::Right | Key::Char('l') => {
+                step = (step + 1).min(steps.len());
+                playing = None;
+            }
+            Key::Left | Key::Char('h') => {
+                step = step.saturating_sub(1);
+                playing = None;
+            }
+            Key::Home | Key::Char('g') => {
+                step = 0;
+                playing = None;
+            }
+            Key::End | Key::Char('G') => {
+                step = steps.len();
+                playing = None;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
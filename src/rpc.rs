@@ -0,0 +1,198 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    io::{self, BufRead, Write},
+};
+
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+
+use crate::{
+    enums::LabelType,
+    goal,
+    heuristic::{HeuristicType, Heuristics},
+    notation,
+    solvers::{labelled_solver, SolveFn},
+    util,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The method was called correctly, but failed, e.g. because a state is unsolvable.
+const FAILED: i64 = -32000;
+
+/// An error returned to the client.
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// State kept between requests, so that tables and solvers are only built once.
+#[derive(Default)]
+struct Server {
+    heuristics: Heuristics,
+    solvers: HashMap<LabelType, SolveFn>,
+}
+
+fn string_param<'a>(params: &'a Map<String, Value>, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing string parameter {name}")))
+}
+
+fn state_param(params: &Map<String, Value>) -> Result<Puzzle, RpcError> {
+    notation::parse_state(string_param(params, "state")?)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("state: {e}")))
+}
+
+fn alg_param(params: &Map<String, Value>) -> Result<Algorithm, RpcError> {
+    notation::parse_algorithm(string_param(params, "alg")?)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("alg: {e}")))
+}
+
+impl Server {
+    fn call(&mut self, method: &str, params: &Map<String, Value>) -> Result<Value, RpcError> {
+        let result = match method {
+            "apply" => {
+                let (mut state, alg) = (state_param(params)?, alg_param(params)?);
+                if !state.try_apply_alg(&alg) {
+                    return Err(RpcError::new(
+                        FAILED,
+                        "the algorithm can not be applied to the state",
+                    ));
+                }
+                json!(state.to_string())
+            }
+            "heuristic" => {
+                let state = state_param(params)?;
+                let name = params.get("type").and_then(Value::as_str).unwrap_or("md");
+                let heuristic = HeuristicType::from_str(name, false)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, format!("type: {e}")))?;
+                if !goal::is_solvable(&state) {
+                    return Err(RpcError::new(FAILED, "the state is unsolvable"));
+                }
+                let bound = self.heuristics.bound(heuristic, &state).ok_or_else(|| {
+                    RpcError::new(FAILED, "the heuristic is not available for this size")
+                })?;
+                json!(bound)
+            }
+            "invert" => json!(alg_param(params)?.inverse().to_string()),
+            "length" => json!(alg_param(params)?.len_stm::<u64>()),
+            "simplify" => {
+                let mut alg = alg_param(params)?;
+                alg.simplify();
+                json!(alg.to_string())
+            }
+            "solvable" => json!(goal::is_solvable(&state_param(params)?)),
+            "solve" => {
+                let state = state_param(params)?;
+                if !goal::is_solvable(&state) {
+                    return Err(RpcError::new(FAILED, "the state is unsolvable"));
+                }
+                let name = params.get("label").and_then(Value::as_str);
+                let label = LabelType::from_str(name.unwrap_or("row-grids"), false)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, format!("label: {e}")))?;
+                let solver = match self.solvers.entry(label) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => e.insert(
+                        labelled_solver(label).map_err(|e| RpcError::new(INVALID_PARAMS, e))?,
+                    ),
+                };
+                let solution = solver(&state).map_err(|e| RpcError::new(FAILED, e))?;
+                json!(solution.to_string())
+            }
+            "verify" => {
+                let (mut state, alg) = (state_param(params)?, alg_param(params)?);
+                json!(state.try_apply_alg(&alg) && goal::is_solved(&state))
+            }
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("unknown method {method}"),
+                ))
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Handles one line of input, returning the response, or `None` for a notification.
+    fn handle(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, e))),
+        };
+
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let params = match request.get("params") {
+            None => Some(Map::new()),
+            Some(Value::Object(params)) => Some(params.clone()),
+            Some(_) => None,
+        };
+
+        let result = match (method, params) {
+            (None, _) => Err(RpcError::new(INVALID_REQUEST, "missing method")),
+            (_, None) => Err(RpcError::new(
+                INVALID_PARAMS,
+                "the parameters must be an object",
+            )),
+            (Some(method), Some(params)) => self.call(method, &params),
+        };
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e),
+        })
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+/// Reads JSON-RPC 2.0 requests from stdin, one per line, and writes a response to stdout for
+/// each of them except notifications, until stdin is closed.
+pub fn serve() -> Result<(), Box<dyn Error>> {
+    let mut server = Server::default();
+    let mut out = io::stdout().lock();
+
+    for line in io::stdin().lock().lines() {
+        if util::interrupted() {
+            break;
+        }
+
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = server.handle(&line) {
+            writeln!(out, "{response}")?;
+            out.flush()?;
+        }
+    }
+
+    Ok(())
+}
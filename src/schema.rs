@@ -0,0 +1,97 @@
+use crate::{
+    session, util::json_string, BLANK_TRAVEL_COLUMNS, COMPARE_COLUMNS, DB_COLUMNS,
+    GENERATE_COLUMNS, GRADE_COLUMNS, LENGTH_COLUMNS, MD_COLUMNS, OPT_DIFF_COLUMNS, SOLVE_COLUMNS,
+};
+
+/// Incremented whenever the structure of the JSON output changes.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The commands with `--format ndjson` output, and the columns of their records.
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("blank-travel", BLANK_TRAVEL_COLUMNS),
+    ("compare-solvers", COMPARE_COLUMNS),
+    ("db export", DB_COLUMNS),
+    ("generate", GENERATE_COLUMNS),
+    ("grade", GRADE_COLUMNS),
+    ("length", LENGTH_COLUMNS),
+    ("md", MD_COLUMNS),
+    ("opt-diff", OPT_DIFF_COLUMNS),
+    ("session export", session::COLUMNS),
+    ("solve", SOLVE_COLUMNS),
+];
+
+/// The JSON Schema type of a column of `command`. Numbers are written as JSON numbers, but the
+/// columns that are missing for unsolvable states hold a string instead.
+fn column_type(command: &str, column: &str) -> &'static str {
+    match (command, column) {
+        ("solve", "length") | ("md", "md") => "{\"type\":[\"integer\",\"string\"]}",
+        ("session export", "moves") => "{\"type\":[\"integer\",\"string\"]}",
+        ("compare-solvers", "length" | "nodes") => "{\"type\":[\"integer\",\"string\"]}",
+        ("blank-travel", "distance" | "turns" | "reversals" | "longest_run") => {
+            "{\"type\":\"integer\"}"
+        }
+        ("compare-solvers", "time_us") => "{\"type\":\"integer\"}",
+        (_, "length" | "optimal" | "diff" | "surplus" | "time") => "{\"type\":\"integer\"}",
+        _ => "{\"type\":\"string\"}",
+    }
+}
+
+fn object(title: &str, properties: &[(String, &str)], required: &[&str]) -> String {
+    let properties: Vec<_> = properties
+        .iter()
+        .map(|(name, ty)| format!("{}:{ty}", json_string(name)))
+        .collect();
+    let required: Vec<_> = required.iter().map(|r| json_string(r)).collect();
+
+    format!(
+        "{{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\",\"title\":{},\
+        \"type\":\"object\",\"properties\":{{{}}},\"required\":[{}]}}",
+        json_string(title),
+        properties.join(","),
+        required.join(",")
+    )
+}
+
+/// The schema of the records printed by `command` with `--format ndjson`, or of the error
+/// objects written with `--error-format json` if `command` is `error`.
+pub fn schema(command: &str) -> Option<String> {
+    if command == "error" {
+        return Some(object(
+            "error",
+            &[
+                ("file".to_string(), "{\"type\":\"string\"}"),
+                ("line".to_string(), "{\"type\":\"integer\"}"),
+                ("input".to_string(), "{\"type\":\"string\"}"),
+                ("error".to_string(), "{\"type\":\"string\"}"),
+            ],
+            &["error"],
+        ));
+    }
+
+    let (_, columns) = COMMANDS.iter().find(|(name, _)| *name == command)?;
+    let mut properties: Vec<_> = columns
+        .iter()
+        .map(|c| (c.to_string(), column_type(command, c)))
+        .collect();
+    properties.push(("time_ms".to_string(), "{\"type\":\"number\"}"));
+
+    let mut required = columns.to_vec();
+    required.push("time_ms");
+
+    Some(object(command, &properties, &required))
+}
+
+/// The schemas of every structured output, keyed by command name.
+pub fn all() -> String {
+    let schemas: Vec<_> = COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(std::iter::once("error"))
+        .map(|name| format!("{}:{}", json_string(name), schema(name).unwrap()))
+        .collect();
+
+    format!(
+        "{{\"version\":{SCHEMA_VERSION},\"schemas\":{{{}}}}}",
+        schemas.join(",")
+    )
+}
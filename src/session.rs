@@ -0,0 +1,640 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    fs::OpenOptions,
+    io::{BufRead, Write},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use serde_json::Value;
+use slidy::{algorithm::algorithm::Algorithm, puzzle::puzzle::Puzzle};
+use thiserror::Error;
+
+use crate::{enums::TableFormat, notation, util};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum SessionError {
+    #[error("FieldCount: expected a time, a scramble, and optionally a solution and a phase, found {0} fields")]
+    FieldCount(usize),
+
+    #[error(
+        "InvalidTime: \"{0}\" is not a time in milliseconds, optionally followed by +2 or inside \
+        DNF(...)"
+    )]
+    Time(String),
+
+    #[error(
+        "InvalidScore: \"{0}\" is not a time in seconds or milliseconds, optionally followed by \
+        +2, or DNF"
+    )]
+    Score(String),
+
+    #[error("InvalidScramble: {0}")]
+    Scramble(String),
+
+    #[error("InvalidSolution: {0}")]
+    Solution(String),
+
+    #[error("InvalidPenalty: \"{0}\" is not a penalty, expected +2, DNF or nothing")]
+    Penalty(String),
+
+    #[error("MissingColumn: the records have no {0} column")]
+    MissingColumn(&'static str),
+
+    #[error("InvalidRecord: {0}")]
+    Record(String),
+}
+
+/// A penalty given to a solve, as in WCA competitions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Penalty {
+    #[default]
+    None,
+    /// Two seconds are added to the time.
+    PlusTwo,
+    /// The solve doesn't count.
+    Dnf,
+}
+
+impl Penalty {
+    /// The penalty for taking `inspection` milliseconds to start a solve when `limit` is
+    /// allowed: +2 for starting in the two seconds after the limit, and DNF after that.
+    pub fn for_inspection(inspection: u64, limit: u64) -> Self {
+        if inspection <= limit {
+            Penalty::None
+        } else if inspection <= limit + 2000 {
+            Penalty::PlusTwo
+        } else {
+            Penalty::Dnf
+        }
+    }
+}
+
+/// The result of a solve after its penalty. A DNF is worse than any time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Score {
+    /// A time in milliseconds.
+    Time(u64),
+    Dnf,
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Time(time) => write!(f, "{}", format_time(*time)),
+            Score::Dnf => write!(f, "DNF"),
+        }
+    }
+}
+
+/// Reads a time with a decimal point, e.g. `12.345` or `1:02.345`, as seconds, and a whole
+/// number as milliseconds, as in session files. A `+` or `+2` after the time adds two seconds,
+/// and `DNF` or `DNF(...)` is a DNF.
+impl FromStr for Score {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || SessionError::Score(s.to_string());
+        if s.eq_ignore_ascii_case("dnf") || (s.starts_with("DNF(") && s.ends_with(')')) {
+            return Ok(Score::Dnf);
+        }
+
+        let (time, penalty) = match s.strip_suffix("+2").or_else(|| s.strip_suffix('+')) {
+            Some(time) => (time, 2000),
+            None => (s, 0),
+        };
+
+        let ms = if time.contains(['.', ':']) {
+            let (minutes, seconds) = time.rsplit_once(':').unwrap_or(("0", time));
+            let minutes: u64 = minutes.parse().map_err(|_| invalid())?;
+            let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(invalid());
+            }
+            minutes * 60_000 + (seconds * 1000.0).round() as u64
+        } else {
+            time.parse().map_err(|_| invalid())?
+        };
+
+        Ok(Score::Time(ms + penalty))
+    }
+}
+
+/// One timed solve, stored in a session file as a line with the time in milliseconds, the
+/// scramble, and optionally the solution and the phase, separated by tabs. A time with a +2
+/// penalty is written as e.g. `12345+2`, and a DNF as `DNF(12345)`.
+#[derive(Clone, Debug)]
+pub struct Solve {
+    /// The time in milliseconds, without the penalty.
+    pub time: u64,
+    pub penalty: Penalty,
+    pub scramble: Puzzle,
+    /// The moves of the solve, if they are known.
+    pub solution: Option<Algorithm>,
+    /// The phase that was practised, for solves done with `practice`.
+    pub phase: Option<String>,
+}
+
+impl FromStr for Solve {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split('\t').map(str::trim).collect();
+        let (time, scramble, solution, phase) = match fields[..] {
+            [time, scramble] => (time, scramble, "", ""),
+            [time, scramble, solution] => (time, scramble, solution, ""),
+            [time, scramble, solution, phase] => (time, scramble, solution, phase),
+            _ => return Err(SessionError::FieldCount(fields.len())),
+        };
+
+        let invalid_time = || SessionError::Time(time.to_string());
+        let (ms, penalty) = if let Some(ms) = time.strip_suffix("+2") {
+            (ms, Penalty::PlusTwo)
+        } else if let Some(ms) = time.strip_prefix("DNF(").and_then(|t| t.strip_suffix(')')) {
+            (ms, Penalty::Dnf)
+        } else {
+            (time, Penalty::None)
+        };
+
+        Ok(Self {
+            time: ms.parse().map_err(|_| invalid_time())?,
+            penalty,
+            scramble: notation::parse_state(scramble).map_err(SessionError::Scramble)?,
+            solution: (!solution.is_empty())
+                .then(|| Algorithm::from_str(solution))
+                .transpose()
+                .map_err(|e| SessionError::Solution(e.to_string()))?,
+            phase: (!phase.is_empty()).then(|| phase.to_string()),
+        })
+    }
+}
+
+impl Display for Solve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.penalty {
+            Penalty::None => write!(f, "{}", self.time)?,
+            Penalty::PlusTwo => write!(f, "{}+2", self.time)?,
+            Penalty::Dnf => write!(f, "DNF({})", self.time)?,
+        }
+        write!(f, "\t{}", self.scramble)?;
+        if self.solution.is_some() || self.phase.is_some() {
+            let solution = self.solution.as_ref().map(Algorithm::to_string);
+            write!(f, "\t{}", solution.unwrap_or_default())?;
+        }
+        if let Some(phase) = &self.phase {
+            write!(f, "\t{phase}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The columns of the records written by `session export`.
+pub const COLUMNS: &[&str] = &[
+    "time", "penalty", "scramble", "solution", "moves", "tps", "phase",
+];
+
+impl Solve {
+    /// The number of moves of the solution, if it is known.
+    pub fn moves(&self) -> Option<u64> {
+        self.solution.as_ref().map(|a| a.len_stm::<u64>())
+    }
+
+    /// The moves per second of the solution, if it is known.
+    pub fn tps(&self) -> Option<f64> {
+        let moves = self.moves()?;
+        (self.time > 0).then(|| moves as f64 * 1000.0 / self.time as f64)
+    }
+
+    /// The fields of the solve in the order of [`COLUMNS`]. Unknown fields are empty.
+    pub fn record(&self) -> [String; 7] {
+        let penalty = match self.penalty {
+            Penalty::None => "",
+            Penalty::PlusTwo => "+2",
+            Penalty::Dnf => "DNF",
+        };
+
+        [
+            self.time.to_string(),
+            penalty.to_string(),
+            self.scramble.to_string(),
+            self.solution
+                .as_ref()
+                .map(Algorithm::to_string)
+                .unwrap_or_default(),
+            self.moves().map(|m| m.to_string()).unwrap_or_default(),
+            self.tps().map(|t| format!("{t:.3}")).unwrap_or_default(),
+            self.phase.clone().unwrap_or_default(),
+        ]
+    }
+
+    /// Reads a solve from a record with named fields, as written by `session export`. The
+    /// `moves` and `tps` fields are ignored, since they follow from the solution and time.
+    pub fn from_record(record: &HashMap<String, String>) -> Result<Self, SessionError> {
+        let field = |name: &str| record.get(name).map(String::as_str).unwrap_or_default();
+
+        let time = record
+            .get("time")
+            .ok_or(SessionError::MissingColumn("time"))?;
+        let scramble = record
+            .get("scramble")
+            .ok_or(SessionError::MissingColumn("scramble"))?;
+        let penalty = match field("penalty") {
+            "" => Penalty::None,
+            "+2" => Penalty::PlusTwo,
+            p if p.eq_ignore_ascii_case("dnf") => Penalty::Dnf,
+            p => return Err(SessionError::Penalty(p.to_string())),
+        };
+        let (solution, phase) = (field("solution"), field("phase"));
+
+        Ok(Self {
+            time: time
+                .parse()
+                .map_err(|_| SessionError::Time(time.to_string()))?,
+            penalty,
+            scramble: notation::parse_state(scramble).map_err(SessionError::Scramble)?,
+            solution: (!solution.is_empty())
+                .then(|| Algorithm::from_str(solution))
+                .transpose()
+                .map_err(|e| SessionError::Solution(e.to_string()))?,
+            phase: (!phase.is_empty()).then(|| phase.to_string()),
+        })
+    }
+
+    pub fn score(&self) -> Score {
+        match self.penalty {
+            Penalty::None => Score::Time(self.time),
+            Penalty::PlusTwo => Score::Time(self.time + 2000),
+            Penalty::Dnf => Score::Dnf,
+        }
+    }
+
+    /// The score of the solve, with a `+` after times that include a +2 penalty.
+    pub fn format_score(&self) -> String {
+        match self.penalty {
+            Penalty::PlusTwo => format!("{}+", self.score()),
+            _ => self.score().to_string(),
+        }
+    }
+}
+
+/// Formats a time in milliseconds as seconds, e.g. `12.345`.
+pub fn format_time(time: u64) -> String {
+    format!("{}.{:03}", time / 1000, time % 1000)
+}
+
+/// The number of best and worst scores left out of an average of `n`: 5% of them at each end,
+/// rounded up, which is one for an ao5 or ao12 and five for an ao100.
+fn trimmed(n: usize) -> usize {
+    n.div_ceil(20)
+}
+
+/// The mean of the last `n` scores, excluding the best and worst [`trimmed`] of them, or `None`
+/// if there are fewer than `n` scores. The average is a DNF if more of the scores are DNFs than
+/// are left out at the worst end.
+pub fn average_of(scores: &[Score], n: usize) -> Option<Score> {
+    if n < 3 || scores.len() < n {
+        return None;
+    }
+
+    let mut last = scores[scores.len() - n..].to_vec();
+    last.sort_unstable();
+    let counted = &last[trimmed(n)..n - trimmed(n)];
+    let times = counted
+        .iter()
+        .map(|&s| match s {
+            Score::Time(time) => Some(time),
+            Score::Dnf => None,
+        })
+        .collect::<Option<Vec<u64>>>();
+
+    Some(times.map_or(Score::Dnf, |t| {
+        Score::Time(t.iter().sum::<u64>() / t.len() as u64)
+    }))
+}
+
+/// The best average of `n` consecutive scores, or `None` if there are fewer than `n` scores.
+pub fn best_average_of(scores: &[Score], n: usize) -> Option<Score> {
+    (n..=scores.len())
+        .filter_map(|end| average_of(&scores[..end], n))
+        .min()
+}
+
+/// A table of the current and best average of each size in `windows`, after the number of
+/// scores and DNFs.
+pub fn averages(scores: &[Score], windows: &[usize]) -> String {
+    let dnfs = scores.iter().filter(|&&s| s == Score::Dnf).count();
+    let score = |s: Option<Score>| s.map_or("-".to_string(), |s| s.to_string());
+
+    let rows: Vec<[String; 3]> = std::iter::once([String::new(), "current".into(), "best".into()])
+        .chain(windows.iter().map(|&n| {
+            [
+                format!("ao{n}"),
+                score(average_of(scores, n)),
+                score(best_average_of(scores, n)),
+            ]
+        }))
+        .collect();
+    let width = |c: usize| rows.iter().map(|r| r[c].len()).max().unwrap_or(0);
+    let (w0, w1, w2) = (width(0), width(1), width(2));
+
+    let mut out = format!("solves: {}  DNFs: {dnfs}\n", scores.len());
+    for [name, current, best] in &rows {
+        out += &format!("\n{name:<w0$}  {current:>w1$}  {best:>w2$}");
+    }
+
+    out
+}
+
+/// Statistics of the scores in a session. The best time and the mean leave out DNFs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub count: usize,
+    pub dnfs: usize,
+    pub best: Option<u64>,
+    pub mean: Option<u64>,
+    pub ao5: Option<Score>,
+    pub ao12: Option<Score>,
+}
+
+impl Stats {
+    pub fn new(scores: &[Score]) -> Self {
+        let times: Vec<u64> = scores
+            .iter()
+            .filter_map(|&s| match s {
+                Score::Time(time) => Some(time),
+                Score::Dnf => None,
+            })
+            .collect();
+
+        Self {
+            count: scores.len(),
+            dnfs: scores.len() - times.len(),
+            best: times.iter().min().copied(),
+            mean: (!times.is_empty()).then(|| times.iter().sum::<u64>() / times.len() as u64),
+            ao5: average_of(scores, 5),
+            ao12: average_of(scores, 12),
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = |t: Option<u64>| t.map_or("-".to_string(), format_time);
+        let score = |s: Option<Score>| s.map_or("-".to_string(), |s| s.to_string());
+        write!(f, "solves: {}  ", self.count)?;
+        if self.dnfs > 0 {
+            write!(f, "DNFs: {}  ", self.dnfs)?;
+        }
+        write!(
+            f,
+            "best: {}  mean: {}  ao5: {}  ao12: {}",
+            time(self.best),
+            time(self.mean),
+            score(self.ao5),
+            score(self.ao12),
+        )
+    }
+}
+
+/// The solves done so far, optionally kept in a file so that a session can be continued later.
+pub struct Session {
+    path: Option<String>,
+    pub solves: Vec<Solve>,
+}
+
+impl Session {
+    /// Opens a session, reading the solves already in the file if it exists.
+    pub fn open(path: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let solves = match path {
+            Some(path) if Path::new(path).exists() => util::read_file(path)?,
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            path: path.map(str::to_string),
+            solves,
+        })
+    }
+
+    /// Adds a solve, appending it to the session file straight away so that it isn't lost if the
+    /// program stops.
+    pub fn add(&mut self, solve: Solve) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("{path}: {e}"))?;
+            writeln!(file, "{solve}")?;
+        }
+
+        self.solves.push(solve);
+
+        Ok(())
+    }
+
+    /// Changes the penalty of the last solve, rewriting the session file. Does nothing if there
+    /// are no solves.
+    pub fn set_penalty(&mut self, penalty: Penalty) -> Result<(), Box<dyn Error>> {
+        let Some(last) = self.solves.last_mut() else {
+            return Ok(());
+        };
+        last.penalty = penalty;
+
+        if let Some(path) = &self.path {
+            let contents: String = self.solves.iter().map(|s| format!("{s}\n")).collect();
+            fs::write(path, contents).map_err(|e| format!("{path}: {e}"))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Stats {
+        let scores: Vec<Score> = self.solves.iter().map(Solve::score).collect();
+        Stats::new(&scores)
+    }
+
+    /// Prints every solve as a record with the columns in [`COLUMNS`].
+    pub fn export(&self, format: TableFormat) {
+        if let Some(header) = format.header(COLUMNS) {
+            println!("{header}");
+        }
+        for solve in &self.solves {
+            println!("{}", format.row(COLUMNS, &solve.record(), Duration::ZERO));
+        }
+    }
+
+    /// Reads records written by [`Session::export`] and adds them to the session.
+    pub fn import(
+        &mut self,
+        input: impl BufRead,
+        format: TableFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut header: Option<Vec<String>> = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let n = i + 1;
+
+            let record = match format {
+                TableFormat::Ndjson => json_record(&line),
+                TableFormat::Csv | TableFormat::Tsv => {
+                    let fields = split_fields(&line, format);
+                    let Some(columns) = &header else {
+                        header = Some(fields);
+                        continue;
+                    };
+                    Ok(columns.iter().cloned().zip(fields).collect())
+                }
+            };
+
+            let solve = record
+                .and_then(|r| Solve::from_record(&r))
+                .map_err(|e| format!("line {n}: {e}"))?;
+            self.add(solve)?;
+        }
+
+        Ok(())
+    }
+
+    /// The phases of the solves in the order they first appear, with `None` for full solves.
+    pub fn phases(&self) -> Vec<Option<&str>> {
+        let mut phases = Vec::new();
+        for solve in &self.solves {
+            let phase = solve.phase.as_deref();
+            if !phases.contains(&phase) {
+                phases.push(phase);
+            }
+        }
+
+        phases
+    }
+
+    /// A summary of the solves of each phase: their statistics, best averages, and mean moves
+    /// and TPS. With `every`, the statistics of each block of that many solves are included, to
+    /// show progress over time.
+    pub fn summary(&self, every: Option<usize>) -> String {
+        let mut lines = Vec::new();
+
+        for phase in self.phases() {
+            let solves: Vec<&Solve> = self
+                .solves
+                .iter()
+                .filter(|s| s.phase.as_deref() == phase)
+                .collect();
+            let scores: Vec<Score> = solves.iter().map(|s| s.score()).collect();
+            let score = |s: Option<Score>| s.map_or("-".to_string(), |s| s.to_string());
+
+            lines.push(phase.unwrap_or("full solves").to_string());
+            lines.push(format!("  {}", Stats::new(&scores)));
+            lines.push(format!(
+                "  best ao5: {}  best ao12: {}  best ao100: {}",
+                score(best_average_of(&scores, 5)),
+                score(best_average_of(&scores, 12)),
+                score(best_average_of(&scores, 100)),
+            ));
+
+            let known: Vec<(u64, u64)> = solves
+                .iter()
+                .filter_map(|s| s.moves().map(|m| (m, s.time)))
+                .collect();
+            if !known.is_empty() {
+                let moves: u64 = known.iter().map(|&(m, _)| m).sum();
+                let time: u64 = known.iter().map(|&(_, t)| t).sum();
+                let tps = if time > 0 {
+                    moves as f64 * 1000.0 / time as f64
+                } else {
+                    0.0
+                };
+                lines.push(format!(
+                    "  mean moves: {:.1}  mean TPS: {tps:.3}",
+                    moves as f64 / known.len() as f64
+                ));
+            }
+
+            if let Some(every) = every.filter(|&n| n > 0) {
+                for (i, block) in scores.chunks(every).enumerate() {
+                    let start = i * every + 1;
+                    let end = start + block.len() - 1;
+                    lines.push(format!("  {start}-{end}: {}", Stats::new(block)));
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// The statistics of the solves of one phase, and their mean number of moves.
+    pub fn phase_stats(&self, phase: &str) -> (Stats, Option<f64>) {
+        let solves: Vec<&Solve> = self
+            .solves
+            .iter()
+            .filter(|s| s.phase.as_deref() == Some(phase))
+            .collect();
+        let scores: Vec<Score> = solves.iter().map(|s| s.score()).collect();
+
+        let moves: Vec<u64> = solves
+            .iter()
+            .filter_map(|s| s.solution.as_ref().map(|a| a.len_stm::<u64>()))
+            .collect();
+        let mean_moves =
+            (!moves.is_empty()).then(|| moves.iter().sum::<u64>() as f64 / moves.len() as f64);
+
+        (Stats::new(&scores), mean_moves)
+    }
+}
+
+/// Splits a CSV or TSV line into fields. Quoted CSV fields may contain commas and doubled
+/// quotes.
+fn split_fields(line: &str, format: TableFormat) -> Vec<String> {
+    if format == TableFormat::Tsv {
+        return line.split('\t').map(str::to_string).collect();
+    }
+
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(String::new()),
+            (c, _) => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields
+}
+
+/// Reads one NDJSON record, with numbers converted to strings like the other fields.
+fn json_record(line: &str) -> Result<HashMap<String, String>, SessionError> {
+    let Value::Object(object) =
+        serde_json::from_str(line).map_err(|e| SessionError::Record(e.to_string()))?
+    else {
+        return Err(SessionError::Record("expected a JSON object".to_string()));
+    };
+
+    Ok(object
+        .into_iter()
+        .map(|(k, v)| {
+            let v = match v {
+                Value::String(s) => s,
+                Value::Null => String::new(),
+                v => v.to_string(),
+            };
+            (k, v)
+        })
+        .collect())
+}
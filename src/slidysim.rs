@@ -0,0 +1,109 @@
+use std::{fmt::Display, str::FromStr};
+
+use slidy::{algorithm::algorithm::Algorithm, puzzle::puzzle::Puzzle};
+use thiserror::Error;
+
+use crate::notation;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum SlidysimError {
+    #[error("FieldCount: expected a time, a scramble and a solution, found {0} fields")]
+    FieldCount(usize),
+
+    #[error("InvalidTime: \"{0}\" is not a time")]
+    Time(String),
+
+    #[error("InvalidScramble: {0}")]
+    Scramble(String),
+
+    #[error("InvalidSolution: {0}")]
+    Solution(String),
+}
+
+/// One solve from a slidysim export. Each line has the time in seconds, the scramble and the
+/// solution, separated by tabs or commas:
+///
+/// ```text
+/// 12.345	1 2 3 4/5 6 7 8/9 10 11 12/13 14 0 15	R
+/// ```
+#[derive(Clone, Debug)]
+pub struct Solve {
+    /// The time in milliseconds.
+    pub time: u64,
+    pub scramble: Puzzle,
+    pub solution: Algorithm,
+}
+
+impl FromStr for Solve {
+    type Err = SlidysimError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let separator = if s.contains('\t') { '\t' } else { ',' };
+        let fields: Vec<&str> = s.trim().split(separator).map(str::trim).collect();
+        let [time, scramble, solution] = fields[..] else {
+            return Err(SlidysimError::FieldCount(fields.len()));
+        };
+
+        Ok(Self {
+            time: parse_time(time).ok_or_else(|| SlidysimError::Time(time.to_string()))?,
+            scramble: notation::parse_state(scramble).map_err(SlidysimError::Scramble)?,
+            solution: notation::parse_algorithm(solution).map_err(SlidysimError::Solution)?,
+        })
+    }
+}
+
+impl Display for Solve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{:03}\t{}\t{}",
+            self.time / 1000,
+            self.time % 1000,
+            self.scramble,
+            self.solution
+        )
+    }
+}
+
+impl Solve {
+    /// Parses a scramble, a solution and optionally a time in milliseconds, separated by tabs.
+    pub fn from_tsv(s: &str) -> Result<Self, SlidysimError> {
+        let fields: Vec<&str> = s.trim_end_matches(['\r', '\n']).split('\t').collect();
+        let (scramble, solution, time) = match fields[..] {
+            [scramble, solution] => (scramble, solution, None),
+            [scramble, solution, time] => (scramble, solution, Some(time)),
+            _ => return Err(SlidysimError::FieldCount(fields.len())),
+        };
+
+        let time = time
+            .map(|t| {
+                t.trim()
+                    .parse()
+                    .map_err(|_| SlidysimError::Time(t.to_string()))
+            })
+            .transpose()?
+            .unwrap_or(0);
+
+        Ok(Self {
+            time,
+            scramble: notation::parse_state(scramble).map_err(SlidysimError::Scramble)?,
+            solution: notation::parse_algorithm(solution).map_err(SlidysimError::Solution)?,
+        })
+    }
+
+    /// The scramble, the solution and the time in milliseconds, separated by tabs.
+    pub fn to_tsv(&self) -> String {
+        format!("{}\t{}\t{}", self.scramble, self.solution, self.time)
+    }
+}
+
+/// Parses a time in seconds, with up to millisecond precision, into milliseconds.
+fn parse_time(s: &str) -> Option<u64> {
+    let (secs, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 3 || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let millis = format!("{frac:0<3}").parse::<u64>().ok()?;
+    Some(secs.parse::<u64>().ok()? * 1000 + millis)
+}
@@ -0,0 +1,83 @@
+use std::{collections::HashMap, error::Error};
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{
+        label::label::{
+            Checkerboard, Diagonals, Fringe, RowGrids, Rows, SplitFringe, SplitSquareFringe,
+            SquareFringe,
+        },
+        puzzle::Puzzle,
+        sliding_puzzle::SlidingPuzzle,
+    },
+    solver::{heuristic::manhattan::ManhattanDistance, solver::Solver},
+};
+
+use crate::{
+    enums::{BlankHome, LabelType},
+    goal, memory,
+};
+
+/// Finds an optimal solution of a state.
+pub type SolveFn = Box<dyn FnMut(&Puzzle) -> Result<Algorithm, Box<dyn Error>>>;
+
+/// A solver using `label` and the Manhattan distance heuristic. The same solver should be used
+/// for every state in a batch, so that its internal structures are only built once.
+pub fn labelled_solver(label: LabelType) -> Result<SolveFn, Box<dyn Error>> {
+    macro_rules! solver {
+        ($label:expr) => {{
+            let mut solver = Solver::new(&ManhattanDistance(&$label), &$label);
+            Box::new(move |state: &Puzzle| Ok(solver.solve(state)?))
+        }};
+    }
+
+    let solver: SolveFn = match label {
+        LabelType::RowGrids => solver!(RowGrids),
+        LabelType::Rows => solver!(Rows),
+        LabelType::Fringe => solver!(Fringe),
+        LabelType::SquareFringe => solver!(SquareFringe),
+        LabelType::SplitFringe => solver!(SplitFringe),
+        LabelType::SplitSquareFringe => solver!(SplitSquareFringe),
+        LabelType::Diagonals => solver!(Diagonals),
+        LabelType::Checkerboard => solver!(Checkerboard),
+        LabelType::Grids => return Err("solving with the grids label is not supported".into()),
+    };
+
+    Ok(with_goal(solver))
+}
+
+/// Wraps a solver of the usual solved state so that it solves to the goal set by
+/// `--blank-home`.
+fn with_goal(mut solver: SolveFn) -> SolveFn {
+    if goal::home() == BlankHome::BottomRight {
+        return solver;
+    }
+
+    Box::new(move |state: &Puzzle| Ok(goal::reflect_alg(&solver(&goal::to_standard(state))?)))
+}
+
+/// Roughly the memory used by one entry of [`cached`], for a state with `area` pieces.
+fn cache_entry_size(area: u64) -> u64 {
+    // The state as a string, a typical solution, and the overhead of the map
+    3 * area + 128
+}
+
+/// Wraps a solver so that states it has already solved are answered from memory, using up to
+/// `capacity` entries, or fewer if they wouldn't fit within the memory limit. Unsolvable states
+/// are not remembered.
+pub fn cached(mut solver: SolveFn, capacity: usize) -> SolveFn {
+    let mut cache: HashMap<String, Algorithm> = HashMap::new();
+    Box::new(move |state: &Puzzle| {
+        let key = state.to_string();
+        if let Some(solution) = cache.get(&key) {
+            return Ok(solution.clone());
+        }
+
+        let solution = solver(state)?;
+        let capacity = capacity.min(memory::max_entries(cache_entry_size(state.area())));
+        if cache.len() < capacity {
+            cache.insert(key, solution.clone());
+        }
+        Ok(solution)
+    })
+}
@@ -0,0 +1,397 @@
+use std::error::Error;
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+
+use crate::{
+    enums::{Convention, Metric},
+    goal, grid, heuristic, notation,
+    progress::Progress,
+    solvers::SolveFn,
+    util,
+};
+
+/// The widest bar drawn in a histogram.
+const BAR_WIDTH: u64 = 50;
+
+/// The most rows in a histogram. Values are grouped into ranges if there are more distinct
+/// values than this.
+const MAX_ROWS: u64 = 30;
+
+/// The percentiles printed in a summary.
+const PERCENTILES: [u64; 5] = [10, 25, 75, 90, 99];
+
+/// The number of standard errors either side of the mean in a 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// A set of values, kept sorted so that the median and percentiles can be read off.
+pub struct Distribution {
+    values: Vec<u64>,
+}
+
+impl Distribution {
+    pub fn new(mut values: Vec<u64>) -> Self {
+        values.sort_unstable();
+        Self { values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.values.first().copied()
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.values.last().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty())
+            .then(|| self.values.iter().sum::<u64>() as f64 / self.values.len() as f64)
+    }
+
+    /// The sample standard deviation, which needs at least two values.
+    pub fn std_dev(&self) -> Option<f64> {
+        let n = self.values.len();
+        let mean = self.mean().filter(|_| n >= 2)?;
+        let squares: f64 = self.values.iter().map(|&v| (v as f64 - mean).powi(2)).sum();
+        Some((squares / (n - 1) as f64).sqrt())
+    }
+
+    /// The middle value, or the mean of the two middle values if there is an even number.
+    pub fn median(&self) -> Option<f64> {
+        let n = self.values.len();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.values[n / 2] as f64),
+            _ => Some((self.values[n / 2 - 1] + self.values[n / 2]) as f64 / 2.0),
+        }
+    }
+
+    /// The smallest value that at least `p` percent of the values are less than or equal to.
+    pub fn percentile(&self, p: u64) -> Option<u64> {
+        let n = self.values.len() as u64;
+        let rank = (p * n).div_ceil(100).max(1);
+        self.values.get(rank as usize - 1).copied()
+    }
+
+    /// One row per value, or per range of values if there are many, with a bar proportional to
+    /// the number of values in it.
+    pub fn histogram(&self) -> String {
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return String::new();
+        };
+
+        let bucket = (max - min + 1).div_ceil(MAX_ROWS);
+        let mut counts = vec![0u64; ((max - min) / bucket + 1) as usize];
+        for &v in &self.values {
+            counts[((v - min) / bucket) as usize] += 1;
+        }
+
+        let largest = counts.iter().copied().max().unwrap_or(1);
+        let labels: Vec<String> = (0..counts.len() as u64)
+            .map(|i| {
+                let start = min + i * bucket;
+                if bucket == 1 {
+                    start.to_string()
+                } else {
+                    format!("{start}-{}", start + bucket - 1)
+                }
+            })
+            .collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+
+        labels
+            .iter()
+            .zip(&counts)
+            .map(|(label, &count)| {
+                let bar = "#".repeat((count * BAR_WIDTH).div_ceil(largest) as usize);
+                format!("{label:>label_width$} {bar} {count}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A table with a row for each statistic and a column for each distribution.
+pub fn summary(columns: &[(&str, &Distribution)]) -> String {
+    let float = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{v:.2}"));
+    let int = |v: Option<u64>| v.map_or("-".to_string(), |v| v.to_string());
+
+    let mut rows: Vec<(String, Vec<String>)> = vec![
+        (
+            String::new(),
+            columns.iter().map(|(name, _)| name.to_string()).collect(),
+        ),
+        (
+            "mean".to_string(),
+            columns.iter().map(|(_, d)| float(d.mean())).collect(),
+        ),
+        (
+            "median".to_string(),
+            columns.iter().map(|(_, d)| float(d.median())).collect(),
+        ),
+        (
+            "min".to_string(),
+            columns.iter().map(|(_, d)| int(d.min())).collect(),
+        ),
+        (
+            "max".to_string(),
+            columns.iter().map(|(_, d)| int(d.max())).collect(),
+        ),
+    ];
+    rows.extend(PERCENTILES.iter().map(|&p| {
+        (
+            format!("p{p}"),
+            columns.iter().map(|(_, d)| int(d.percentile(p))).collect(),
+        )
+    }));
+
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let width = rows
+        .iter()
+        .flat_map(|(_, fields)| fields.iter().map(String::len))
+        .max()
+        .unwrap_or(0);
+
+    rows.iter()
+        .map(|(name, fields)| {
+            let fields: Vec<String> = fields.iter().map(|f| format!("{f:>width$}")).collect();
+            format!("{name:<name_width$}  {}", fields.join("  "))
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The number of algorithms, a summary of their lengths in both metrics, and a histogram of their
+/// lengths in `metric`.
+pub fn algorithms(algs: &[Algorithm], metric: Metric) -> String {
+    let stm = Distribution::new(algs.iter().map(|a| a.len_stm::<u64>()).collect());
+    let mtm = Distribution::new(algs.iter().map(|a| a.len_mtm::<u64>()).collect());
+    if stm.is_empty() {
+        return "count: 0".to_string();
+    }
+
+    let (name, histogram) = match metric {
+        Metric::Stm => ("stm", &stm),
+        Metric::Mtm => ("mtm", &mtm),
+    };
+
+    format!(
+        "count: {}\n\n{}\n\nlength ({name}):\n{}",
+        stm.len(),
+        summary(&[("stm", &stm), ("mtm", &mtm)]),
+        histogram.histogram(),
+    )
+}
+
+/// The number and share of the moves of `algs` in each direction, and the longest streak of
+/// moves in each direction, with the (1-based) number of the algorithm it is in. The directions
+/// are written in the notation convention in use.
+pub fn directions(algs: &[Algorithm]) -> String {
+    let mut counts = [0u64; 4];
+    // The length of the longest streak in each direction, and the algorithm it is in
+    let mut streaks = [(0u64, 0usize); 4];
+
+    for (i, alg) in algs.iter().enumerate() {
+        let mut streak = (None, 0);
+        for dir in grid::directions(alg) {
+            let d = grid::DIRECTIONS.iter().position(|&d| d == dir).unwrap_or(0);
+            counts[d] += 1;
+            streak = match streak {
+                (Some(s), n) if s == d => (Some(d), n + 1),
+                _ => (Some(d), 1),
+            };
+            if streak.1 > streaks[d].0 {
+                streaks[d] = (streak.1, i + 1);
+            }
+        }
+    }
+
+    let total: u64 = counts.iter().sum();
+    let share = |n: u64| {
+        if total == 0 {
+            0.0
+        } else {
+            n as f64 * 100.0 / total as f64
+        }
+    };
+
+    let mut out = "direction  moves   share  longest streak".to_string();
+    for (d, &dir) in grid::DIRECTIONS.iter().enumerate() {
+        let dir = match notation::convention() {
+            Convention::Tile => dir,
+            Convention::Blank => grid::opposite(dir),
+        };
+        let (streak, alg) = streaks[d];
+        let streak = if streak == 0 {
+            "-".to_string()
+        } else {
+            format!("{streak} (algorithm {alg})")
+        };
+        out += &format!(
+            "\n{:<9}  {:>5}  {:>5.1}%  {streak}",
+            grid::direction_char(dir),
+            counts[d],
+            share(counts[d]),
+        );
+    }
+
+    // DIRECTIONS is ordered up, left, down, right
+    out += &format!(
+        "\n\nvertical: {:.1}%  horizontal: {:.1}%",
+        share(counts[0] + counts[2]),
+        share(counts[1] + counts[3]),
+    );
+
+    out
+}
+
+/// Whether the permutation of the pieces of `state`, ignoring the gap, is even.
+fn is_even(state: &Puzzle) -> bool {
+    let pieces: Vec<u64> = grid::pieces(state)
+        .into_iter()
+        .filter(|&p| p != 0)
+        .collect();
+    let mut seen = vec![false; pieces.len()];
+    let mut transpositions = 0;
+
+    // A cycle of length n is n - 1 transpositions
+    for start in 0..pieces.len() {
+        let mut i = start;
+        let mut len = 0;
+        while !seen[i] {
+            seen[i] = true;
+            i = pieces[i] as usize - 1;
+            len += 1;
+        }
+        transpositions += len.saturating_sub(1);
+    }
+
+    transpositions % 2 == 0
+}
+
+/// The number of states with the gap in each position, drawn as a grid.
+fn gap_positions(states: &[Puzzle]) -> String {
+    let (width, height) = states[0].size().into();
+    let mut counts = vec![0u64; (width * height) as usize];
+    for state in states {
+        let (x, y) = state.gap_position_xy();
+        counts[(x + y * width) as usize] += 1;
+    }
+
+    let cell = counts
+        .iter()
+        .map(|c| c.to_string().len())
+        .max()
+        .unwrap_or(1);
+    counts
+        .chunks(width as usize)
+        .map(|row| {
+            row.iter()
+                .map(|c| format!("{c:>cell$}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The number of states, a summary and histogram of their Manhattan distances, how many have an
+/// even or odd permutation, and the positions of the gap. With a solver, the summary and a
+/// histogram of their optimal solution lengths are included too.
+pub fn states(states: &[Puzzle], solver: Option<&mut SolveFn>) -> Result<String, Box<dyn Error>> {
+    let Some(first) = states.first() else {
+        return Ok("count: 0".to_string());
+    };
+    if states.iter().any(|s| s.size() != first.size()) {
+        return Err("the states must all have the same size".into());
+    }
+
+    let md = Distribution::new(states.iter().map(heuristic::manhattan_distance).collect());
+    let mut columns = vec![("md", &md)];
+
+    let mut unsolvable = 0;
+    let optimal = match solver {
+        Some(solver) => {
+            let mut progress = Progress::new("solve", Some(states.len() as u64));
+            let mut lengths = Vec::new();
+            for state in states {
+                if goal::is_solvable(state) {
+                    lengths.push(solver(state)?.len_stm::<u64>());
+                } else {
+                    unsolvable += 1;
+                }
+                progress.inc();
+            }
+            Some(Distribution::new(lengths))
+        }
+        None => None,
+    };
+    if let Some(optimal) = &optimal {
+        columns.push(("optimal", optimal));
+    }
+
+    let even = states.iter().filter(|s| is_even(s)).count();
+    let mut out = format!(
+        "count: {}\n\n{}\n\nmd:\n{}",
+        states.len(),
+        summary(&columns),
+        md.histogram(),
+    );
+    if let Some(optimal) = &optimal {
+        out += &format!("\n\noptimal length:\n{}", optimal.histogram());
+        if unsolvable > 0 {
+            out += &format!("\n({unsolvable} unsolvable states not included)");
+        }
+    }
+    out += &format!(
+        "\n\npermutation parity: {even} even, {} odd\n\ngap position:\n{}",
+        states.len() - even,
+        gap_positions(states),
+    );
+
+    Ok(out)
+}
+
+/// An estimate of the mean optimal solution length of random states, from the optimal solutions
+/// of `states`: the sample mean and standard deviation, and a 95% confidence interval for the
+/// mean. If the solving is interrupted, the estimate uses the states solved so far.
+pub fn estimate_mean(states: &[Puzzle], solver: &mut SolveFn) -> Result<String, Box<dyn Error>> {
+    let mut progress = Progress::new("solve", Some(states.len() as u64));
+    let mut lengths = Vec::new();
+    for state in states {
+        if util::interrupted() {
+            break;
+        }
+        lengths.push(solver(state)?.len_stm::<u64>());
+        progress.inc();
+    }
+    drop(progress);
+
+    let lengths = Distribution::new(lengths);
+    let (Some(mean), Some(std_dev)) = (lengths.mean(), lengths.std_dev()) else {
+        return Err("at least two states must be solved to estimate the mean".into());
+    };
+    let margin = Z_95 * std_dev / (lengths.len() as f64).sqrt();
+
+    Ok(format!(
+        "samples: {}\n\
+        mean: {mean:.3}\n\
+        std dev: {std_dev:.3}\n\
+        95% confidence interval: {:.3} to {:.3} (+/- {margin:.3})",
+        lengths.len(),
+        mean - margin,
+        mean + margin,
+    ))
+}
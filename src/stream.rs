@@ -0,0 +1,301 @@
+use std::io::{self, BufRead, Write};
+
+use slidy::algorithm::direction::Direction;
+use thiserror::Error;
+
+use crate::{enums::Convention, grid, notation};
+
+#[derive(Debug, Error)]
+pub enum StreamError {
+    #[error("InvalidCharacter: '{0}' is not part of an algorithm")]
+    InvalidCharacter(char),
+
+    #[error("MissingDirection: the amount {0} does not follow a move")]
+    MissingDirection(char),
+
+    #[error("InvalidAmount: a move must have an amount between 1 and {}", u64::MAX)]
+    InvalidAmount,
+
+    #[error("InvalidRange: the slice starts at {start}, after its end at {end}")]
+    InvalidRange { start: u64, end: u64 },
+
+    #[error("SliceOutOfRange: the algorithm has {len} moves, but the slice ends at {end}")]
+    SliceOutOfRange { len: u64, end: u64 },
+
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// One input line, read a byte at a time without storing it.
+pub struct Line<'a> {
+    reader: &'a mut dyn BufRead,
+    ended: bool,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(reader: &'a mut dyn BufRead) -> Self {
+        Self {
+            reader,
+            ended: false,
+        }
+    }
+
+    /// The next byte of the line, or `None` at the end of the line or the input.
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.ended {
+            return Ok(None);
+        }
+
+        let byte = self.reader.fill_buf()?.first().copied();
+        match byte {
+            Some(b) => {
+                self.reader.consume(1);
+                if b == b'\n' {
+                    self.ended = true;
+                    Ok(None)
+                } else {
+                    Ok(Some(b))
+                }
+            }
+            None => {
+                self.ended = true;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Reads up to the end of the line, so that the next line can be read after an error.
+    pub fn skip_rest(&mut self) -> io::Result<()> {
+        while !self.ended {
+            let buf = self.reader.fill_buf()?;
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    self.reader.consume(i + 1);
+                    self.ended = true;
+                }
+                None if buf.is_empty() => self.ended = true,
+                None => {
+                    let len = buf.len();
+                    self.reader.consume(len);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The moves of an algorithm on one input line, with consecutive moves in the same direction
+/// combined, as they are when an algorithm is parsed.
+pub struct Moves<'a, 'b> {
+    line: &'a mut Line<'b>,
+    /// A direction that has been read, but whose amount hasn't been read yet.
+    pending: Option<Direction>,
+    /// A move that has been read, but not yet combined with the previous one.
+    peeked: Option<(Direction, u64)>,
+}
+
+impl<'a, 'b> Moves<'a, 'b> {
+    pub fn new(line: &'a mut Line<'b>) -> Self {
+        Self {
+            line,
+            pending: None,
+            peeked: None,
+        }
+    }
+
+    /// Reads one move as it is written, e.g. `U3`, applying `--lenient`, `--notation` and
+    /// `--convention`.
+    fn next_token(&mut self) -> Result<Option<(Direction, u64)>, StreamError> {
+        let mut amount: Option<u64> = None;
+        loop {
+            let Some(b) = self.line.next_byte()? else {
+                return match (self.pending.take(), amount) {
+                    (_, Some(0)) => Err(StreamError::InvalidAmount),
+                    (dir, amount) => Ok(dir.map(|d| (d, amount.unwrap_or(1)))),
+                };
+            };
+
+            if !b.is_ascii() {
+                return Err(StreamError::InvalidCharacter(char::from(b)));
+            }
+            let Some(c) = notation::normalize_char(char::from(b)) else {
+                continue;
+            };
+
+            if let Some(dir) = grid::parse_direction(c) {
+                if let Some(prev) = self.pending.replace(dir) {
+                    return match amount {
+                        Some(0) => Err(StreamError::InvalidAmount),
+                        amount => Ok(Some((prev, amount.unwrap_or(1)))),
+                    };
+                }
+            } else if let Some(digit) = c.to_digit(10) {
+                if self.pending.is_none() {
+                    return Err(StreamError::MissingDirection(c));
+                }
+                amount = Some(
+                    amount
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|a| a.checked_add(digit as u64))
+                        .ok_or(StreamError::InvalidAmount)?,
+                );
+            } else if c.is_ascii_whitespace() {
+                continue;
+            } else {
+                return Err(StreamError::InvalidCharacter(c));
+            }
+        }
+    }
+}
+
+impl Iterator for Moves<'_, '_> {
+    type Item = Result<(Direction, u64), StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut current = match self.peeked.take() {
+            Some(m) => m,
+            None => match self.next_token() {
+                Ok(Some(m)) => m,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+
+        loop {
+            match self.next_token() {
+                Ok(Some((dir, amount))) if dir == current.0 => {
+                    current.1 = match current.1.checked_add(amount) {
+                        Some(a) => a,
+                        None => return Some(Err(StreamError::InvalidAmount)),
+                    };
+                }
+                Ok(Some(m)) => {
+                    self.peeked = Some(m);
+                    break;
+                }
+                Ok(None) => break,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        Some(Ok(current))
+    }
+}
+
+/// The length of an algorithm in single tile moves, or in multi-tile moves if `mtm` is set.
+pub fn length(moves: Moves, mtm: bool) -> Result<u64, StreamError> {
+    let mut len: u64 = 0;
+    for m in moves {
+        let (_, amount) = m?;
+        len = len.saturating_add(if mtm { 1 } else { amount });
+    }
+
+    Ok(len)
+}
+
+/// Writes moves to `out` as they are read, in the same notation as the `format` command.
+pub struct Formatter {
+    pub long: bool,
+    pub spaced: bool,
+    /// Whether to use `--convention` when writing the moves, rather than the tile convention.
+    pub convention: bool,
+    first: bool,
+}
+
+impl Formatter {
+    pub fn new(long: bool, spaced: bool, convention: bool) -> Self {
+        Self {
+            long,
+            spaced,
+            convention,
+            first: true,
+        }
+    }
+
+    pub fn write(&mut self, out: &mut impl Write, dir: Direction, amount: u64) -> io::Result<()> {
+        let c = grid::direction_char(dir);
+        let c = match (self.convention, notation::convention()) {
+            (true, Convention::Blank) => notation::flip_char(c),
+            _ => c,
+        };
+
+        if self.long {
+            for _ in 0..amount {
+                self.separate(out)?;
+                write!(out, "{c}")?;
+            }
+        } else {
+            self.separate(out)?;
+            match amount {
+                1 => write!(out, "{c}")?,
+                _ => write!(out, "{c}{amount}")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn separate(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.spaced && !self.first {
+            write!(out, " ")?;
+        }
+        self.first = false;
+        Ok(())
+    }
+}
+
+/// Writes an algorithm in the notation given by `formatter`.
+pub fn format(
+    moves: Moves,
+    formatter: &mut Formatter,
+    out: &mut impl Write,
+) -> Result<(), StreamError> {
+    for m in moves {
+        let (dir, amount) = m?;
+        formatter.write(out, dir, amount)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the single tile moves from `start` up to `end` of an algorithm, or to the end of the
+/// algorithm if `end` is `None`.
+pub fn slice(
+    moves: Moves,
+    start: u64,
+    end: Option<u64>,
+    out: &mut impl Write,
+) -> Result<(), StreamError> {
+    if let Some(end) = end.filter(|&end| end < start) {
+        return Err(StreamError::InvalidRange { start, end });
+    }
+
+    let mut formatter = Formatter::new(false, false, false);
+    let mut pos: u64 = 0;
+    for m in moves {
+        let (dir, amount) = m?;
+        let next = pos.saturating_add(amount);
+
+        let from = pos.max(start);
+        let to = end.map_or(next, |end| next.min(end));
+        if from < to {
+            formatter.write(out, dir, to - from)?;
+        }
+
+        pos = next;
+        if end.is_some_and(|end| pos >= end) {
+            break;
+        }
+    }
+
+    match end {
+        Some(end) if pos < end => Err(StreamError::SliceOutOfRange { len: pos, end }),
+        None if pos < start => Err(StreamError::SliceOutOfRange {
+            len: pos,
+            end: start,
+        }),
+        _ => Ok(()),
+    }
+}
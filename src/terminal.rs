@@ -0,0 +1,145 @@
+use std::{
+    error::Error,
+    io::{self, IsTerminal, Write},
+};
+
+/// A key pressed in an interactive mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Backspace,
+    Char(char),
+}
+
+impl Key {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let key = match bytes {
+            [] => return None,
+            [0x1b, b'[' | b'O', b'A', ..] => Key::Up,
+            [0x1b, b'[' | b'O', b'B', ..] => Key::Down,
+            [0x1b, b'[' | b'O', b'C', ..] => Key::Right,
+            [0x1b, b'[' | b'O', b'D', ..] => Key::Left,
+            [0x1b, b'[' | b'O', b'H', ..] | [0x1b, b'[', b'1' | b'7', b'~', ..] => Key::Home,
+            [0x1b, b'[' | b'O', b'F', ..] | [0x1b, b'[', b'4' | b'8', b'~', ..] => Key::End,
+            [0x1b, ..] => Key::Escape,
+            [b'\r' | b'\n', ..] => Key::Enter,
+            [0x7f | 0x08, ..] => Key::Backspace,
+            _ => {
+                let s = String::from_utf8_lossy(bytes);
+                Key::Char(s.chars().next()?)
+            }
+        };
+
+        Some(key)
+    }
+}
+
+/// The terminal in raw mode on the alternate screen, so that keys can be read as they are
+/// pressed. The terminal is restored when this is dropped, including when the program is
+/// interrupted.
+pub struct Screen {
+    #[cfg(unix)]
+    original: libc::termios,
+    /// The terminal that keys are read from: stdin, or `/dev/tty` if stdin is used for input.
+    #[cfg(unix)]
+    fd: libc::c_int,
+}
+
+impl Screen {
+    #[cfg(unix)]
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        if !io::stdout().is_terminal() {
+            return Err("interactive modes need a terminal".into());
+        }
+
+        let fd = if io::stdin().is_terminal() {
+            libc::STDIN_FILENO
+        } else {
+            match unsafe { libc::open(b"/dev/tty\0".as_ptr().cast(), libc::O_RDONLY) } {
+                -1 => return Err("interactive modes need a terminal".into()),
+                fd => fd,
+            }
+        };
+
+        let original = unsafe {
+            let mut termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut termios) == -1 {
+                return Err(io::Error::last_os_error().into());
+            }
+            termios
+        };
+
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        // Reads return after at most a tenth of a second, so that timers can be redrawn
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 1;
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } == -1 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // Switch to the alternate screen and hide the cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+
+        Ok(Self { original, fd })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Err("interactive modes are only supported on unix".into())
+    }
+
+    /// Replaces the contents of the screen with `text`.
+    pub fn draw(&mut self, text: &str) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        // Overwrite the previous contents instead of clearing the screen first, to avoid flicker
+        let text = text.replace('\n', "\x1b[K\n");
+        write!(out, "\x1b[H{text}\x1b[J")?;
+        out.flush()
+    }
+
+    /// Waits a short time for a key to be pressed, returning `None` if none was.
+    #[cfg(unix)]
+    pub fn read_key(&mut self) -> io::Result<Option<Key>> {
+        let mut buf = [0u8; 16];
+        let n = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if n == -1 {
+            let e = io::Error::last_os_error();
+            // SIGINT interrupts the read, and is handled by the caller
+            return match e.kind() {
+                io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(e),
+            };
+        }
+
+        Ok(Key::parse(&buf[..n as usize]))
+    }
+
+    #[cfg(not(unix))]
+    pub fn read_key(&mut self) -> io::Result<Option<Key>> {
+        Ok(None)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+
+        #[cfg(unix)]
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.original);
+            if self.fd != libc::STDIN_FILENO {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
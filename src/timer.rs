@@ -0,0 +1,155 @@
+use std::{error::Error, time::Instant};
+
+use slidy::puzzle::{
+    puzzle::Puzzle,
+    scrambler::{RandomState, Scrambler},
+    size::Size,
+};
+
+use crate::{
+    goal,
+    render::{self, RenderOptions},
+    session::{self, Penalty, Session, Solve},
+    terminal::{Key, Screen},
+    util,
+};
+
+enum Phase {
+    /// Waiting for space to start the timer, or the inspection.
+    Ready,
+    /// Looking at the scramble before the solve, until space is pressed.
+    Inspecting(Instant),
+    /// Timing a solve, with the penalty for the inspection taking too long.
+    Running(Instant, Penalty),
+}
+
+fn scramble(size: Size) -> Puzzle {
+    let mut state = Puzzle::new(size);
+    RandomState.scramble(&mut state);
+    goal::from_standard(&state)
+}
+
+/// The time left to inspect, counting down in whole seconds, or the penalty once the time is up.
+fn countdown(start: Instant, inspection: u64) -> String {
+    let elapsed = start.elapsed().as_millis() as u64;
+    match Penalty::for_inspection(elapsed, inspection) {
+        Penalty::None => (inspection - elapsed).div_ceil(1000).to_string(),
+        Penalty::PlusTwo => "+2".to_string(),
+        Penalty::Dnf => "DNF".to_string(),
+    }
+}
+
+fn view(
+    scramble: &Puzzle,
+    phase: &Phase,
+    session: &Session,
+    inspection: Option<u64>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    let (time, help) = match *phase {
+        Phase::Ready => (
+            session
+                .solves
+                .last()
+                .map_or(session::format_time(0), Solve::format_score),
+            if session.solves.is_empty() {
+                "space: start  q: quit"
+            } else {
+                "space: start  2: +2  d: DNF  q: quit"
+            },
+        ),
+        Phase::Inspecting(start) => (
+            format!("inspection: {}", countdown(start, inspection.unwrap_or(0))),
+            "space: start  esc: cancel",
+        ),
+        Phase::Running(start, _) => (
+            session::format_time(start.elapsed().as_millis() as u64),
+            "space: stop",
+        ),
+    };
+
+    let recent: Vec<String> = session
+        .solves
+        .iter()
+        .rev()
+        .take(5)
+        .map(Solve::format_score)
+        .collect();
+
+    Ok(format!(
+        "{scramble}\n\n{}\n\n{time}\n\n{}\nrecent: {}\n\n{help}\n",
+        render::terminal(scramble, options)?,
+        session.stats(),
+        recent.join(" "),
+    ))
+}
+
+/// Toggles the penalty of the last solve between `penalty` and no penalty.
+fn toggle_penalty(session: &mut Session, penalty: Penalty) -> Result<(), Box<dyn Error>> {
+    let current = session.solves.last().map(|s| s.penalty);
+    let new = if current == Some(penalty) {
+        Penalty::None
+    } else {
+        penalty
+    };
+
+    session.set_penalty(new)
+}
+
+/// Times solves of random states of the given size, started and stopped with space. The solves
+/// are added to `session`.
+///
+/// If `inspection` is given, the first space starts a countdown of that many seconds before the
+/// solve, and starting the solve up to two seconds late gives a +2 penalty, or a DNF after that.
+pub fn timer(
+    size: Size,
+    mut session: Session,
+    inspection: Option<u64>,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let inspection = inspection.map(|s| s * 1000);
+    let mut screen = Screen::new()?;
+    let mut state = scramble(size);
+    let mut phase = Phase::Ready;
+
+    while !util::interrupted() {
+        screen.draw(&view(&state, &phase, &session, inspection, options)?)?;
+
+        let key = screen.read_key()?;
+        phase = match (phase, key) {
+            (Phase::Ready, Some(Key::Escape | Key::Char('q'))) => break,
+            (Phase::Ready, Some(Key::Char(' '))) => match inspection {
+                Some(_) => Phase::Inspecting(Instant::now()),
+                None => Phase::Running(Instant::now(), Penalty::None),
+            },
+            (Phase::Ready, Some(Key::Char('2'))) => {
+                toggle_penalty(&mut session, Penalty::PlusTwo)?;
+                Phase::Ready
+            }
+            (Phase::Ready, Some(Key::Char('d'))) => {
+                toggle_penalty(&mut session, Penalty::Dnf)?;
+                Phase::Ready
+            }
+            (Phase::Inspecting(_), Some(Key::Escape)) => Phase::Ready,
+            (Phase::Inspecting(start), Some(Key::Char(' '))) => {
+                let elapsed = start.elapsed().as_millis() as u64;
+                let penalty = Penalty::for_inspection(elapsed, inspection.unwrap_or(0));
+                Phase::Running(Instant::now(), penalty)
+            }
+            (Phase::Running(start, penalty), Some(_)) => {
+                session.add(Solve {
+                    time: start.elapsed().as_millis() as u64,
+                    penalty,
+                    scramble: state,
+                    solution: None,
+                    phase: None,
+                })?;
+                state = scramble(size);
+                Phase::Ready
+            }
+            (phase, _) => phase,
+        };
+    }
+
+    Ok(())
+}
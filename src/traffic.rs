@@ -0,0 +1,82 @@
+use std::error::Error;
+
+use slidy::{
+    algorithm::algorithm::Algorithm,
+    puzzle::{coloring::Coloring, puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle},
+};
+use svg::{
+    node::{
+        self,
+        element::{Rectangle, Text},
+    },
+    Document,
+};
+
+use crate::{
+    coloring::{DistanceHeatmap, Gradient},
+    grid, render,
+};
+
+/// The number of times a piece moves into each cell while `alg` is applied to `state`, in
+/// row-major order.
+pub fn counts(state: &Puzzle, alg: &Algorithm) -> Result<Vec<u64>, Box<dyn Error>> {
+    let size = state.size();
+    let (width, _) = size.into();
+    let mut counts = vec![0; size.area() as usize];
+    let mut gap = state.gap_position_xy();
+
+    for dir in grid::directions(alg) {
+        counts[(gap.0 + gap.1 * width) as usize] += 1;
+        gap = grid::moved_piece_pos(gap, dir, size)
+            .ok_or("the algorithm can not be applied to the state")?;
+    }
+
+    Ok(counts)
+}
+
+/// Draws a grid with each cell colored by its count, from the first color of the gradient (no
+/// moves) to the last (the most moves into any one cell).
+pub fn document(
+    size: Size,
+    counts: &[u64],
+    tile_size: f32,
+    gradient: Option<Gradient>,
+) -> Document {
+    let (width, height) = size.into();
+    let gradient = gradient.unwrap_or_else(DistanceHeatmap::default_gradient);
+    let max = counts.iter().copied().max().unwrap_or(0);
+
+    let mut document = Document::new()
+        .set("width", width as f32 * tile_size)
+        .set("height", height as f32 * tile_size);
+
+    for (i, &count) in counts.iter().enumerate() {
+        let (x, y) = (
+            (i as u64 % width) as f32 * tile_size,
+            (i as u64 / width) as f32 * tile_size,
+        );
+
+        document = document
+            .add(
+                Rectangle::new()
+                    .set("x", x)
+                    .set("y", y)
+                    .set("width", tile_size)
+                    .set("height", tile_size)
+                    .set("fill", render::hex(gradient.color(count, max + 1)))
+                    .set("stroke", "black")
+                    .set("stroke-width", 1),
+            )
+            .add(
+                Text::new()
+                    .set("x", x + tile_size / 2.0)
+                    .set("y", y + tile_size / 2.0)
+                    .set("font-size", tile_size * 30.0 / 75.0)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "central")
+                    .add(node::Text::new(count.to_string())),
+            );
+    }
+
+    document
+}
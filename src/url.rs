@@ -0,0 +1,91 @@
+use std::error::Error;
+
+use slidy::puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle};
+
+use crate::notation;
+
+/// The query parameters that are checked for a state when parsing a link without a template.
+const STATE_PARAMS: [&str; 3] = ["state", "scramble", "puzzle"];
+
+/// Percent-encodes everything except unreserved characters.
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn decode(s: &str) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Vec::new();
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                let hex = std::str::from_utf8(&hex)?;
+                bytes.push(
+                    u8::from_str_radix(hex, 16).map_err(|_| format!("invalid escape %{hex}"))?,
+                );
+            }
+            b'+' => bytes.push(b' '),
+            b => bytes.push(b),
+        }
+    }
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Fills in a link template. `{state}` is replaced by the state, and `{width}` and `{height}` by
+/// the size of the puzzle.
+pub fn link(template: &str, state: &Puzzle) -> String {
+    let (width, height) = state.size().into();
+    template
+        .replace("{state}", &encode(&state.to_string()))
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string())
+}
+
+/// Extracts the state from a link. With a template, the link must match it, otherwise the state
+/// is taken from the first query parameter named `state`, `scramble` or `puzzle`.
+pub fn parse(link: &str, template: Option<&str>) -> Result<Puzzle, Box<dyn Error>> {
+    let encoded = match template {
+        Some(template) => {
+            let (prefix, suffix) = template
+                .split_once("{state}")
+                .ok_or("the template does not contain {state}")?;
+            // The size placeholders can't be matched exactly, so only the text next to the state
+            // is used
+            let prefix = prefix.rsplit(['}', '{']).next().unwrap_or_default();
+            let suffix = suffix.split(['{', '}']).next().unwrap_or_default();
+
+            let start = link
+                .find(prefix)
+                .ok_or("the link does not match the template")?
+                + prefix.len();
+            let rest = &link[start..];
+            let end = if suffix.is_empty() {
+                rest.len()
+            } else {
+                rest.find(suffix)
+                    .ok_or("the link does not match the template")?
+            };
+            &rest[..end]
+        }
+        None => {
+            let (_, query) = link.split_once('?').ok_or("the link has no query string")?;
+            let query = query.split('#').next().unwrap_or_default();
+            query
+                .split('&')
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| STATE_PARAMS.contains(name))
+                .map(|(_, value)| value)
+                .ok_or("the link has no state parameter")?
+        }
+    };
+
+    Ok(notation::parse_state(&decode(encoded)?)?)
+}
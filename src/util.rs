@@ -1,6 +1,337 @@
-use std::{error::Error, str::FromStr};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
 
-pub fn try_func<T: FromStr + 'static, R, F: Fn(&mut T) -> R>(
+use rayon::prelude::*;
+
+use crate::{
+    enums::{ErrorFormat, ErrorPolicy},
+    notation::parse_input,
+    stream::Line,
+};
+
+static ERROR_OPTIONS: OnceLock<(ErrorFormat, ErrorPolicy)> = OnceLock::new();
+
+static EXIT_STATUS: AtomicI32 = AtomicI32::new(0);
+
+static INPUTS: OnceLock<Vec<String>> = OnceLock::new();
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+static TIME: AtomicBool = AtomicBool::new(false);
+
+static PROCESSED: AtomicU64 = AtomicU64::new(0);
+
+static FAILED: AtomicU64 = AtomicU64::new(0);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+/// The number of lines read at a time when processing inputs on several threads. The results of
+/// each chunk are printed in order once the whole chunk is done.
+const CHUNK_SIZE: usize = 1024;
+
+/// An error that can be sent between threads.
+pub type SendError = Box<dyn Error + Send + Sync>;
+
+/// Sets the files that inputs are read from, in order, instead of stdin. `-` means stdin. Must
+/// be called before any input is read, and only once.
+pub fn set_inputs(paths: Vec<String>) {
+    INPUTS.set(paths).unwrap();
+}
+
+/// Sets how much is logged to stderr (0 for nothing, 1 for a summary of the run, 2 for every
+/// input), and whether the time taken for each input is reported.
+pub fn set_logging(verbosity: u8, time: bool) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+    TIME.store(time, Ordering::Relaxed);
+}
+
+/// Writes a message to stderr if the verbosity is at least `level`.
+pub fn log(level: u8, message: std::fmt::Arguments) {
+    if VERBOSITY.load(Ordering::Relaxed) >= level {
+        let prefix = if level > 1 { "debug" } else { "info" };
+        eprintln!("{prefix}: {message}");
+    }
+}
+
+/// The number of inputs processed so far, and how many of them failed.
+pub fn progress() -> (u64, u64) {
+    (
+        PROCESSED.load(Ordering::Relaxed),
+        FAILED.load(Ordering::Relaxed),
+    )
+}
+
+/// Logs the number of inputs processed, and the total time taken if `--time` was given. If the
+/// command was interrupted, the number of inputs processed is always written.
+pub fn log_summary(elapsed: Duration) {
+    let (processed, failed) = progress();
+    if interrupted() {
+        set_status(Status::Interrupted);
+        eprintln!("interrupted: processed {processed} inputs, {failed} failed");
+    } else {
+        log(
+            1,
+            format_args!("processed {processed} inputs, {failed} failed"),
+        );
+    }
+
+    if TIME.load(Ordering::Relaxed) {
+        eprintln!("time: total {:.3}ms", elapsed.as_secs_f64() * 1000.0);
+    }
+}
+
+/// Runs `f` on one input, counting and timing it.
+fn process<T, R: Outcome, F: Fn(&mut T) -> R>(
+    f: &F,
+    t: &mut T,
+    location: Option<&Location>,
+) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let result = f(t).into_result();
+    processed(location, start.elapsed());
+
+    result
+}
+
+/// Counts an input as processed, and logs the time it took.
+fn processed(location: Option<&Location>, elapsed: Duration) {
+    PROCESSED.fetch_add(1, Ordering::Relaxed);
+    let name = location.map_or("argument".to_string(), Location::to_string);
+    log(2, format_args!("{name}: done in {elapsed:?}"));
+    if TIME.load(Ordering::Relaxed) {
+        eprintln!("time: {name}: {:.3}ms", elapsed.as_secs_f64() * 1000.0);
+    }
+}
+
+/// Parses an input line and runs `f` on it. Lines that can't be parsed still count as
+/// processed.
+fn process_line<T: FromStr + 'static, R: Outcome, F: Fn(&mut T) -> R>(
+    f: &F,
+    location: &Location,
+    line: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    <T as FromStr>::Err: Error,
+{
+    match parse_input::<T>(line) {
+        Ok(mut t) => process(f, &mut t, Some(location)),
+        Err(e) => {
+            PROCESSED.fetch_add(1, Ordering::Relaxed);
+            Err(e.into())
+        }
+    }
+}
+
+/// Where an input line was read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Location {
+    /// The input file, or `None` for stdin.
+    file: Option<String>,
+    /// The line number, starting from 1.
+    line: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{file}:{}", self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+/// A reader for every input file, or for stdin if no files were given, along with the file name,
+/// or `None` for stdin. Each file is only opened when its reader is needed.
+fn input_readers() -> impl Iterator<Item = (Option<String>, io::Result<Box<dyn BufRead>>)> {
+    let paths = match INPUTS.get() {
+        Some(paths) if !paths.is_empty() => paths.clone(),
+        _ => vec!["-".to_string()],
+    };
+
+    paths.into_iter().map(|path| {
+        log(
+            1,
+            format_args!(
+                "reading inputs from {}",
+                if path == "-" { "stdin" } else { &path }
+            ),
+        );
+
+        if path == "-" {
+            let reader: Box<dyn BufRead> = Box::new(io::stdin().lock());
+            return (None, Ok(reader));
+        }
+
+        let reader = File::open(&path)
+            .map(|file| Box::new(BufReader::new(file)) as Box<dyn BufRead>)
+            .map_err(|e| io::Error::new(e.kind(), format!("{path}: {e}")));
+        (Some(path), reader)
+    })
+}
+
+/// The lines of every input file, or of stdin if no files were given.
+fn input_lines() -> Box<dyn Iterator<Item = (Location, io::Result<String>)>> {
+    Box::new(
+        input_readers().flat_map(|(file, reader)| -> Box<dyn Iterator<Item = _>> {
+            let location = move |i: usize| Location {
+                file: file.clone(),
+                line: i + 1,
+            };
+
+            match reader {
+                Ok(reader) => Box::new(
+                    reader
+                        .lines()
+                        .enumerate()
+                        .map(move |(i, line)| (location(i), line)),
+                ),
+                Err(e) => Box::new(std::iter::once((location(0), Err(e)))),
+            }
+        }),
+    )
+}
+
+/// The exit code of the program. When several inputs have different statuses, the largest code
+/// is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success = 0,
+    /// An input could not be parsed or processed.
+    Invalid = 1,
+    /// A puzzle state is unsolvable.
+    Unsolvable = 2,
+    /// An input was filtered out, e.g. a suboptimal algorithm in `filter-optimal`.
+    Rejected = 3,
+    /// The command was stopped by SIGINT, the usual 128 + the signal number.
+    Interrupted = 130,
+}
+
+pub fn set_status(status: Status) {
+    EXIT_STATUS.fetch_max(status as i32, Ordering::Relaxed);
+}
+
+pub fn exit_code() -> i32 {
+    EXIT_STATUS.load(Ordering::Relaxed)
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    // A second Ctrl-C stops the program immediately
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
+
+/// Makes SIGINT stop the command after the input currently being processed, so that the
+/// results so far are kept.
+pub fn handle_interrupts() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Whether SIGINT has been received. Long running loops should stop when this is true.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Sets the number of threads used by [`par_try_func`].
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs.max(1), Ordering::Relaxed);
+}
+
+/// Sets how errors for individual inputs are reported. Must be called before any input is
+/// processed, and only once.
+pub fn set_error_options(format: ErrorFormat, policy: ErrorPolicy) {
+    ERROR_OPTIONS.set((format, policy)).unwrap();
+}
+
+/// The result of running a command on one input.
+pub trait Outcome {
+    fn into_result(self) -> Result<(), Box<dyn Error>>;
+}
+
+impl Outcome for () {
+    fn into_result(self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+impl<E: Into<Box<dyn Error>>> Outcome for Result<(), E> {
+    fn into_result(self) -> Result<(), Box<dyn Error>> {
+        self.map_err(Into::into)
+    }
+}
+
+fn error_options() -> (ErrorFormat, ErrorPolicy) {
+    ERROR_OPTIONS
+        .get()
+        .copied()
+        .unwrap_or((ErrorFormat::Text, ErrorPolicy::Continue))
+}
+
+/// Writes an error for one input to stderr in the format set by `--error-format`.
+fn write_error(location: Option<&Location>, input: Option<&str>, error: &dyn Error) {
+    match error_options().0 {
+        ErrorFormat::Text => {
+            let location = location.map(|l| format!("{l}: ")).unwrap_or_default();
+            let input = input.map(|i| format!("\"{i}\": ")).unwrap_or_default();
+            eprintln!("error: {location}{input}{error}");
+        }
+        ErrorFormat::Json => {
+            let mut fields = Vec::new();
+            if let Some(location) = location {
+                if let Some(file) = &location.file {
+                    fields.push(format!("\"file\":{}", json_string(file)));
+                }
+                fields.push(format!("\"line\":{}", location.line));
+            }
+            if let Some(input) = input {
+                fields.push(format!("\"input\":{}", json_string(input)));
+            }
+            fields.push(format!("\"error\":{}", json_string(&error.to_string())));
+            eprintln!("{{{}}}", fields.join(","));
+        }
+    }
+}
+
+/// Writes an error for one input to stderr, and exits if the batch should stop. `location`
+/// and `input` are where the input was read from and its contents, if it wasn't given as an
+/// argument.
+fn report(location: Option<&Location>, input: Option<&str>, error: &dyn Error) {
+    write_error(location, input, error);
+
+    FAILED.fetch_add(1, Ordering::Relaxed);
+    set_status(Status::Invalid);
+    if error_options().1 == ErrorPolicy::Stop {
+        crate::output::abort(exit_code());
+    }
+}
+
+/// Writes an error for an input that was read correctly but rejected, e.g. a solution that
+/// doesn't solve its state. Unlike an input that can't be processed, this never stops the batch.
+pub fn report_rejected(input: &str, error: &dyn Error) {
+    write_error(None, Some(input), error);
+    set_status(Status::Rejected);
+}
+
+pub fn try_func<T: FromStr + 'static, R: Outcome, F: Fn(&mut T) -> R>(
     f: F,
     t: Option<T>,
 ) -> Result<(), Box<dyn Error>>
@@ -8,7 +339,9 @@ where
     <T as FromStr>::Err: Error,
 {
     if let Some(mut t) = t {
-        f(&mut t);
+        if let Err(e) = process(&f, &mut t, None) {
+            report(None, None, e.as_ref());
+        }
     } else {
         loop_func(f)?;
     }
@@ -16,19 +349,135 @@ where
     Ok(())
 }
 
-pub fn loop_func<T: FromStr + 'static, R, F: Fn(&mut T) -> R>(f: F) -> Result<(), Box<dyn Error>>
+pub fn loop_func<T: FromStr + 'static, R: Outcome, F: Fn(&mut T) -> R>(
+    f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    <T as FromStr>::Err: Error,
+{
+    for (location, line) in input_lines() {
+        if interrupted() {
+            break;
+        }
+
+        let line = line?;
+        if let Err(e) = process_line(&f, &location, &line) {
+            report(Some(&location), Some(&line), e.as_ref());
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `f` on every input line without reading the lines into memory first, for inputs such as
+/// very long algorithms. `f` reads as much of the line as it needs, and the rest is skipped.
+pub fn stream_func<R: Outcome, F: Fn(&mut Line) -> R>(f: F) -> Result<(), Box<dyn Error>> {
+    for (file, reader) in input_readers() {
+        let mut reader = reader?;
+        let mut line = 0;
+        while !reader.fill_buf()?.is_empty() {
+            if interrupted() {
+                return Ok(());
+            }
+
+            line += 1;
+            let location = Location {
+                file: file.clone(),
+                line,
+            };
+
+            let mut input = Line::new(reader.as_mut());
+            if let Err(e) = process(&f, &mut input, Some(&location)) {
+                report(Some(&location), None, e.as_ref());
+            }
+            input.skip_rest()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Like [`try_func`], but `f` returns the text to print instead of printing it, so that inputs
+/// can be processed on several threads. The output is in the same order as the inputs.
+pub fn par_try_func<T, F>(f: F, t: Option<T>) -> Result<(), Box<dyn Error>>
 where
+    T: FromStr + Send + 'static,
     <T as FromStr>::Err: Error,
+    F: Fn(&mut T) -> Result<Option<String>, SendError> + Sync,
 {
-    for line in std::io::stdin().lines() {
-        let mut t = T::from_str(&line?)?;
-        f(&mut t);
+    let print = |t: &mut T| -> Result<(), Box<dyn Error>> {
+        if let Some(out) = f(t).map_err(|e| -> Box<dyn Error> { e })? {
+            println!("{out}");
+        }
+        Ok(())
+    };
+
+    let jobs = JOBS.load(Ordering::Relaxed);
+    if t.is_some() || jobs == 1 {
+        return try_func(print, t);
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    let mut lines = input_lines();
+
+    while !interrupted() {
+        // The lines read before an error are still processed, and the error is returned after
+        let mut chunk = Vec::new();
+        let mut error = None;
+        for (location, line) in lines.by_ref().take(CHUNK_SIZE) {
+            match line {
+                Ok(line) => chunk.push((location, line)),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        if chunk.is_empty() && error.is_none() {
+            break;
+        }
+
+        // The time taken by each input, or `None` if it couldn't be parsed
+        let results: Vec<(Option<Duration>, Result<Option<String>, SendError>)> =
+            pool.install(|| {
+                chunk
+                    .par_iter()
+                    .map(|(_, line)| match parse_input::<T>(line) {
+                        Ok(mut t) => {
+                            let start = Instant::now();
+                            let result = f(&mut t);
+                            (Some(start.elapsed()), result)
+                        }
+                        Err(e) => (None, Err(e.to_string().into())),
+                    })
+                    .collect()
+            });
+
+        // The inputs are counted, logged and printed one at a time in order, the same as
+        // `process_line` does on one thread
+        for ((location, line), (elapsed, result)) in chunk.iter().zip(results) {
+            match elapsed {
+                Some(elapsed) => processed(Some(location), elapsed),
+                None => {
+                    PROCESSED.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            match result {
+                Ok(Some(out)) => println!("{out}"),
+                Ok(None) => {}
+                Err(e) => report(Some(location), Some(line), e.as_ref()),
+            }
+        }
+
+        if let Some(e) = error {
+            return Err(e.into());
+        }
     }
 
     Ok(())
 }
 
-pub fn try_func_once<T: FromStr + 'static, R, F: Fn(&mut T) -> R>(
+pub fn try_func_once<T: FromStr + 'static, R: Outcome, F: Fn(&mut T) -> R>(
     f: F,
     t: Option<T>,
 ) -> Result<(), Box<dyn Error>>
@@ -36,12 +485,68 @@ where
     <T as FromStr>::Err: Error,
 {
     if let Some(mut t) = t {
-        f(&mut t);
-    } else {
-        let line = std::io::stdin().lines().next().unwrap()?;
-        let mut t = T::from_str(&line)?;
-        f(&mut t);
+        if let Err(e) = process(&f, &mut t, None) {
+            report(None, None, e.as_ref());
+        }
+        return Ok(());
+    }
+
+    let (location, line) = input_lines().next().ok_or("no input")?;
+    let line = line?;
+    if let Err(e) = process_line(&f, &location, &line) {
+        report(Some(&location), Some(&line), e.as_ref());
     }
 
     Ok(())
 }
+
+pub fn read_all<T: FromStr + 'static>() -> Result<Vec<T>, Box<dyn Error>>
+where
+    <T as FromStr>::Err: Error,
+{
+    let mut v = Vec::new();
+    for (location, line) in input_lines() {
+        let line = line?;
+        match parse_input::<T>(&line) {
+            Ok(t) => v.push(t),
+            Err(e) => return Err(format!("{location}: \"{line}\": {e}").into()),
+        }
+    }
+
+    Ok(v)
+}
+
+/// Reads and parses every line of a file.
+pub fn read_file<T: FromStr + 'static>(path: &str) -> Result<Vec<T>, Box<dyn Error>>
+where
+    <T as FromStr>::Err: Error,
+{
+    let file = File::open(path).map_err(|e| format!("{path}: {e}"))?;
+
+    let mut v = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        match parse_input::<T>(&line) {
+            Ok(t) => v.push(t),
+            Err(e) => return Err(format!("{path}:{}: \"{line}\": {e}", i + 1).into()),
+        }
+    }
+
+    Ok(v)
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\t' => out += "\\t",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use slidy::puzzle::{puzzle::Puzzle, size::Size};
+
+/// A problem found in a puzzle state string. Rows and columns are numbered from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    Empty,
+    NotANumber {
+        row: usize,
+        column: usize,
+        token: String,
+    },
+    RowLength {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+    WrongSize {
+        found: (u64, u64),
+        expected: (u64, u64),
+    },
+    OutOfRange {
+        row: usize,
+        column: usize,
+        piece: u64,
+        max: u64,
+    },
+    Duplicate {
+        piece: u64,
+        positions: Vec<(usize, usize)>,
+    },
+    Missing(Vec<u64>),
+    Other(String),
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Empty => write!(f, "the state contains no pieces"),
+            Problem::NotANumber { row, column, token } => {
+                write!(f, "row {row}, column {column}: \"{token}\" is not a number")
+            }
+            Problem::RowLength { row, len, expected } => {
+                write!(f, "row {row} has {len} pieces, expected {expected}")
+            }
+            Problem::WrongSize { found, expected } => {
+                let ((w, h), (ew, eh)) = (found, expected);
+                write!(f, "the state has size {w}x{h}, expected {ew}x{eh}")
+            }
+            Problem::OutOfRange {
+                row,
+                column,
+                piece,
+                max,
+            } => write!(
+                f,
+                "row {row}, column {column}: piece {piece} is out of range (expected 0 to {max})"
+            ),
+            Problem::Duplicate { piece, positions } => {
+                let positions: Vec<_> = positions
+                    .iter()
+                    .map(|(row, column)| format!("row {row} column {column}"))
+                    .collect();
+                write!(
+                    f,
+                    "piece {piece} appears more than once ({})",
+                    positions.join(", ")
+                )
+            }
+            Problem::Missing(pieces) => {
+                let pieces: Vec<_> = pieces.iter().map(u64::to_string).collect();
+                write!(f, "missing pieces: {}", pieces.join(", "))
+            }
+            Problem::Other(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// Finds every problem with a puzzle state string, optionally also checking that it has the
+/// given size.
+pub fn validate(s: &str, size: Option<Size>) -> Vec<Problem> {
+    let rows: Vec<Vec<&str>> = s
+        .trim()
+        .split('/')
+        .map(|row| row.split_whitespace().collect())
+        .collect();
+
+    let width = rows[0].len();
+    if width == 0 && rows.len() == 1 {
+        return vec![Problem::Empty];
+    }
+
+    let mut problems = Vec::new();
+
+    for (row, r) in rows.iter().enumerate().skip(1) {
+        if r.len() != width {
+            problems.push(Problem::RowLength {
+                row: row + 1,
+                len: r.len(),
+                expected: width,
+            });
+        }
+    }
+
+    let found = Size::new(width as u64, rows.len() as u64).ok();
+    if let (Some(found), Some(expected)) = (found, size) {
+        let (found, expected): ((u64, u64), (u64, u64)) = (found.into(), expected.into());
+        if found != expected {
+            problems.push(Problem::WrongSize { found, expected });
+        }
+    }
+
+    let area = size
+        .or(found)
+        .map(|s| s.area())
+        .unwrap_or_else(|| rows.iter().map(Vec::len).sum::<usize>() as u64);
+
+    let mut positions: BTreeMap<u64, Vec<(usize, usize)>> = BTreeMap::new();
+    for (row, r) in rows.iter().enumerate() {
+        for (column, token) in r.iter().enumerate() {
+            let (row, column) = (row + 1, column + 1);
+            match token.parse::<u64>() {
+                Ok(piece) if piece >= area => problems.push(Problem::OutOfRange {
+                    row,
+                    column,
+                    piece,
+                    max: area.saturating_sub(1),
+                }),
+                Ok(piece) => positions.entry(piece).or_default().push((row, column)),
+                Err(_) => problems.push(Problem::NotANumber {
+                    row,
+                    column,
+                    token: token.to_string(),
+                }),
+            }
+        }
+    }
+
+    for (&piece, positions) in &positions {
+        if positions.len() > 1 {
+            problems.push(Problem::Duplicate {
+                piece,
+                positions: positions.clone(),
+            });
+        }
+    }
+
+    let missing: Vec<u64> = (0..area).filter(|p| !positions.contains_key(p)).collect();
+    if !missing.is_empty() {
+        problems.push(Problem::Missing(missing));
+    }
+
+    if problems.is_empty() {
+        if let Err(e) = Puzzle::from_str(s) {
+            problems.push(Problem::Other(e.to_string()));
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,254 @@
+use std::{error::Error, time::Instant};
+
+use slidy::{
+    algorithm::direction::Direction,
+    puzzle::{puzzle::Puzzle, sliding_puzzle::SlidingPuzzle},
+};
+
+use crate::{
+    enums::Convention,
+    goal, grid,
+    heuristic::{HeuristicType, Heuristics},
+    notation,
+    render::{self, RenderOptions},
+    terminal::{Key, Screen},
+    util,
+};
+
+/// A node on the search stack, with the index in [`grid::DIRECTIONS`] of the next move to try.
+struct Frame {
+    next: usize,
+}
+
+/// An IDA* search that expands one node at a time, so that it can be drawn between nodes. This
+/// is much slower than the solvers, and is only meant to show how they work and to compare
+/// heuristics.
+pub struct Search<'a> {
+    state: Puzzle,
+    heuristic: HeuristicType,
+    heuristics: &'a mut Heuristics,
+    stack: Vec<Frame>,
+    /// The moves from the start to the current node.
+    path: Vec<Direction>,
+    bound: u64,
+    /// The smallest cost above the bound seen in this iteration, which is the next bound.
+    next_bound: u64,
+    pub nodes: u64,
+    iteration_nodes: u64,
+    /// The path to the node with the smallest heuristic seen so far, and its heuristic.
+    best: (u64, Vec<Direction>),
+    /// Whether the last node was pruned because its cost was above the bound.
+    pruned: bool,
+    solution: Option<Vec<Direction>>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(
+        state: Puzzle,
+        heuristic: HeuristicType,
+        heuristics: &'a mut Heuristics,
+    ) -> Result<Self, Box<dyn Error>> {
+        let h = heuristics
+            .bound(heuristic, &state)
+            .ok_or("the heuristic is not available for puzzles of this size")?;
+
+        let mut search = Self {
+            state,
+            heuristic,
+            heuristics,
+            stack: Vec::new(),
+            path: Vec::new(),
+            bound: h,
+            next_bound: u64::MAX,
+            nodes: 0,
+            iteration_nodes: 0,
+            best: (h, Vec::new()),
+            pruned: false,
+            solution: None,
+        };
+        search.enter();
+
+        Ok(search)
+    }
+
+    fn h(&mut self) -> u64 {
+        // The heuristic is available for this size, so it was checked in `new`
+        self.heuristics
+            .bound(self.heuristic, &self.state)
+            .unwrap_or_default()
+    }
+
+    /// Expands the node just moved to: stops if it is solved, and backs out of it if its cost
+    /// is above the bound.
+    fn enter(&mut self) {
+        self.nodes += 1;
+        self.iteration_nodes += 1;
+
+        let h = self.h();
+        if h == 0 {
+            self.solution = Some(self.path.clone());
+            return;
+        }
+        if h < self.best.0 {
+            self.best = (h, self.path.clone());
+        }
+
+        let cost = self.path.len() as u64 + h;
+        self.pruned = cost > self.bound;
+        if self.pruned {
+            self.next_bound = self.next_bound.min(cost);
+            self.back();
+        } else {
+            self.stack.push(Frame { next: 0 });
+        }
+    }
+
+    /// Undoes the last move of the path.
+    fn back(&mut self) {
+        if let Some(dir) = self.path.pop() {
+            self.state.try_move_dir(grid::opposite(dir));
+        }
+    }
+
+    /// Moves to the next node of the search and expands it, starting the next iteration with a
+    /// larger bound if every node within the bound has been expanded.
+    fn step(&mut self) {
+        if self.solution.is_some() {
+            return;
+        }
+
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                self.bound = self.next_bound;
+                self.next_bound = u64::MAX;
+                self.iteration_nodes = 0;
+                self.enter();
+                return;
+            };
+
+            let Some(&dir) = grid::DIRECTIONS.get(frame.next) else {
+                self.stack.pop();
+                // The start has no move leading to it
+                if !self.stack.is_empty() {
+                    self.back();
+                }
+                continue;
+            };
+            frame.next += 1;
+
+            // Undoing the previous move can never be part of an optimal solution
+            if self.path.last() == Some(&grid::opposite(dir)) || !self.state.try_move_dir(dir) {
+                continue;
+            }
+            self.path.push(dir);
+            self.enter();
+            return;
+        }
+    }
+
+    /// Searches until a solution is found, returning `None` if there is none within `max_nodes`
+    /// nodes.
+    pub fn run(&mut self, max_nodes: u64) -> Option<&[Direction]> {
+        while self.solution.is_none() && self.nodes < max_nodes && !util::interrupted() {
+            self.step();
+        }
+
+        self.solution.as_deref()
+    }
+
+    fn view(
+        &mut self,
+        speed: f64,
+        paused: bool,
+        options: &RenderOptions,
+    ) -> Result<String, Box<dyn Error>> {
+        let h = self.h();
+        let status = match (&self.solution, paused) {
+            (Some(solution), _) => format!("Solved in {} moves!", solution.len()),
+            (None, true) => "Paused".to_string(),
+            (None, false) => format!("{speed} nodes/s"),
+        };
+        let node = if self.pruned {
+            "pruned, cost above the bound"
+        } else {
+            "expanding"
+        };
+
+        Ok(format!(
+            "{}\n\n\
+            Bound: {}  Nodes: {} ({} in this iteration)\n\
+            Depth: {}  Heuristic: {h}  Cost: {}  ({node})\n\
+            Path: {}\n\
+            Best partial path: {} (heuristic {})\n\n\
+            {status}\n\
+            space: pause  right: step  +/-: speed  q: quit\n",
+            render::terminal(&self.state, options)?,
+            self.bound,
+            self.nodes,
+            self.iteration_nodes,
+            self.path.len(),
+            self.path.len() as u64 + h,
+            moves(self.solution.as_ref().unwrap_or(&self.path)),
+            moves(&self.best.1),
+            self.best.0,
+        ))
+    }
+}
+
+fn moves(path: &[Direction]) -> String {
+    let moves: String = path.iter().map(|&d| grid::direction_char(d)).collect();
+    match notation::convention() {
+        Convention::Tile => moves,
+        Convention::Blank => notation::flip(&moves),
+    }
+}
+
+/// Shows an IDA* search for a solution of `state` in the terminal, one node at a time, with the
+/// bound, the number of nodes, the path to the node being expanded and the state at that node.
+/// `speed` is the number of nodes expanded per second.
+pub fn watch_solve(
+    state: Puzzle,
+    heuristic: HeuristicType,
+    mut speed: f64,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    if !goal::is_solvable(&state) {
+        return Err("the state is unsolvable".into());
+    }
+
+    let mut heuristics = Heuristics::default();
+    let mut search = Search::new(state, heuristic, &mut heuristics)?;
+    let mut screen = Screen::new()?;
+    let mut paused = false;
+
+    // The nodes that are due to be expanded, carried over between frames
+    let mut due = 0.0;
+    let mut last = Instant::now();
+
+    while !util::interrupted() {
+        if !paused {
+            due += last.elapsed().as_secs_f64() * speed;
+            while due >= 1.0 && search.solution.is_none() {
+                search.step();
+                due -= 1.0;
+            }
+        }
+        last = Instant::now();
+
+        screen.draw(&search.view(speed, paused, options)?)?;
+
+        match screen.read_key()? {
+            Some(Key::Escape | Key::Char('q')) => break,
+            Some(Key::Char(' ')) => paused = !paused,
+            Some(Key::Right | Key::Char('l')) => {
+                paused = true;
+                search.step();
+            }
+            Some(Key::Char('+' | '=')) => speed *= 2.0,
+            Some(Key::Char('-')) => speed = (speed / 2.0).max(0.25),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    str::FromStr,
+};
+
+use slidy::{
+    algorithm::{algorithm::Algorithm, direction::Direction, r#move::r#move::Move},
+    puzzle::{puzzle::Puzzle, size::Size, sliding_puzzle::SlidingPuzzle},
+};
+use thiserror::Error;
+
+use crate::{
+    goal,
+    grid::{self, DIRECTIONS},
+    memory, util,
+};
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum WeightedError {
+    #[error("ParseError: failed to parse \"{0}\"")]
+    Parse(String),
+
+    #[error("WeightCount: got {len} weights, expected one for each of the {expected} pieces")]
+    WeightCount { len: usize, expected: u64 },
+
+    #[error("NotApplicable: the algorithm can not be applied to the state")]
+    NotApplicable,
+
+    #[error("Unsolvable: the state is unsolvable")]
+    Unsolvable,
+
+    #[error("OutOfMemory: the search reached the memory limit after {0} states")]
+    OutOfMemory(usize),
+
+    #[error("Interrupted: the search was interrupted")]
+    Interrupted,
+}
+
+/// The cost of moving each piece. By default a piece costs its own number, so e.g. moving the 15
+/// costs 15 times as much as moving the 1.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Weights(Option<Vec<u64>>);
+
+impl FromStr for Weights {
+    type Err = WeightedError;
+
+    /// Parses the weights of the pieces 1, 2, ... in order, separated by commas.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let weights = s
+            .split(',')
+            .map(|w| {
+                w.trim()
+                    .parse::<u64>()
+                    .map_err(|_| WeightedError::Parse(w.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self(Some(weights)))
+    }
+}
+
+impl Weights {
+    /// Checks that there is a weight for every piece of a puzzle of this size.
+    pub fn check(&self, size: Size) -> Result<(), WeightedError> {
+        match &self.0 {
+            Some(weights) if weights.len() as u64 != size.area() - 1 => {
+                Err(WeightedError::WeightCount {
+                    len: weights.len(),
+                    expected: size.area() - 1,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn weight(&self, piece: u64) -> u64 {
+        match &self.0 {
+            Some(weights) => weights[piece as usize - 1],
+            None => piece,
+        }
+    }
+
+    /// The sum of the weight of each piece times its Manhattan distance from its solved
+    /// position. Each move moves one piece by one position, so this is never more than the cost
+    /// of a solution.
+    pub fn manhattan_distance(&self, state: &Puzzle) -> u64 {
+        let size = state.size();
+        let (width, _) = size.into();
+        grid::pieces(state)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, p)| p != 0)
+            .map(|(i, p)| {
+                let pos = (i as u64 % width, i as u64 / width);
+                self.weight(p) * grid::manhattan(pos, goal::solved_pos_xy(p, size))
+            })
+            .sum()
+    }
+}
+
+/// The sum of the weights of the pieces moved when `alg` is applied to `state`.
+pub fn cost(state: &Puzzle, alg: &Algorithm, weights: &Weights) -> Result<u64, WeightedError> {
+    weights.check(state.size())?;
+
+    let mut state = state.clone();
+    let mut cost = 0;
+    for dir in grid::directions(alg) {
+        let pos = grid::moved_piece_pos(state.gap_position_xy(), dir, state.size())
+            .ok_or(WeightedError::NotApplicable)?;
+        cost += weights.weight(state.piece_at_xy(pos));
+        state.try_move_dir(dir);
+    }
+
+    Ok(cost)
+}
+
+/// The cost of `alg` as a solution of the scramble that it solves on a puzzle of this size.
+pub fn solution_cost(alg: &Algorithm, size: Size, weights: &Weights) -> Result<u64, WeightedError> {
+    // The inverse moves the same pieces as `alg`, in the reverse order
+    cost(&goal::solved(size), &alg.inverse(), weights)
+}
+
+/// A solution with the least total cost.
+pub struct Solution {
+    pub alg: Algorithm,
+    pub cost: u64,
+    /// The number of states that were expanded.
+    pub nodes: usize,
+}
+
+/// Finds a solution of `state` with the least total cost using A*, with
+/// [`Weights::manhattan_distance`] as the heuristic. Every state that is reached is kept in
+/// memory, so this is only practical for small puzzles or short solutions.
+pub fn solve(state: &Puzzle, weights: &Weights) -> Result<Solution, WeightedError> {
+    let size = state.size();
+    weights.check(size)?;
+    if !goal::is_solvable(state) {
+        return Err(WeightedError::Unsolvable);
+    }
+
+    // The cost of the cheapest path found to each state, and the last move of that path
+    let mut reached: HashMap<String, (u64, Option<Direction>)> = HashMap::new();
+    let max_states = memory::max_entries(2 * size.area() + 64);
+    let mut queue = BinaryHeap::new();
+
+    reached.insert(state.to_string(), (0, None));
+    queue.push(Reverse((
+        weights.manhattan_distance(state),
+        0,
+        state.to_string(),
+    )));
+
+    let mut nodes = 0;
+    while let Some(Reverse((_, g, key))) = queue.pop() {
+        if reached[&key].0 < g {
+            continue;
+        }
+        if util::interrupted() {
+            return Err(WeightedError::Interrupted);
+        }
+
+        let current = Puzzle::from_str(&key).unwrap();
+        if goal::is_solved(&current) {
+            return Ok(Solution {
+                alg: path(current, &reached),
+                cost: g,
+                nodes,
+            });
+        }
+        nodes += 1;
+
+        for dir in DIRECTIONS {
+            let Some(pos) = grid::moved_piece_pos(current.gap_position_xy(), dir, size) else {
+                continue;
+            };
+            let cost = g + weights.weight(current.piece_at_xy(pos));
+            let mut next = current.clone();
+            next.try_move_dir(dir);
+
+            let next_key = next.to_string();
+            if reached.get(&next_key).is_some_and(|&(c, _)| c <= cost) {
+                continue;
+            }
+            if reached.len() >= max_states {
+                return Err(WeightedError::OutOfMemory(reached.len()));
+            }
+            reached.insert(next_key.clone(), (cost, Some(dir)));
+            queue.push(Reverse((
+                cost + weights.manhattan_distance(&next),
+                cost,
+                next_key,
+            )));
+        }
+    }
+
+    Err(WeightedError::Unsolvable)
+}
+
+/// The moves from the start of the search to `state`, found by undoing the last move of the
+/// cheapest path to each state until the start is reached.
+fn path(mut state: Puzzle, reached: &HashMap<String, (u64, Option<Direction>)>) -> Algorithm {
+    let mut path = Vec::new();
+    while let Some(dir) = reached[&state.to_string()].1 {
+        state.try_move_dir(grid::opposite(dir));
+        path.push(dir);
+    }
+
+    let moves = path.into_iter().rev().map(|d| Move::new(d, 1)).collect();
+    let mut alg = Algorithm::with_moves(moves);
+    alg.simplify();
+    alg
+}